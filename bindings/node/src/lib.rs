@@ -0,0 +1,58 @@
+//! Node.js bindings for shellfirm, exposing the full native pipeline
+//! (including environment access for check filters) to Electron-based
+//! terminals and VS Code extensions that can load native modules.
+
+use napi::{Error, Result, Status};
+use napi_derive::napi;
+use shellfirm::{environment::SystemEnvironment, Config, Decision, Guardian};
+
+/// Assessment of a single command, returned to JavaScript.
+#[napi(object)]
+pub struct JsAssessment {
+    /// The command that was assessed.
+    pub command: String,
+    /// Ids of all checks that matched the command.
+    pub match_ids: Vec<String>,
+    /// Descriptions of the matched checks.
+    pub descriptions: Vec<String>,
+    /// The decision: "allow", "challenge" or "deny".
+    pub decision: String,
+}
+
+fn to_napi_err(err: anyhow::Error) -> Error {
+    Error::new(Status::GenericFailure, err.to_string())
+}
+
+/// Assess the given command against the user's shellfirm configuration,
+/// running check filters against the real environment. Never prompts.
+#[napi]
+pub fn assess_command(command: String) -> Result<JsAssessment> {
+    let config = Config::new(None).map_err(to_napi_err)?;
+    let guardian = Guardian::new(&config).map_err(to_napi_err)?;
+    let assessment = guardian.assess(&command, &SystemEnvironment::default());
+
+    Ok(JsAssessment {
+        command: assessment.command,
+        match_ids: assessment
+            .matches
+            .iter()
+            .map(|c| c.id.to_string())
+            .collect(),
+        descriptions: assessment
+            .matches
+            .iter()
+            .map(|c| c.description.to_string())
+            .collect(),
+        decision: match assessment.decision {
+            Decision::Allow => "allow".to_string(),
+            Decision::Challenge => "challenge".to_string(),
+            Decision::Deny => "deny".to_string(),
+        },
+    })
+}
+
+/// Return true when the given command matches at least one active check.
+#[napi]
+pub fn is_risky_command(command: String) -> Result<bool> {
+    Ok(assess_command(command)?.decision != "allow")
+}
@@ -0,0 +1,107 @@
+"""Jupyter/IPython guard for shellfirm.
+
+Intercepts shell escapes (`!command`, `%%bash` / `%%sh` / `%%script` cells)
+before they run and checks them with the `shellfirm` binary. When a risky
+pattern is found the user gets a confirmation prompt inside the notebook,
+kinda like the terminal challenge.
+
+Install for every IPython/Jupyter session:
+
+    python shellfirm_jupyter.py install
+
+Or load manually inside a notebook:
+
+    %load_ext shellfirm_jupyter
+"""
+
+import os
+import shutil
+import subprocess
+import sys
+
+CANCEL_MESSAGE = "shellfirm: command cancelled"
+
+
+def _shellfirm_matches(command):
+    """Return the matched checks output for the given command, or None."""
+    binary = shutil.which("shellfirm")
+    if binary is None:
+        return None
+    try:
+        result = subprocess.run(
+            [binary, "pre-command", "--test", "--command", command],
+            capture_output=True,
+            text=True,
+            timeout=10,
+        )
+    except (OSError, subprocess.TimeoutExpired):
+        return None
+    output = result.stdout.strip()
+    # `pre-command --test` prints `[]` / `---\n[]` when nothing matched
+    if result.returncode != 0 or output.rstrip("-\n ") in ("", "[]"):
+        return None
+    return output
+
+
+def _confirm(command, matches):
+    sys.stderr.write("#######################\n")
+    sys.stderr.write("# RISKY COMMAND FOUND #\n")
+    sys.stderr.write("#######################\n")
+    sys.stderr.write(matches + "\n")
+    answer = input("Type `yes` to run `{}`: ".format(command))
+    return answer.strip().lower() == "yes"
+
+
+def _extract_shell_commands(lines):
+    """Return the shell command lines of the cell, or an empty list."""
+    if not lines:
+        return []
+    first = lines[0].strip()
+    if first.startswith(("%%bash", "%%sh", "%%script")):
+        return [line.rstrip("\n") for line in lines[1:] if line.strip()]
+    return [
+        line.strip().lstrip("!")
+        for line in lines
+        if line.strip().startswith("!")
+    ]
+
+
+def _transform(lines):
+    for command in _extract_shell_commands(lines):
+        matches = _shellfirm_matches(command)
+        if matches is not None and not _confirm(command, matches):
+            return ["raise KeyboardInterrupt({!r})\n".format(CANCEL_MESSAGE)]
+    return lines
+
+
+def load_ipython_extension(ipython):
+    if _transform not in ipython.input_transformers_cleanup:
+        ipython.input_transformers_cleanup.append(_transform)
+
+
+def unload_ipython_extension(ipython):
+    if _transform in ipython.input_transformers_cleanup:
+        ipython.input_transformers_cleanup.remove(_transform)
+
+
+def install():
+    """Copy this shim into the default IPython startup folder."""
+    startup = os.path.expanduser("~/.ipython/profile_default/startup")
+    os.makedirs(startup, exist_ok=True)
+    shutil.copy(__file__, os.path.join(startup, "shellfirm_jupyter.py"))
+    with open(os.path.join(startup, "00-load-shellfirm.py"), "w") as f:
+        f.write(
+            "import os\n"
+            "import sys\n"
+            "sys.path.insert(0, r'{}')\n"
+            "import shellfirm_jupyter\n"
+            "shellfirm_jupyter.load_ipython_extension(get_ipython())\n".format(startup)
+        )
+    print("shellfirm jupyter guard installed to {}".format(startup))
+
+
+if __name__ == "__main__":
+    if len(sys.argv) > 1 and sys.argv[1] == "install":
+        install()
+    else:
+        print(__doc__)
@@ -13,6 +13,11 @@ if test $status != 0
     echo "`shellfirm` binary is missing. see installation guide: https://github.com/kaplanelad/shellfirm#installation."
 end
 
+# session variables used by `shellfirm status` to detect a loaded but not
+# firing hook
+set -x SHELLFIRM_HOOK "1"
+set -x SHELLFIRM_SHELL_PID "$fish_pid"
+
 function pre_exec
     stty sane 
     set -l cmd (commandline)
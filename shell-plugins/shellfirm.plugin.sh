@@ -14,6 +14,11 @@ if [ "$?" != 0 ]; then
     return
 fi
 
+# session variables used by `shellfirm status` to detect a loaded but not
+# firing hook
+export SHELLFIRM_HOOK="1"
+export SHELLFIRM_SHELL_PID="$$"
+
 function preexec () {
     if [[ "${1}" == *"shellfirm pre-command"* ]]; then
         return
@@ -0,0 +1,29 @@
+/* C bindings for the shellfirm validation engine.
+ *
+ * Build the library with the `ffi` feature to get these symbols:
+ *   cargo build --release --features ffi
+ */
+
+#ifndef SHELLFIRM_H
+#define SHELLFIRM_H
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Validate the given null-terminated command and return the assessment
+ * serialized to YAML (command, matched checks and decision), or NULL when
+ * the command could not be validated.
+ *
+ * The returned string must be released with shellfirm_free_result. */
+char *shellfirm_validate_command(const char *command);
+
+/* Release a string returned from shellfirm_validate_command.
+ * Passing NULL is a no-op. */
+void shellfirm_free_result(char *result);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* SHELLFIRM_H */
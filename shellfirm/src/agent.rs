@@ -0,0 +1,474 @@
+//! Programmatic risk assessment for agent frameworks (LLM tool runners, CI
+//! bots) embedding shellfirm. Unlike the interactive pipeline there is no
+//! challenge prompt: the agent policy decides between allowing, requiring a
+//! human in the loop, and denying outright.
+
+use serde_derive::{Deserialize, Serialize};
+
+use crate::{
+    checks::{run_check_on_command_with_environment, Check, Severity},
+    command,
+    environment::Environment,
+};
+
+/// Agent policy: when to auto-deny and when to require human approval.
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct AgentConfig {
+    /// Global severity at (or above) which matched commands are denied
+    /// automatically. `None` never auto-denies.
+    #[serde(default)]
+    pub auto_deny_severity: Option<Severity>,
+    /// Per check group / context label overrides. The first rule matching a
+    /// check wins over the global threshold.
+    #[serde(default)]
+    pub rules: Vec<AgentRule>,
+    /// When a compound command is denied, offer the command with the denied
+    /// segments removed as the `auto_rewrite`, so agent frameworks can run
+    /// the safe remainder instead of retrying blindly.
+    #[serde(default)]
+    pub strip_denied_segments: bool,
+}
+
+/// A scoped agent policy rule.
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct AgentRule {
+    /// Check group the rule applies to. `None` applies to every group.
+    #[serde(default)]
+    pub group: Option<String>,
+    /// Context label (e.g. `production`) the rule applies to. `None` applies
+    /// to every context.
+    #[serde(default)]
+    pub context_label: Option<String>,
+    /// Severity at (or above) which the rule denies, overriding the global
+    /// threshold.
+    #[serde(default)]
+    pub auto_deny_severity: Option<Severity>,
+    /// Force a human in the loop for any match of this rule, regardless of
+    /// severity.
+    #[serde(default)]
+    pub require_human_approval: bool,
+}
+
+impl AgentRule {
+    /// Check if the rule applies to the given check and context labels.
+    fn applies_to(&self, check: &Check, context_labels: &[String]) -> bool {
+        if let Some(group) = &self.group {
+            if group != &check.from {
+                return false;
+            }
+        }
+        if let Some(context_label) = &self.context_label {
+            if !context_labels.contains(context_label) {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Human readable scope of the rule, used in `denial_reason`.
+    fn describe(&self) -> String {
+        match (&self.group, &self.context_label) {
+            (Some(group), Some(context)) => {
+                format!("rule for group `{group}` in context `{context}`")
+            }
+            (Some(group), None) => format!("rule for group `{group}`"),
+            (None, Some(context)) => format!("rule for context `{context}`"),
+            (None, None) => "catch-all rule".to_string(),
+        }
+    }
+}
+
+/// Deterministic safe rewrites per check id. An empty `find` appends the
+/// replacement to the command instead of substituting.
+const REWRITES: &[(&str, &str, &str)] = &[
+    ("terraform:apply_with_auto_approve", " -auto-approve", ""),
+    ("kubernetes:delete_namespace", "", " --dry-run=client"),
+];
+
+/// Return a safe rewritten variant of the command when a deterministic
+/// rewrite exists for one of the matched checks, so agent frameworks can
+/// retry the safe variant without an LLM call.
+///
+/// # Arguments
+///
+/// * `command` - the command that was assessed.
+/// * `matches` - checks that matched the command.
+#[must_use]
+pub fn auto_rewrite(command: &str, matches: &[Check]) -> Option<String> {
+    for check in matches {
+        // force pushes always have a lease-protected variant
+        if check.from == "git"
+            && command.contains("--force")
+            && !command.contains("--force-with-lease")
+        {
+            return Some(command.replace("--force", "--force-with-lease"));
+        }
+
+        if let Some((_, find, replace)) = REWRITES.iter().find(|(id, _, _)| id == &check.id) {
+            if find.is_empty() {
+                if !command.contains(replace.trim()) {
+                    return Some(format!("{command}{replace}"));
+                }
+            } else if command.contains(find) {
+                return Some(command.replace(find, replace));
+            }
+        }
+    }
+    None
+}
+
+/// What the agent framework should do with the command.
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+pub enum AgentDecision {
+    /// No check matched, the command can run.
+    Allow,
+    /// At least one check matched, a human should approve the command.
+    RequireHumanApproval,
+    /// The policy denies the command, it must not run.
+    Deny,
+}
+
+/// Result of assessing one command against the agent policy.
+#[derive(Debug, Serialize, Clone)]
+pub struct RiskAssessment {
+    /// The assessed command.
+    pub command: String,
+    /// Ids of all checks that matched the command.
+    pub match_ids: Vec<String>,
+    /// Highest severity across the matched checks.
+    pub severity: Option<Severity>,
+    /// What the agent framework should do.
+    pub decision: AgentDecision,
+    /// Which policy rule drove a deny / require-approval decision.
+    pub denial_reason: Option<String>,
+    /// Safe rewritten command when a deterministic rewrite exists (e.g.
+    /// `--force-with-lease` instead of `--force`).
+    pub auto_rewrite: Option<String>,
+}
+
+/// Assess the given command against the agent policy. Never prompts.
+///
+/// # Arguments
+///
+/// * `agent_config` - the agent policy.
+/// * `checks` - active checks to match against.
+/// * `command` - command to assess.
+/// * `context_labels` - labels describing where the command is going to run
+///   (e.g. `production`), matched against scoped rules.
+/// * `environment` - environment the command is going to run in.
+#[must_use]
+pub fn assess_command(
+    agent_config: &AgentConfig,
+    checks: &[Check],
+    command: &str,
+    context_labels: &[String],
+    environment: &dyn Environment,
+) -> RiskAssessment {
+    let matches: Vec<Check> = command::parse_and_split_command(command)
+        .iter()
+        .flat_map(|c| run_check_on_command_with_environment(checks, c, environment))
+        .collect();
+
+    let severity = matches.iter().map(|c| c.severity).max();
+
+    let mut decision = if matches.is_empty() {
+        AgentDecision::Allow
+    } else {
+        AgentDecision::RequireHumanApproval
+    };
+    let mut denial_reason = None;
+    let mut denied: Vec<&Check> = Vec::new();
+
+    for check in &matches {
+        let rule = agent_config
+            .rules
+            .iter()
+            .find(|rule| rule.applies_to(check, context_labels));
+
+        let threshold = rule
+            .and_then(|rule| rule.auto_deny_severity)
+            .or(agent_config.auto_deny_severity);
+
+        if let Some(threshold) = threshold {
+            if check.severity >= threshold {
+                decision = AgentDecision::Deny;
+                denied.push(check);
+                if denial_reason.is_none() {
+                    denial_reason = Some(format!(
+                        "check `{}` severity {:?} crossed the {:?} auto-deny threshold ({})",
+                        check.id,
+                        check.severity,
+                        threshold,
+                        rule.map_or_else(|| "global policy".to_string(), AgentRule::describe)
+                    ));
+                }
+                continue;
+            }
+        }
+
+        if denial_reason.is_none() {
+            if let Some(rule) = rule {
+                if rule.require_human_approval {
+                    denial_reason = Some(format!(
+                        "check `{}` requires human approval ({})",
+                        check.id,
+                        rule.describe()
+                    ));
+                }
+            }
+        }
+    }
+
+    // a denied compound command can still carry safe segments: offer the
+    // remainder as the rewrite when the policy opted in
+    let auto_rewrite = if agent_config.strip_denied_segments && decision == AgentDecision::Deny {
+        strip_denied_segments(command, &denied).or_else(|| auto_rewrite(command, &matches))
+    } else {
+        auto_rewrite(command, &matches)
+    };
+
+    RiskAssessment {
+        auto_rewrite,
+        command: command.to_string(),
+        match_ids: matches.iter().map(|c| c.id.to_string()).collect(),
+        severity,
+        decision,
+        denial_reason,
+    }
+}
+
+/// Rewrite a `&&`-compound command with the denied segments removed. `None`
+/// when the command is not `&&`-composed, when every segment is denied, or
+/// when no segment would be removed — stripping across `;` or `|` would
+/// change semantics, so only `&&` chains are rewritten.
+///
+/// # Arguments
+///
+/// * `command` - the denied command line.
+/// * `denied` - checks that drove the denial.
+fn strip_denied_segments(command: &str, denied: &[&Check]) -> Option<String> {
+    if !command.contains("&&") || command.contains(';') || command.contains('|') {
+        return None;
+    }
+    let segments: Vec<&str> = command.split("&&").map(str::trim).collect();
+    let kept: Vec<&str> = segments
+        .iter()
+        .filter(|segment| !denied.iter().any(|check| check.test.is_match(segment)))
+        .copied()
+        .collect();
+    if kept.is_empty() || kept.len() == segments.len() {
+        return None;
+    }
+    Some(kept.join(" && "))
+}
+
+#[cfg(test)]
+mod test_agent {
+    use insta::assert_debug_snapshot;
+
+    use super::*;
+    use crate::environment::MockEnvironment;
+
+    const CHECKS: &str = r"
+- id: git:force_push
+  test: git push.+--force
+  description: force push
+  from: git
+  severity: High
+- id: fs:recursively_delete
+  test: rm.+-r
+  description: recursive delete
+  from: fs
+  severity: Critical
+";
+
+    fn get_checks() -> Vec<Check> {
+        serde_yaml::from_str(CHECKS).unwrap()
+    }
+
+    #[test]
+    fn can_allow_unmatched_command() {
+        let assessment = assess_command(
+            &AgentConfig::default(),
+            &get_checks(),
+            "git status",
+            &[],
+            &MockEnvironment::default(),
+        );
+        assert_debug_snapshot!(assessment);
+    }
+
+    #[test]
+    fn can_auto_rewrite_force_push() {
+        let assessment = assess_command(
+            &AgentConfig::default(),
+            &get_checks(),
+            "git push origin main --force",
+            &[],
+            &MockEnvironment::default(),
+        );
+        assert_debug_snapshot!(assessment.auto_rewrite);
+    }
+
+    #[test]
+    fn can_auto_rewrite_from_rewrite_table() {
+        let checks: Vec<Check> = serde_yaml::from_str(
+            r"
+- id: kubernetes:delete_namespace
+  test: kubectl delete namespace
+  description: deletes a namespace
+  from: kubernetes
+",
+        )
+        .unwrap();
+        let assessment = assess_command(
+            &AgentConfig::default(),
+            &checks,
+            "kubectl delete namespace staging",
+            &[],
+            &MockEnvironment::default(),
+        );
+        assert_debug_snapshot!(assessment.auto_rewrite);
+    }
+
+    #[test]
+    fn no_auto_rewrite_without_deterministic_variant() {
+        let assessment = assess_command(
+            &AgentConfig::default(),
+            &get_checks(),
+            "rm -rf /",
+            &[],
+            &MockEnvironment::default(),
+        );
+        assert_debug_snapshot!(assessment.auto_rewrite);
+    }
+
+    #[test]
+    fn matched_command_requires_human_approval() {
+        let assessment = assess_command(
+            &AgentConfig::default(),
+            &get_checks(),
+            "git push origin main --force",
+            &[],
+            &MockEnvironment::default(),
+        );
+        assert_debug_snapshot!((assessment.decision, assessment.denial_reason));
+    }
+
+    #[test]
+    fn global_threshold_denies_critical_commands() {
+        let agent_config = AgentConfig {
+            auto_deny_severity: Some(Severity::Critical),
+            strip_denied_segments: false,
+            rules: vec![],
+        };
+        let assessment = assess_command(
+            &agent_config,
+            &get_checks(),
+            "rm -rf /",
+            &[],
+            &MockEnvironment::default(),
+        );
+        assert_debug_snapshot!((assessment.decision, assessment.denial_reason));
+    }
+
+    #[test]
+    fn can_strip_denied_segments_from_compound_commands() {
+        let agent_config = AgentConfig {
+            auto_deny_severity: Some(Severity::Critical),
+            strip_denied_segments: true,
+            rules: vec![],
+        };
+        let assessment = assess_command(
+            &agent_config,
+            &get_checks(),
+            "rm -rf / && cargo build",
+            &[],
+            &MockEnvironment::default(),
+        );
+        assert_debug_snapshot!((assessment.decision, assessment.auto_rewrite));
+
+        // every segment is denied: nothing safe remains to offer
+        let assessment = assess_command(
+            &agent_config,
+            &get_checks(),
+            "rm -rf /",
+            &[],
+            &MockEnvironment::default(),
+        );
+        assert_debug_snapshot!(assessment.auto_rewrite);
+    }
+
+    #[test]
+    fn group_rule_overrides_global_threshold() {
+        let agent_config = AgentConfig {
+            auto_deny_severity: None,
+            strip_denied_segments: false,
+            rules: vec![AgentRule {
+                group: Some("git".to_string()),
+                context_label: None,
+                auto_deny_severity: Some(Severity::High),
+                require_human_approval: false,
+            }],
+        };
+        let assessment = assess_command(
+            &agent_config,
+            &get_checks(),
+            "git push origin main --force",
+            &[],
+            &MockEnvironment::default(),
+        );
+        assert_debug_snapshot!((assessment.decision, assessment.denial_reason));
+    }
+
+    #[test]
+    fn context_rule_applies_only_with_matching_label() {
+        let agent_config = AgentConfig {
+            auto_deny_severity: None,
+            strip_denied_segments: false,
+            rules: vec![AgentRule {
+                group: None,
+                context_label: Some("production".to_string()),
+                auto_deny_severity: Some(Severity::High),
+                require_human_approval: false,
+            }],
+        };
+        let in_production = assess_command(
+            &agent_config,
+            &get_checks(),
+            "git push origin main --force",
+            &["production".to_string()],
+            &MockEnvironment::default(),
+        );
+        let in_dev = assess_command(
+            &agent_config,
+            &get_checks(),
+            "git push origin main --force",
+            &[],
+            &MockEnvironment::default(),
+        );
+        assert_debug_snapshot!((in_production.decision, in_dev.decision));
+    }
+
+    #[test]
+    fn rule_can_force_human_approval() {
+        let agent_config = AgentConfig {
+            auto_deny_severity: None,
+            strip_denied_segments: false,
+            rules: vec![AgentRule {
+                group: Some("git".to_string()),
+                context_label: None,
+                auto_deny_severity: None,
+                require_human_approval: true,
+            }],
+        };
+        let assessment = assess_command(
+            &agent_config,
+            &get_checks(),
+            "git push origin main --force",
+            &[],
+            &MockEnvironment::default(),
+        );
+        assert_debug_snapshot!((assessment.decision, assessment.denial_reason));
+    }
+}
@@ -0,0 +1,713 @@
+//! Append-only audit log of intercepted commands, one JSON record per line.
+//!
+//! # Schema compatibility guarantee
+//!
+//! Every record carries an explicit `schema_version`. Writers always emit
+//! [`CURRENT_SCHEMA_VERSION`]; the reader upgrades older records on the fly,
+//! so a log written by any past release stays readable by newer tooling.
+//! Within a version fields are only ever added — an existing field never
+//! changes meaning.
+
+use std::{
+    fs,
+    io::Write,
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{bail, Context, Result};
+use serde_derive::{Deserialize, Serialize};
+
+use crate::{config::LastCommand, guardian::Decision};
+
+/// File name of the audit log, inside the config folder.
+pub const AUDIT_FILE_NAME: &str = "audit.jsonl";
+
+/// Schema version written by this release.
+///
+/// Version history:
+/// * 1 (implicit, records without a `schema_version` field): a single
+///   `check_id` string and no `decision` — every record was a challenge.
+/// * 2: explicit `schema_version`, a `match_ids` list and a `decision`.
+/// * 3: adds `outcome`; old records upgrade to `Intercepted`.
+/// * 4: adds `identity` (who typed, on shared accounts); old records upgrade
+///   to no identity.
+/// * 5: adds `justification` (break-glass); old records upgrade to no
+///   justification.
+/// * 6: adds `source` (command provenance); old records upgrade to no
+///   source.
+/// * 7: adds `tags` (labels of the matched checks, for compliance mapping);
+///   old records upgrade to no tags.
+pub const CURRENT_SCHEMA_VERSION: u32 = 7;
+
+/// Window after an interception in which a passing, modified version of the
+/// same command is recorded as [`Outcome::Edited`].
+pub const EDITED_WINDOW_SECONDS: u64 = 60;
+
+/// What ultimately happened after an interception.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    /// The command was intercepted; what happened next is unknown.
+    Intercepted,
+    /// A modified version of a recently challenged command passed, i.e. the
+    /// warning led to a safer rewrite.
+    Edited,
+    /// The command was denied, but the user broke the glass with a
+    /// justification and it was allowed to run once.
+    BrokeGlass,
+}
+
+/// One intercepted command, as stored in the audit log.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct AuditEvent {
+    /// Schema version of this record.
+    pub schema_version: u32,
+    /// When the command was intercepted (seconds since epoch).
+    pub timestamp: u64,
+    /// The intercepted command.
+    pub command: String,
+    /// Ids of the checks that matched it.
+    pub match_ids: Vec<String>,
+    /// The decision the command got.
+    pub decision: Decision,
+    /// What happened after the interception.
+    pub outcome: Outcome,
+    /// Who was really typing, when it can be told apart on a shared account
+    /// (see [`crate::context::detect_identity`]).
+    pub identity: Option<String>,
+    /// The justification the user gave when breaking the glass (only on
+    /// [`Outcome::BrokeGlass`] records).
+    pub justification: Option<String>,
+    /// Where the command came from (`interactive`, `script`, `ci`,
+    /// `agent:<name>`), when the hook reported it.
+    pub source: Option<String>,
+    /// Tags of the checks that matched, de-duplicated, so exports can be
+    /// sliced by label or compliance mapping (e.g. `irreversible`,
+    /// `cis-1.2.3`).
+    pub tags: Vec<String>,
+}
+
+impl AuditEvent {
+    /// Create an event for the current moment, at the current schema version.
+    #[must_use]
+    pub fn new(command: &str, match_ids: Vec<String>, decision: Decision) -> Self {
+        Self {
+            schema_version: CURRENT_SCHEMA_VERSION,
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs()),
+            command: command.to_string(),
+            match_ids,
+            decision,
+            outcome: Outcome::Intercepted,
+            identity: None,
+            justification: None,
+            source: None,
+            tags: vec![],
+        }
+    }
+}
+
+/// Is the given passing command a modified version of the last intercepted
+/// one: same program, different command line, within
+/// [`EDITED_WINDOW_SECONDS`].
+#[must_use]
+pub fn is_edited_followup(last: &LastCommand, command: &str, now: u64) -> bool {
+    if now.saturating_sub(last.intercepted_at) > EDITED_WINDOW_SECONDS {
+        return false;
+    }
+    if last.command.trim() == command.trim() {
+        return false;
+    }
+    match (
+        last.command.split_whitespace().next(),
+        command.split_whitespace().next(),
+    ) {
+        (Some(previous), Some(current)) => previous == current,
+        _ => false,
+    }
+}
+
+/// Append one event to the audit log.
+///
+/// # Errors
+///
+/// Will return `Err` when the log file could not be written.
+pub fn append(path: &Path, event: &AuditEvent) -> Result<()> {
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+    writeln!(file, "{}", serde_json::to_string(event)?)?;
+    Ok(())
+}
+
+/// Hard timeout for posting a break-glass event to the review webhook.
+const WEBHOOK_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Post a break-glass event to the configured review webhook, as a `POST` of
+/// the single audit record.
+///
+/// # Errors
+///
+/// Will return `Err` when the network is disabled or the webhook did not
+/// acknowledge the event.
+pub fn notify_break_glass(
+    webhook: &str,
+    event: &AuditEvent,
+    network: crate::network::NetworkMode,
+) -> Result<()> {
+    crate::network::ensure_allowed(network, "break-glass webhook")?;
+    let body_file =
+        std::env::temp_dir().join(format!("shellfirm-break-glass-{}.json", std::process::id()));
+    fs::write(&body_file, serde_json::to_string(event)?)?;
+    // no shell runs the post: the argument vector reaches curl untouched,
+    // so the content-type header survives with its whitespace
+    let body_argument = format!("@{}", body_file.display());
+    let result = crate::environment::run_args_with_timeout(
+        "curl",
+        &[
+            "-fsS",
+            "-X",
+            "POST",
+            "-H",
+            "Content-Type: application/json",
+            "--data-binary",
+            &body_argument,
+            webhook,
+        ],
+        WEBHOOK_TIMEOUT,
+    );
+    let _ = fs::remove_file(&body_file);
+    result
+        .map(|_| ())
+        .with_context(|| format!("break-glass webhook {webhook} did not acknowledge the event"))
+}
+
+/// Read all events from the audit log, upgrading old records on the fly. A
+/// missing log is an empty log.
+///
+/// # Errors
+///
+/// Will return `Err` when the file could not be read or a record could not
+/// be parsed or upgraded.
+pub fn read_events(path: &Path) -> Result<Vec<AuditEvent>> {
+    if !path.exists() {
+        return Ok(vec![]);
+    }
+    fs::read_to_string(path)?
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            let record = serde_json::from_str(line)
+                .with_context(|| format!("could not parse audit record: {line}"))?;
+            upgrade_record(record)
+        })
+        .collect()
+}
+
+/// Upgrade one raw record to [`CURRENT_SCHEMA_VERSION`], one version step at
+/// a time.
+fn upgrade_record(mut record: serde_json::Value) -> Result<AuditEvent> {
+    let version = record
+        .get("schema_version")
+        .and_then(serde_json::Value::as_u64)
+        .unwrap_or(1);
+    if version > u64::from(CURRENT_SCHEMA_VERSION) {
+        bail!("unknown audit schema version {version}, upgrade shellfirm to read this log");
+    }
+
+    let object = record
+        .as_object_mut()
+        .context("audit record is not an object")?;
+    if version < 2 {
+        if let Some(check_id) = object.remove("check_id") {
+            object.insert("match_ids".to_string(), serde_json::json!([check_id]));
+        }
+        object
+            .entry("decision")
+            .or_insert_with(|| serde_json::json!("Challenge"));
+    }
+    if version < 3 {
+        object
+            .entry("outcome")
+            .or_insert_with(|| serde_json::json!("Intercepted"));
+    }
+    if version < 4 {
+        object
+            .entry("identity")
+            .or_insert_with(|| serde_json::Value::Null);
+    }
+    if version < 5 {
+        object
+            .entry("justification")
+            .or_insert_with(|| serde_json::Value::Null);
+    }
+    if version < 6 {
+        object
+            .entry("source")
+            .or_insert_with(|| serde_json::Value::Null);
+    }
+    if version < 7 {
+        object
+            .entry("tags")
+            .or_insert_with(|| serde_json::json!([]));
+    }
+    object.insert(
+        "schema_version".to_string(),
+        serde_json::json!(CURRENT_SCHEMA_VERSION),
+    );
+    Ok(serde_json::from_value(record)?)
+}
+
+/// Optional SQLite sink for large histories, enabled with the
+/// `audit-sqlite` feature. Records are stored as raw JSON next to an indexed
+/// timestamp, so reads go through the same schema upgrade path as the JSONL
+/// log and SQLite provides the locking for concurrent writers.
+#[cfg(feature = "audit-sqlite")]
+pub mod sqlite {
+    use std::path::Path;
+
+    use anyhow::{Context, Result};
+    use rusqlite::Connection;
+
+    use super::{upgrade_record, AuditEvent};
+
+    /// File name of the SQLite audit database, inside the config folder.
+    pub const SQLITE_FILE_NAME: &str = "audit.sqlite";
+
+    fn open(path: &Path) -> Result<Connection> {
+        let connection = Connection::open(path)?;
+        connection.execute_batch(
+            "CREATE TABLE IF NOT EXISTS audit_events (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                timestamp INTEGER NOT NULL,
+                record TEXT NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_audit_events_timestamp
+                ON audit_events (timestamp);",
+        )?;
+        Ok(connection)
+    }
+
+    /// Append one event to the SQLite audit database.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` when the database could not be opened or written.
+    pub fn append(path: &Path, event: &AuditEvent) -> Result<()> {
+        open(path)?.execute(
+            "INSERT INTO audit_events (timestamp, record) VALUES (?1, ?2)",
+            (
+                i64::try_from(event.timestamp).unwrap_or(i64::MAX),
+                serde_json::to_string(event)?,
+            ),
+        )?;
+        Ok(())
+    }
+
+    /// Read all events in insertion order, upgrading old records on the fly.
+    /// A missing database is an empty log.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` when the database could not be read or a record
+    /// could not be upgraded.
+    pub fn read_events(path: &Path) -> Result<Vec<AuditEvent>> {
+        if !path.exists() {
+            return Ok(vec![]);
+        }
+        let connection = open(path)?;
+        let mut statement = connection.prepare("SELECT record FROM audit_events ORDER BY id")?;
+        let records = statement
+            .query_map([], |row| row.get::<_, String>(0))?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        records
+            .iter()
+            .map(|record| {
+                let value = serde_json::from_str(record)
+                    .with_context(|| format!("could not parse audit record: {record}"))?;
+                upgrade_record(value)
+            })
+            .collect()
+    }
+
+    /// Copy an existing JSONL audit log into the SQLite database, upgrading
+    /// records on the way. Returns the number of migrated events.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` when the log could not be read or the database
+    /// could not be written.
+    pub fn migrate_from_jsonl(jsonl_path: &Path, sqlite_path: &Path) -> Result<usize> {
+        let events = super::read_events(jsonl_path)?;
+        let mut connection = open(sqlite_path)?;
+        let transaction = connection.transaction()?;
+        for event in &events {
+            transaction.execute(
+                "INSERT INTO audit_events (timestamp, record) VALUES (?1, ?2)",
+                (
+                    i64::try_from(event.timestamp).unwrap_or(i64::MAX),
+                    serde_json::to_string(event)?,
+                ),
+            )?;
+        }
+        transaction.commit()?;
+        Ok(events.len())
+    }
+}
+
+/// Client for a central audit collector, so organizations can collect events
+/// from all machines into one place without a full SIEM setup.
+///
+/// # Protocol
+///
+/// Events are ingested with a `POST` to the configured URL carrying
+/// `Content-Type: application/json` and, when a token is configured,
+/// `Authorization: Bearer <token>`. The body is one JSON object:
+/// `{"events": [<event>, ...]}` at [`super::CURRENT_SCHEMA_VERSION`]. Any
+/// 2xx response acknowledges the whole batch; anything else leaves the batch
+/// spooled on disk for a later flush, so offline machines lose nothing.
+pub mod remote {
+    use std::path::Path;
+
+    use anyhow::{Context, Result};
+    use serde_derive::{Deserialize, Serialize};
+
+    use super::AuditEvent;
+    use crate::{
+        environment::run_args_with_timeout,
+        network::{self, NetworkMode},
+    };
+
+    /// File name of the offline spool, inside the config folder.
+    pub const SPOOL_FILE_NAME: &str = "audit-spool.jsonl";
+
+    /// Hard timeout for uploading one batch.
+    const UPLOAD_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+    /// Base delay of the exponential backoff between upload attempts.
+    const BACKOFF_BASE_MS: u64 = 500;
+
+    const fn default_batch_size() -> usize {
+        50
+    }
+
+    const fn default_max_retries() -> u32 {
+        3
+    }
+
+    /// Remote collector settings (`audit.remote` in the settings file).
+    #[derive(Debug, Deserialize, Serialize, Clone)]
+    pub struct RemoteAudit {
+        /// Ingestion endpoint, e.g. `https://audit.example.com/v1/events`.
+        pub url: String,
+        /// Name of an environment variable holding the bearer token. The
+        /// token itself never goes into the settings file.
+        #[serde(default)]
+        pub token_env: Option<String>,
+        /// Events per upload request.
+        #[serde(default = "default_batch_size")]
+        pub batch_size: usize,
+        /// Upload attempts per batch, with exponential backoff between them.
+        #[serde(default = "default_max_retries")]
+        pub max_retries: u32,
+    }
+
+    /// Spool one event for a later flush.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` when the spool file could not be written.
+    pub fn spool(path: &Path, event: &AuditEvent) -> Result<()> {
+        super::append(path, event)
+    }
+
+    /// Upload all spooled events in batches, clearing the spool as batches
+    /// are acknowledged. Returns the number of uploaded events; events of a
+    /// failed batch stay spooled.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` when the network is disabled, the spool could not
+    /// be read, or a batch was not acknowledged within the retry budget.
+    pub fn flush(spool_path: &Path, remote: &RemoteAudit, network: NetworkMode) -> Result<usize> {
+        let events = super::read_events(spool_path)?;
+        if events.is_empty() {
+            return Ok(0);
+        }
+        network::ensure_allowed(network, "remote audit collector")?;
+
+        let mut sent = 0;
+        for batch in events.chunks(remote.batch_size.max(1)) {
+            if let Err(err) = send_batch_with_retry(remote, batch) {
+                requeue(spool_path, &events[sent..])?;
+                return Err(err);
+            }
+            sent += batch.len();
+            requeue(spool_path, &events[sent..])?;
+        }
+        Ok(sent)
+    }
+
+    /// Rewrite the spool with the events that are still unsent.
+    fn requeue(spool_path: &Path, events: &[AuditEvent]) -> Result<()> {
+        let lines = events
+            .iter()
+            .map(|event| serde_json::to_string(event).map(|line| line + "\n"))
+            .collect::<std::result::Result<String, _>>()?;
+        std::fs::write(spool_path, lines)?;
+        Ok(())
+    }
+
+    fn send_batch_with_retry(remote: &RemoteAudit, batch: &[AuditEvent]) -> Result<()> {
+        let mut last_error = None;
+        for attempt in 0..remote.max_retries.max(1) {
+            if attempt > 0 {
+                std::thread::sleep(std::time::Duration::from_millis(
+                    BACKOFF_BASE_MS << (attempt - 1),
+                ));
+            }
+            match send_batch(remote, batch) {
+                Ok(()) => return Ok(()),
+                Err(err) => last_error = Some(err),
+            }
+        }
+        Err(last_error.context("no upload attempt was made")?)
+    }
+
+    /// Upload one batch, acknowledged by any 2xx response.
+    fn send_batch(remote: &RemoteAudit, batch: &[AuditEvent]) -> Result<()> {
+        let body = serde_json::to_string(&serde_json::json!({ "events": batch }))?;
+        let body_file =
+            std::env::temp_dir().join(format!("shellfirm-audit-batch-{}.json", std::process::id()));
+        std::fs::write(&body_file, body)?;
+
+        // no shell runs the upload: the argument vector is passed to curl
+        // untouched, so the header may contain whitespace and the token is
+        // read from the configured environment variable in-process
+        let auth_header = remote
+            .token_env
+            .as_ref()
+            .and_then(|env| std::env::var(env).ok())
+            .map(|token| format!("Authorization: Bearer {token}"));
+        let body_argument = format!("@{}", body_file.display());
+        let mut args = vec!["-fsS", "-X", "POST", "-H", "Content-Type: application/json"];
+        if let Some(header) = &auth_header {
+            args.push("-H");
+            args.push(header);
+        }
+        args.extend(["--data-binary", &body_argument, &remote.url]);
+        let result = run_args_with_timeout("curl", &args, UPLOAD_TIMEOUT);
+        let _ = std::fs::remove_file(&body_file);
+        result.map(|_| ()).with_context(|| {
+            format!(
+                "audit collector {} did not acknowledge the batch",
+                remote.url
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod test_audit {
+    use insta::assert_debug_snapshot;
+    use tempdir::TempDir;
+
+    use super::*;
+
+    #[test]
+    fn can_append_and_read_events() {
+        let temp_dir = TempDir::new("audit").unwrap();
+        let path = temp_dir.path().join(AUDIT_FILE_NAME);
+
+        let mut event = AuditEvent::new(
+            "rm -rf /",
+            vec!["fs:recursively_delete".to_string()],
+            Decision::Challenge,
+        );
+        event.timestamp = 1_700_000_000;
+        append(&path, &event).unwrap();
+        event.command = "git push --force".to_string();
+        event.match_ids = vec!["git:force_push".to_string()];
+        event.decision = Decision::Deny;
+        append(&path, &event).unwrap();
+
+        assert_debug_snapshot!(read_events(&path));
+        temp_dir.close().unwrap();
+    }
+
+    #[test]
+    fn upgrades_version_one_records_on_the_fly() {
+        let temp_dir = TempDir::new("audit").unwrap();
+        let path = temp_dir.path().join(AUDIT_FILE_NAME);
+        std::fs::write(
+            &path,
+            r#"{"timestamp":1600000000,"command":"rm -rf /","check_id":"fs:recursively_delete"}"#,
+        )
+        .unwrap();
+
+        assert_debug_snapshot!(read_events(&path));
+        temp_dir.close().unwrap();
+    }
+
+    #[test]
+    fn rejects_records_from_a_newer_schema() {
+        let temp_dir = TempDir::new("audit").unwrap();
+        let path = temp_dir.path().join(AUDIT_FILE_NAME);
+        std::fs::write(&path, r#"{"schema_version":99,"command":"x"}"#).unwrap();
+
+        assert_debug_snapshot!(read_events(&path).unwrap_err().to_string());
+        temp_dir.close().unwrap();
+    }
+
+    #[test]
+    fn can_detect_an_edited_followup() {
+        let last = LastCommand {
+            command: "rm -rf /".to_string(),
+            check_ids: vec!["fs:recursively_delete".to_string()],
+            intercepted_at: 1_000,
+        };
+        // same program, modified, within the window
+        assert_debug_snapshot!(is_edited_followup(&last, "rm -rf ./scratch", 1_030));
+        // identical command is a retry, not a rewrite
+        assert_debug_snapshot!(is_edited_followup(&last, "rm -rf /", 1_030));
+        // different program is unrelated
+        assert_debug_snapshot!(is_edited_followup(&last, "ls -la", 1_030));
+        // too late
+        assert_debug_snapshot!(is_edited_followup(&last, "rm -rf ./scratch", 1_100));
+    }
+
+    #[test]
+    fn upgrades_version_two_records_on_the_fly() {
+        let temp_dir = TempDir::new("audit").unwrap();
+        let path = temp_dir.path().join(AUDIT_FILE_NAME);
+        std::fs::write(
+            &path,
+            r#"{"schema_version":2,"timestamp":1650000000,"command":"rm -rf /","match_ids":["fs:recursively_delete"],"decision":"Deny"}"#,
+        )
+        .unwrap();
+
+        assert_debug_snapshot!(read_events(&path));
+        temp_dir.close().unwrap();
+    }
+
+    #[test]
+    fn break_glass_webhook_honors_the_network_gate() {
+        let mut event = AuditEvent::new(
+            "rm -rf /",
+            vec!["fs:recursively_delete".to_string()],
+            Decision::Deny,
+        );
+        event.outcome = Outcome::BrokeGlass;
+        event.justification = Some("incident #42, restoring the primary".to_string());
+
+        assert_debug_snapshot!(notify_break_glass(
+            "https://review.example.com/hook",
+            &event,
+            crate::network::NetworkMode::Never
+        )
+        .unwrap_err()
+        .to_string());
+    }
+
+    #[test]
+    fn missing_log_reads_as_empty() {
+        let temp_dir = TempDir::new("audit").unwrap();
+        assert_debug_snapshot!(read_events(&temp_dir.path().join(AUDIT_FILE_NAME)));
+        temp_dir.close().unwrap();
+    }
+}
+
+#[cfg(test)]
+mod test_audit_remote {
+    use insta::assert_debug_snapshot;
+    use tempdir::TempDir;
+
+    use super::{remote, AuditEvent, Decision};
+    use crate::network::NetworkMode;
+
+    fn get_remote() -> remote::RemoteAudit {
+        serde_yaml::from_str("url: https://audit.example.com/v1/events").unwrap()
+    }
+
+    #[test]
+    fn can_parse_remote_settings_with_defaults() {
+        assert_debug_snapshot!(get_remote());
+    }
+
+    #[test]
+    fn flush_of_an_empty_spool_sends_nothing() {
+        let temp_dir = TempDir::new("audit").unwrap();
+        let spool_path = temp_dir.path().join(remote::SPOOL_FILE_NAME);
+        assert_debug_snapshot!(remote::flush(
+            &spool_path,
+            &get_remote(),
+            NetworkMode::Never
+        ));
+        temp_dir.close().unwrap();
+    }
+
+    #[test]
+    fn flush_honors_the_network_gate_and_keeps_the_spool() {
+        let temp_dir = TempDir::new("audit").unwrap();
+        let spool_path = temp_dir.path().join(remote::SPOOL_FILE_NAME);
+        let event = AuditEvent::new(
+            "rm -rf /",
+            vec!["fs:recursively_delete".to_string()],
+            Decision::Challenge,
+        );
+        remote::spool(&spool_path, &event).unwrap();
+
+        assert_debug_snapshot!(
+            remote::flush(&spool_path, &get_remote(), NetworkMode::Never)
+                .unwrap_err()
+                .to_string()
+        );
+        // nothing was lost
+        assert_debug_snapshot!(super::read_events(&spool_path).unwrap().len());
+        temp_dir.close().unwrap();
+    }
+}
+
+#[cfg(all(test, feature = "audit-sqlite"))]
+mod test_audit_sqlite {
+    use insta::assert_debug_snapshot;
+    use tempdir::TempDir;
+
+    use super::{sqlite, AuditEvent, Decision, AUDIT_FILE_NAME};
+
+    #[test]
+    fn can_append_and_read_events() {
+        let temp_dir = TempDir::new("audit").unwrap();
+        let path = temp_dir.path().join(sqlite::SQLITE_FILE_NAME);
+
+        let mut event = AuditEvent::new(
+            "rm -rf /",
+            vec!["fs:recursively_delete".to_string()],
+            Decision::Challenge,
+        );
+        event.timestamp = 1_700_000_000;
+        sqlite::append(&path, &event).unwrap();
+
+        assert_debug_snapshot!(sqlite::read_events(&path));
+        temp_dir.close().unwrap();
+    }
+
+    #[test]
+    fn can_migrate_a_jsonl_log() {
+        let temp_dir = TempDir::new("audit").unwrap();
+        let jsonl_path = temp_dir.path().join(AUDIT_FILE_NAME);
+        // a version 1 record upgrades during migration
+        std::fs::write(
+            &jsonl_path,
+            r#"{"timestamp":1600000000,"command":"rm -rf /","check_id":"fs:recursively_delete"}"#,
+        )
+        .unwrap();
+
+        let sqlite_path = temp_dir.path().join(sqlite::SQLITE_FILE_NAME);
+        assert_debug_snapshot!(sqlite::migrate_from_jsonl(&jsonl_path, &sqlite_path));
+        assert_debug_snapshot!(sqlite::read_events(&sqlite_path));
+        temp_dir.close().unwrap();
+    }
+}
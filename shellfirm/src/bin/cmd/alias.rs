@@ -0,0 +1,90 @@
+use std::io::BufRead;
+
+use anyhow::Result;
+use clap::{App, AppSettings::ArgRequiredElseHelp, ArgMatches, Command};
+use shellfirm::Config;
+
+pub fn command() -> Command<'static> {
+    Command::new("alias")
+        .about("Manage the shell aliases expanded before the checks run")
+        .setting(ArgRequiredElseHelp)
+        .subcommand(App::new("import").about(
+            "Import aliases from `alias` output on stdin (e.g. `alias | shellfirm alias import`)",
+        ))
+}
+
+pub fn run(arg_matches: &ArgMatches, config: &Config) -> Result<shellfirm::CmdExit> {
+    match arg_matches.subcommand() {
+        Some(("import", _subcommand_matches)) => {
+            let stdin = std::io::stdin();
+            let lines: Vec<String> = stdin.lock().lines().collect::<Result<_, _>>()?;
+            run_import(config, &lines)
+        }
+        _ => unreachable!(),
+    }
+}
+
+fn run_import(config: &Config, lines: &[String]) -> Result<shellfirm::CmdExit> {
+    let aliases = parse_alias_output(lines);
+    if aliases.is_empty() {
+        return Ok(shellfirm::CmdExit {
+            code: exitcode::DATAERR,
+            message: Some(
+                "no aliases found on stdin, pipe the output of `alias` into this command"
+                    .to_string(),
+            ),
+        });
+    }
+    config.update_aliases(&aliases)?;
+    Ok(shellfirm::CmdExit {
+        code: exitcode::OK,
+        message: Some(format!("imported {} aliases", aliases.len())),
+    })
+}
+
+/// Parse `alias` output lines into an alias map. Both the bash form
+/// (`alias k='kubectl'`) and the zsh form (`k=kubectl`) are accepted; lines
+/// without a `=` are skipped.
+///
+/// # Arguments
+///
+/// * `lines` - the lines piped to stdin.
+fn parse_alias_output(lines: &[String]) -> std::collections::BTreeMap<String, String> {
+    lines
+        .iter()
+        .filter_map(|line| {
+            let line = line.trim().strip_prefix("alias ").unwrap_or(line.trim());
+            let (name, expansion) = line.split_once('=')?;
+            let name = name.trim();
+            let expansion = expansion
+                .trim()
+                .trim_matches(|quote| quote == '\'' || quote == '"');
+            if name.is_empty() || expansion.is_empty() || name.contains(char::is_whitespace) {
+                return None;
+            }
+            Some((name.to_string(), expansion.to_string()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test_alias_command {
+    use insta::assert_debug_snapshot;
+
+    use super::*;
+
+    #[test]
+    fn can_parse_alias_output() {
+        let lines: Vec<String> = [
+            "alias k='kubectl'",
+            "alias rmrf='rm -rf'",
+            "gs=git status",
+            "# a comment without an equals sign",
+            "alias =broken",
+        ]
+        .iter()
+        .map(std::string::ToString::to_string)
+        .collect();
+        assert_debug_snapshot!(parse_alias_output(&lines));
+    }
+}
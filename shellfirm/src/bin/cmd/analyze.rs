@@ -0,0 +1,124 @@
+use std::io::BufRead;
+
+use anyhow::Result;
+use clap::{Arg, ArgMatches, Command};
+use serde_derive::Serialize;
+use shellfirm::{
+    checks::{Check, Severity},
+    environment::{Environment, SystemEnvironment},
+    Decision, Guardian, Settings,
+};
+
+pub fn command() -> Command<'static> {
+    Command::new("analyze")
+        .about("Assess commands and stream one JSON analysis object per line")
+        .arg(
+            Arg::new("stdin")
+                .long("stdin")
+                .help("Read newline-delimited commands from stdin (e.g. `history | shellfirm analyze --stdin`)")
+                .takes_value(false),
+        )
+        .arg(Arg::new("command").help("A single command to assess"))
+}
+
+pub fn run(
+    arg_matches: &ArgMatches,
+    settings: &Settings,
+    checks: &[Check],
+) -> Result<shellfirm::CmdExit> {
+    let guardian = Guardian::with_checks(settings.clone(), checks.to_vec());
+    let environment = SystemEnvironment::with_timeout(std::time::Duration::from_millis(
+        settings.max_subprocess_latency_ms,
+    ));
+
+    if arg_matches.is_present("stdin") {
+        let stdin = std::io::stdin();
+        for line in stdin.lock().lines() {
+            let command = line?;
+            if command.trim().is_empty() {
+                continue;
+            }
+            println!("{}", analysis_json(&guardian, &command, &environment)?);
+        }
+    } else if let Some(command) = arg_matches.value_of("command") {
+        println!("{}", analysis_json(&guardian, command, &environment)?);
+    }
+
+    Ok(shellfirm::CmdExit {
+        code: exitcode::OK,
+        message: None,
+    })
+}
+
+/// One streamed analysis object. This shape is a stable API other tools pipe
+/// from — extend it with new fields, never rename or remove existing ones.
+#[derive(Debug, Serialize)]
+struct AnalysisLine {
+    /// The assessed command.
+    command: String,
+    /// Ids of the matched checks.
+    matches: Vec<String>,
+    /// Highest severity among the matched checks, `null` when nothing
+    /// matched.
+    severity: Option<Severity>,
+    /// The decision under the current policy.
+    decision: Decision,
+}
+
+/// Assess one command and serialize the analysis object.
+///
+/// # Arguments
+///
+/// * `guardian` - the loaded validation engine.
+/// * `command` - the command to assess.
+/// * `environment` - environment the command is going to run in.
+fn analysis_json(
+    guardian: &Guardian,
+    command: &str,
+    environment: &dyn Environment,
+) -> Result<String> {
+    let assessment = guardian.assess(command, environment);
+    Ok(serde_json::to_string(&AnalysisLine {
+        command: command.to_string(),
+        matches: assessment
+            .matches
+            .iter()
+            .map(|check| check.id.to_string())
+            .collect(),
+        severity: assessment.matches.iter().map(|check| check.severity).max(),
+        decision: assessment.decision,
+    })?)
+}
+
+#[cfg(test)]
+mod test_analyze_cli_command {
+    use insta::assert_debug_snapshot;
+    use shellfirm::environment::MockEnvironment;
+
+    use super::*;
+
+    #[test]
+    fn can_serialize_analysis_lines() {
+        let checks: Vec<Check> = serde_yaml::from_str(
+            r"
+- id: fs:recursively_delete
+  test: rm.+-rf
+  description: deletes everything
+  from: fs
+  severity: Critical
+",
+        )
+        .unwrap();
+        let settings = Settings {
+            deny_patterns_ids: vec!["fs:recursively_delete".to_string()],
+            ..Settings::default()
+        };
+        let guardian = Guardian::with_checks(settings, checks.clone());
+        let environment = MockEnvironment::default();
+        assert_debug_snapshot!(analysis_json(&guardian, "rm -rf /", &environment));
+        assert_debug_snapshot!(analysis_json(&guardian, "ls -la", &environment));
+
+        let guardian = Guardian::with_checks(Settings::default(), checks);
+        assert_debug_snapshot!(analysis_json(&guardian, "rm -rf ./build", &environment));
+    }
+}
@@ -0,0 +1,402 @@
+use std::{
+    collections::BTreeMap,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{anyhow, bail, Result};
+use clap::{App, AppSettings::ArgRequiredElseHelp, Arg, ArgMatches, Command};
+use shellfirm::{
+    audit::{self, AuditEvent, Outcome},
+    checks::Check,
+    Config, Decision,
+};
+
+pub fn command() -> Command<'static> {
+    let command = Command::new("audit")
+        .about("Review and manage the audit log")
+        .setting(ArgRequiredElseHelp)
+        .subcommand(
+            App::new("search")
+                .about("Show audit events matching the given filters")
+                .arg(
+                    Arg::new("since")
+                        .long("since")
+                        .help("Only events newer than this, e.g. `7d`, `24h`, `30m`")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::new("outcome")
+                        .long("outcome")
+                        .help("Only events with this outcome")
+                        .possible_values([
+                            "denied",
+                            "challenged",
+                            "intercepted",
+                            "edited",
+                            "broke-glass",
+                        ])
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::new("check")
+                        .long("check")
+                        .help("Only events matched by this check id, e.g. `git:force_push`")
+                        .takes_value(true),
+                ),
+        )
+        .subcommand(
+            App::new("stats").about("Count audit events per check id, outcome and severity"),
+        );
+    #[cfg(feature = "audit-sqlite")]
+    let command = command.subcommand(
+        App::new("migrate").about("Copy the JSONL audit log into the SQLite audit database"),
+    );
+    command
+}
+
+pub fn run(matches: &ArgMatches, config: &Config, checks: &[Check]) -> Result<shellfirm::CmdExit> {
+    match matches.subcommand() {
+        Some(("search", subcommand_matches)) => run_search(subcommand_matches, config),
+        Some(("stats", _subcommand_matches)) => run_stats(config, checks),
+        #[cfg(feature = "audit-sqlite")]
+        Some(("migrate", _subcommand_matches)) => run_migrate(config),
+        _ => Err(anyhow!("command not found")),
+    }
+}
+
+fn run_search(arg_matches: &ArgMatches, config: &Config) -> Result<shellfirm::CmdExit> {
+    let since_cutoff = match arg_matches.value_of("since") {
+        Some(spec) => match parse_since(spec) {
+            Some(seconds) => Some(now().saturating_sub(seconds)),
+            None => bail!("could not parse `--since {spec}`, expected e.g. `7d`, `24h` or `30m`"),
+        },
+        None => None,
+    };
+
+    let events: Vec<AuditEvent> = read_all_events(config)?
+        .into_iter()
+        .filter(|event| {
+            matches_filters(
+                event,
+                since_cutoff,
+                arg_matches.value_of("outcome"),
+                arg_matches.value_of("check"),
+            )
+        })
+        .collect();
+
+    Ok(shellfirm::CmdExit {
+        code: exitcode::OK,
+        message: Some(render_search_lines(&events, now()).join("\n")),
+    })
+}
+
+fn run_stats(config: &Config, checks: &[Check]) -> Result<shellfirm::CmdExit> {
+    let events = read_all_events(config)?;
+    Ok(shellfirm::CmdExit {
+        code: exitcode::OK,
+        message: Some(render_stats_lines(&events, checks).join("\n")),
+    })
+}
+
+#[cfg(feature = "audit-sqlite")]
+pub fn run_migrate(config: &Config) -> Result<shellfirm::CmdExit> {
+    let migrated =
+        audit::sqlite::migrate_from_jsonl(&config.audit_file_path(), &config.audit_sqlite_path())?;
+    Ok(shellfirm::CmdExit {
+        code: exitcode::OK,
+        message: Some(format!(
+            "migrated {migrated} audit events to {}",
+            config.audit_sqlite_path().display()
+        )),
+    })
+}
+
+/// Read the events of every audit sink (the JSONL log and, when enabled, the
+/// SQLite database), oldest first.
+fn read_all_events(config: &Config) -> Result<Vec<AuditEvent>> {
+    let mut events = audit::read_events(&config.audit_file_path())?;
+    #[cfg(feature = "audit-sqlite")]
+    events.extend(audit::sqlite::read_events(&config.audit_sqlite_path())?);
+    events.sort_by_key(|event| event.timestamp);
+    Ok(events)
+}
+
+/// Seconds since epoch.
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+/// Parse a `--since` spec like `7d`, `24h`, `30m` or `90s` into seconds.
+/// `None` when the spec is malformed.
+fn parse_since(spec: &str) -> Option<u64> {
+    let spec = spec.trim();
+    let (value, unit) = spec.split_at(spec.len().checked_sub(1)?);
+    let value: u64 = value.parse().ok()?;
+    match unit {
+        "s" => Some(value),
+        "m" => Some(value * 60),
+        "h" => Some(value * 60 * 60),
+        "d" => Some(value * 60 * 60 * 24),
+        "w" => Some(value * 60 * 60 * 24 * 7),
+        _ => None,
+    }
+}
+
+/// Does the event pass all the given search filters.
+///
+/// # Arguments
+///
+/// * `event` - the audit event.
+/// * `since_cutoff` - only events at or after this timestamp.
+/// * `outcome` - only events with this outcome (`denied` and `challenged`
+///   filter on the decision, the rest on what happened afterwards).
+/// * `check` - only events matched by this check id.
+fn matches_filters(
+    event: &AuditEvent,
+    since_cutoff: Option<u64>,
+    outcome: Option<&str>,
+    check: Option<&str>,
+) -> bool {
+    if since_cutoff.is_some_and(|cutoff| event.timestamp < cutoff) {
+        return false;
+    }
+    if check.is_some_and(|check| !event.match_ids.iter().any(|id| id == check)) {
+        return false;
+    }
+    match outcome {
+        Some("denied") => event.decision == Decision::Deny,
+        Some("challenged") => event.decision == Decision::Challenge,
+        Some("intercepted") => event.outcome == Outcome::Intercepted,
+        Some("edited") => event.outcome == Outcome::Edited,
+        Some("broke-glass") => event.outcome == Outcome::BrokeGlass,
+        _ => true,
+    }
+}
+
+/// Render the matching events, oldest first, one line each.
+///
+/// # Arguments
+///
+/// * `events` - the matching events.
+/// * `now` - current time in seconds since epoch.
+fn render_search_lines(events: &[AuditEvent], now: u64) -> Vec<String> {
+    if events.is_empty() {
+        return vec!["no matching audit events".to_string()];
+    }
+    let mut lines: Vec<String> = events
+        .iter()
+        .map(|event| {
+            format!(
+                "{:>8} {:?}/{:?} [{}] {}",
+                format_age(now.saturating_sub(event.timestamp)),
+                event.decision,
+                event.outcome,
+                event.match_ids.join(", "),
+                event.command
+            )
+        })
+        .collect();
+    lines.push(format!(
+        "{} matching event{}",
+        events.len(),
+        if events.len() == 1 { "" } else { "s" }
+    ));
+    lines
+}
+
+/// Render the per-check-id, per-outcome and per-severity counts. The
+/// severity of an event is the highest severity among its matched checks;
+/// ids no longer in the checks bundle count as `unknown`.
+///
+/// # Arguments
+///
+/// * `events` - all recorded events.
+/// * `checks` - the active checks, mapping check ids to severities.
+fn render_stats_lines(events: &[AuditEvent], checks: &[Check]) -> Vec<String> {
+    if events.is_empty() {
+        return vec!["the audit log is empty".to_string()];
+    }
+
+    let mut by_check: BTreeMap<String, usize> = BTreeMap::new();
+    let mut by_outcome: BTreeMap<String, usize> = BTreeMap::new();
+    let mut by_severity: BTreeMap<String, usize> = BTreeMap::new();
+    for event in events {
+        for id in &event.match_ids {
+            *by_check.entry(id.to_string()).or_default() += 1;
+        }
+        let outcome = match event.decision {
+            Decision::Deny => "denied".to_string(),
+            _ => format!("{:?}", event.outcome).to_lowercase(),
+        };
+        *by_outcome.entry(outcome).or_default() += 1;
+        let severity = event
+            .match_ids
+            .iter()
+            .filter_map(|id| checks.iter().find(|check| &check.id == id))
+            .map(|check| check.severity)
+            .max()
+            .map_or_else(|| "unknown".to_string(), |severity| format!("{severity:?}"));
+        *by_severity.entry(severity).or_default() += 1;
+    }
+
+    let mut lines = vec![format!("{} audit events", events.len())];
+    for (title, counts) in [
+        ("by check id:", by_check),
+        ("by outcome:", by_outcome),
+        ("by severity:", by_severity),
+    ] {
+        lines.push(title.to_string());
+        let mut counts: Vec<(String, usize)> = counts.into_iter().collect();
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        for (key, count) in counts {
+            lines.push(format!("  {count:>5} {key}"));
+        }
+    }
+    lines
+}
+
+/// Render an age in the largest round unit, e.g. `3d` or `5h`.
+fn format_age(seconds: u64) -> String {
+    if seconds >= 60 * 60 * 24 {
+        format!("{}d", seconds / (60 * 60 * 24))
+    } else if seconds >= 60 * 60 {
+        format!("{}h", seconds / (60 * 60))
+    } else if seconds >= 60 {
+        format!("{}m", seconds / 60)
+    } else {
+        format!("{seconds}s")
+    }
+}
+
+#[cfg(test)]
+mod test_audit_cli_command {
+
+    use insta::assert_debug_snapshot;
+
+    use super::*;
+
+    fn get_events() -> Vec<AuditEvent> {
+        let mut denied = AuditEvent::new(
+            "git push --force",
+            vec!["git:force_push".to_string()],
+            Decision::Deny,
+        );
+        denied.timestamp = 1_700_000_000;
+        let mut challenged = AuditEvent::new(
+            "rm -rf /",
+            vec!["fs:recursively_delete".to_string()],
+            Decision::Challenge,
+        );
+        challenged.timestamp = 1_700_000_000 - 60 * 60 * 24 * 10;
+        let mut edited = AuditEvent::new(
+            "git push --force",
+            vec!["git:force_push".to_string()],
+            Decision::Challenge,
+        );
+        edited.timestamp = 1_700_000_000 - 60 * 60 * 5;
+        edited.outcome = Outcome::Edited;
+        vec![denied, challenged, edited]
+    }
+
+    fn get_checks() -> Vec<Check> {
+        serde_yaml::from_str(
+            r"
+- id: fs:recursively_delete
+  test: rm.+-rf
+  description: deletes everything
+  from: fs
+  severity: Critical
+- id: git:force_push
+  test: git push.+-f
+  description: overwrites the remote history
+  from: git
+  severity: High
+",
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn can_parse_since() {
+        assert_debug_snapshot!(parse_since("7d"));
+        assert_debug_snapshot!(parse_since("24h"));
+        assert_debug_snapshot!(parse_since("30m"));
+        assert_debug_snapshot!(parse_since("90s"));
+        assert_debug_snapshot!(parse_since("2w"));
+        assert_debug_snapshot!(parse_since("7 days"));
+        assert_debug_snapshot!(parse_since(""));
+    }
+
+    #[test]
+    fn can_filter_events() {
+        let events = get_events();
+        let filtered: Vec<&str> = events
+            .iter()
+            .filter(|event| matches_filters(event, None, Some("denied"), None))
+            .map(|event| event.command.as_str())
+            .collect();
+        assert_debug_snapshot!(filtered);
+
+        let filtered: Vec<&str> = events
+            .iter()
+            .filter(|event| matches_filters(event, None, None, Some("git:force_push")))
+            .map(|event| event.command.as_str())
+            .collect();
+        assert_debug_snapshot!(filtered);
+
+        // the 10 day old event is outside a 7 day window
+        let filtered: Vec<&str> = events
+            .iter()
+            .filter(|event| {
+                matches_filters(event, Some(1_700_000_000 - 60 * 60 * 24 * 7), None, None)
+            })
+            .map(|event| event.command.as_str())
+            .collect();
+        assert_debug_snapshot!(filtered);
+
+        let filtered: Vec<&str> = events
+            .iter()
+            .filter(|event| matches_filters(event, None, Some("edited"), None))
+            .map(|event| event.command.as_str())
+            .collect();
+        assert_debug_snapshot!(filtered);
+    }
+
+    #[test]
+    fn can_render_search_lines() {
+        assert_debug_snapshot!(render_search_lines(&get_events(), 1_700_000_030));
+        assert_debug_snapshot!(render_search_lines(&[], 1_700_000_030));
+    }
+
+    #[test]
+    fn can_render_stats_lines() {
+        assert_debug_snapshot!(render_stats_lines(&get_events(), &get_checks()));
+        // an id missing from the bundle counts as unknown severity
+        assert_debug_snapshot!(render_stats_lines(&get_events(), &[]));
+        assert_debug_snapshot!(render_stats_lines(&[], &get_checks()));
+    }
+
+    #[cfg(feature = "audit-sqlite")]
+    #[test]
+    fn can_run_migrate() {
+        let temp_dir = tempdir::TempDir::new("config-app").unwrap();
+        let config = Config::new(Some(&temp_dir.path().join("app").display().to_string())).unwrap();
+
+        let event = AuditEvent::new(
+            "rm -rf /",
+            vec!["fs:recursively_delete".to_string()],
+            shellfirm::Decision::Challenge,
+        );
+        audit::append(&config.audit_file_path(), &event).unwrap();
+
+        assert_debug_snapshot!(run_migrate(&config).map(|exit| exit.code));
+        assert_debug_snapshot!(audit::sqlite::read_events(&config.audit_sqlite_path())
+            .unwrap()
+            .len());
+        temp_dir.close().unwrap();
+    }
+}
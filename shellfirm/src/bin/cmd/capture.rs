@@ -0,0 +1,50 @@
+use anyhow::Result;
+use clap::{Arg, ArgMatches, Command};
+use shellfirm::{capture, checks::Check, environment::SystemEnvironment, Settings};
+
+pub fn command() -> Command<'static> {
+    Command::new("capture")
+        .about("Snapshot the analysis of a command into a redacted repro bundle for bug reports.")
+        .arg(
+            Arg::new("command")
+                .short('c')
+                .long("command")
+                .help("The command to analyze and capture")
+                .required(true)
+                .takes_value(true),
+        )
+        .arg(
+            Arg::new("out")
+                .long("out")
+                .help("Write the bundle to this file instead of stdout")
+                .takes_value(true),
+        )
+}
+
+pub fn run(
+    arg_matches: &ArgMatches,
+    settings: &Settings,
+    checks: &[Check],
+) -> Result<shellfirm::CmdExit> {
+    let environment = SystemEnvironment::with_timeout(std::time::Duration::from_millis(
+        settings.max_subprocess_latency_ms,
+    ));
+    let bundle = capture::snapshot(
+        arg_matches.value_of("command").unwrap_or(""),
+        checks,
+        &environment,
+    );
+    let json = serde_json::to_string_pretty(&bundle)?;
+
+    let message = match arg_matches.value_of("out") {
+        Some(path) => {
+            std::fs::write(path, json)?;
+            format!("repro bundle written to {path}")
+        }
+        None => json,
+    };
+    Ok(shellfirm::CmdExit {
+        code: exitcode::OK,
+        message: Some(message),
+    })
+}
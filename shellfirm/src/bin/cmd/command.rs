@@ -1,12 +1,12 @@
 use anyhow::Result;
 use clap::{Arg, ArgMatches, Command};
-use lazy_static::lazy_static;
-use regex::Regex;
-use shellfirm::{checks, checks::Check, Settings};
-
-lazy_static! {
-    static ref REGEX_STRING_COMMAND_REPLACE: Regex = Regex::new(r#"('|")([\s\S]*?)('|")"#).unwrap();
-}
+use shellfirm::{
+    checks,
+    checks::Check,
+    context,
+    environment::{Environment, SystemEnvironment},
+    Config, Settings,
+};
 
 pub fn command() -> Command<'static> {
     Command::new("pre-command")
@@ -26,18 +26,48 @@ pub fn command() -> Command<'static> {
                 .help("Check if the command is risky and exit")
                 .takes_value(false),
         )
+        .arg(
+            Arg::new("assume-context")
+                .long("assume-context")
+                .help("Force context signals, e.g. `k8s=prod,branch=main,ssh=true`")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::new("source")
+                .long("source")
+                .help("Provenance of the command: interactive, script, ci or agent:<name>")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::new("challenge-seed")
+                .long("challenge-seed")
+                .help("Seed the challenge generation, so test harnesses can assert exact prompts")
+                .takes_value(true),
+        )
 }
 
 pub fn run(
     arg_matches: &ArgMatches,
+    config: Option<&Config>,
     settings: &Settings,
     checks: &[Check],
 ) -> Result<shellfirm::CmdExit> {
+    if let Some(seed) = arg_matches.value_of("challenge-seed") {
+        std::env::set_var(shellfirm::CHALLENGE_SEED_ENV_VAR, seed);
+    }
+    if let Some(source) = arg_matches.value_of("source") {
+        // reject a typo'd source instead of silently treating it as
+        // interactive
+        context::Source::parse(source)?;
+        std::env::set_var(context::SOURCE_ENV_VAR, source);
+    }
     execute(
         arg_matches.value_of("command").unwrap_or(""),
         settings,
         checks,
         arg_matches.is_present("test"),
+        arg_matches.value_of("assume-context"),
+        config,
     )
 }
 
@@ -46,32 +76,122 @@ fn execute(
     settings: &Settings,
     checks: &[Check],
     dryrun: bool,
+    assume_context: Option<&str>,
+    config: Option<&Config>,
 ) -> Result<shellfirm::CmdExit> {
-    let command = REGEX_STRING_COMMAND_REPLACE
-        .replace_all(command, "")
-        .to_string();
-
-    let splitted_command: Vec<&str> = command
-        .split(|c| c == '&' || c == '|' || c == "&&".chars().next().unwrap())
-        .collect();
+    if let (Some(config), Ok(shell_pid)) = (config, std::env::var("SHELLFIRM_SHELL_PID")) {
+        // best effort heartbeat so `shellfirm status` can tell the hook fires
+        if let Err(err) = config.record_heartbeat(&shell_pid) {
+            log::debug!("could not record heartbeat: {:?}", err);
+        }
+    }
 
-    log::debug!("splitted_command {:?}", splitted_command);
-    let matches: Vec<checks::Check> = splitted_command
-        .iter()
-        .flat_map(|c| checks::run_check_on_command(checks, c))
-        .collect();
+    let system_environment = SystemEnvironment::with_timeout(std::time::Duration::from_millis(
+        settings.max_subprocess_latency_ms,
+    ));
+    // a context switch takes effect before any later command runs: evaluate
+    // the switch itself against the context it is switching into, so the
+    // prod rules fire now instead of one command too late
+    let environment: Box<dyn Environment> = match context::pending_k8s_context(command) {
+        Some(target) => Box::new(context::OverriddenEnvironment::new(
+            Box::new(system_environment),
+            context::Overrides {
+                k8s_context: Some(target),
+                ..context::Overrides::default()
+            },
+        )),
+        None => Box::new(system_environment),
+    };
+    // an explicit `--assume-context` still wins over everything detected
+    let environment: Box<dyn Environment> = match assume_context {
+        Some(spec) => Box::new(context::OverriddenEnvironment::new(
+            environment,
+            context::Overrides::parse(spec)?,
+        )),
+        None => environment,
+    };
+    shellfirm::diagnostics::set_stage("analyze");
+    // `alias rmrf='rm -rf'` must not bypass the checks: analyze the command
+    // the shell is actually going to run
+    let command = &shellfirm::command::expand_aliases(command, &settings.aliases);
+    let mut analysis = checks::analyze_command(checks, command, environment.as_ref());
+    settings.apply_severity_filter(&mut analysis, environment.as_ref());
+    settings.apply_allow_rules(&mut analysis, command, environment.as_ref());
+    settings.apply_namespace_rules(&mut analysis, command);
+    let matches = &analysis.matches;
 
     log::debug!("matches found {}. {:?}", matches.len(), matches);
 
     if dryrun {
         return Ok(shellfirm::CmdExit {
             code: exitcode::OK,
-            message: Some(serde_yaml::to_string(&matches)?),
+            message: Some(serde_yaml::to_string(&analysis)?),
         });
     }
 
     if !matches.is_empty() {
-        checks::challenge(&settings.challenge, &matches, &settings.deny_patterns_ids)?;
+        let mut deny_ids = settings.active_deny_patterns_ids(environment.as_ref());
+        // a command targeting a deny-listed namespace (`kube-system`,
+        // `prod-*`) denies its kubernetes matches outright
+        if settings.denied_namespace(command).is_some() {
+            deny_ids.extend(
+                matches
+                    .iter()
+                    .filter(|check| check.from.starts_with("kubernetes"))
+                    .map(|check| check.id.to_string()),
+            );
+        }
+        if let Some(config) = config {
+            // best effort statistics for the status segments
+            if let Err(err) = config.increment_block_counter() {
+                log::debug!("could not update stats file: {:?}", err);
+            }
+            // best effort audit trail
+            let decision = if matches
+                .iter()
+                .any(|c| deny_ids.contains(&c.id) || settings.is_denied_by_tag(c))
+            {
+                shellfirm::Decision::Deny
+            } else {
+                shellfirm::Decision::Challenge
+            };
+            let mut event = shellfirm::audit::AuditEvent::new(
+                command,
+                matches.iter().map(|c| c.id.to_string()).collect(),
+                decision,
+            );
+            event.identity = context::detect_identity(environment.as_ref());
+            event.source = Some(context::detect_source(environment.as_ref()).to_string());
+            event.tags = checks::matched_tags(matches);
+            record_audit_event(config, settings, &event);
+            // keep the command around before the challenge, a cancelled
+            // challenge kills this process
+            if settings.save_last_command {
+                let check_ids = matches.iter().map(|c| c.id.to_string()).collect();
+                if let Err(err) = config.record_last_command(command, check_ids) {
+                    log::debug!("could not record last command: {:?}", err);
+                }
+            }
+        }
+        if settings.copy_blocked_command_to_clipboard {
+            copy_to_clipboard(command);
+        }
+        shellfirm::diagnostics::set_stage("challenge");
+        let resolution = checks::challenge(settings, matches, command, &deny_ids)?;
+        if let (checks::ChallengeResolution::BrokeGlass(justification), Some(config)) =
+            (&resolution, config)
+        {
+            record_break_glass(
+                config,
+                settings,
+                command,
+                matches,
+                justification,
+                environment.as_ref(),
+            );
+        }
+    } else if let Some(config) = config {
+        record_edited_followup(config, settings, command, environment.as_ref());
     }
 
     Ok(shellfirm::CmdExit {
@@ -80,6 +200,111 @@ fn execute(
     })
 }
 
+/// Best effort audit record when a passing command is a modified version of
+/// the last intercepted one, so warnings leading to safer rewrites can be
+/// measured.
+fn record_edited_followup(
+    config: &Config,
+    settings: &Settings,
+    command: &str,
+    environment: &dyn Environment,
+) {
+    let Ok(Some(last)) = config.get_last_command() else {
+        return;
+    };
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    if !shellfirm::audit::is_edited_followup(&last, command, now) {
+        return;
+    }
+    let mut event =
+        shellfirm::audit::AuditEvent::new(command, last.check_ids, shellfirm::Decision::Allow);
+    event.outcome = shellfirm::audit::Outcome::Edited;
+    event.identity = context::detect_identity(environment);
+    event.source = Some(context::detect_source(environment).to_string());
+    record_audit_event(config, settings, &event);
+}
+
+/// Best effort audit record and review webhook when a denied command was
+/// allowed to run once with a justification.
+fn record_break_glass(
+    config: &Config,
+    settings: &Settings,
+    command: &str,
+    matches: &[Check],
+    justification: &str,
+    environment: &dyn Environment,
+) {
+    let mut event = shellfirm::audit::AuditEvent::new(
+        command,
+        matches.iter().map(|c| c.id.to_string()).collect(),
+        shellfirm::Decision::Deny,
+    );
+    event.outcome = shellfirm::audit::Outcome::BrokeGlass;
+    event.justification = Some(justification.to_string());
+    event.identity = context::detect_identity(environment);
+    event.source = Some(context::detect_source(environment).to_string());
+    event.tags = checks::matched_tags(matches);
+    record_audit_event(config, settings, &event);
+    if let Some(webhook) = &settings.break_glass.webhook {
+        if let Err(err) = shellfirm::audit::notify_break_glass(webhook, &event, settings.network) {
+            log::debug!("could not notify break-glass webhook: {:?}", err);
+        }
+    }
+}
+
+/// Best effort write of an audit event to every configured sink.
+fn record_audit_event(config: &Config, settings: &Settings, event: &shellfirm::audit::AuditEvent) {
+    if let Err(err) = shellfirm::audit::append(&config.audit_file_path(), event) {
+        log::debug!("could not record audit event: {:?}", err);
+    }
+    #[cfg(feature = "audit-sqlite")]
+    if let Err(err) = shellfirm::audit::sqlite::append(&config.audit_sqlite_path(), event) {
+        log::debug!("could not record audit event in sqlite: {:?}", err);
+    }
+    if let Some(remote) = &settings.audit.remote {
+        let spool_path = config.audit_spool_path();
+        if let Err(err) = shellfirm::audit::remote::spool(&spool_path, event) {
+            log::debug!("could not spool audit event: {:?}", err);
+        }
+        if let Err(err) = shellfirm::audit::remote::flush(&spool_path, remote, settings.network) {
+            log::debug!("could not flush audit spool: {:?}", err);
+        }
+    }
+}
+
+/// Best effort copy of the command to the system clipboard, trying the
+/// platform clipboard tools in order.
+fn copy_to_clipboard(command: &str) {
+    use std::{io::Write, process::Stdio};
+
+    for tool in [
+        vec!["pbcopy"],
+        vec!["wl-copy"],
+        vec!["xclip", "-selection", "clipboard"],
+    ] {
+        let Ok(mut child) = std::process::Command::new(tool[0])
+            .args(&tool[1..])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+        else {
+            continue;
+        };
+        if let Some(stdin) = child.stdin.as_mut() {
+            if stdin.write_all(command.as_bytes()).is_err() {
+                continue;
+            }
+        }
+        if child.wait().map(|status| status.success()).unwrap_or(false) {
+            return;
+        }
+    }
+    log::debug!("no clipboard tool available");
+}
+
 #[cfg(test)]
 mod test_command_cli_command {
 
@@ -105,7 +330,9 @@ mod test_command_cli_command {
             "rm -rf /",
             &settings,
             &settings.get_active_checks().unwrap(),
-            true
+            true,
+            None,
+            None
         ));
         temp_dir.close().unwrap();
     }
@@ -121,7 +348,9 @@ mod test_command_cli_command {
             "command",
             &settings,
             &settings.get_active_checks().unwrap(),
-            true
+            true,
+            None,
+            None
         ));
         temp_dir.close().unwrap();
     }
@@ -1,9 +1,10 @@
 use anyhow::{anyhow, Result};
 use clap::{App, AppSettings::ArgRequiredElseHelp, Arg, ArgMatches, Command};
+use console::Style;
 use shellfirm::{dialog, Challenge, Config, Settings};
 use strum::IntoEnumIterator;
 
-const ALL_GROUP_CHECKS: &[&str] = &include!(concat!(env!("OUT_DIR"), "/all_the_files.rs"));
+pub const ALL_GROUP_CHECKS: &[&str] = &include!(concat!(env!("OUT_DIR"), "/all_the_files.rs"));
 
 pub fn command() -> Command<'static> {
     Command::new("config")
@@ -12,12 +13,71 @@ pub fn command() -> Command<'static> {
         .subcommand(
             App::new("update-groups")
                 .about("enable check group")
-                .arg(Arg::new("check-group").help("Check group")),
+                .arg(Arg::new("check-group").help("Check group"))
+                .arg(
+                    Arg::new("dry-run")
+                        .long("dry-run")
+                        .help("Only print the settings changes")
+                        .takes_value(false),
+                )
+                .arg(
+                    Arg::new("yes")
+                        .long("yes")
+                        .help("Apply the changes without asking, for automation")
+                        .takes_value(false),
+                ),
+        )
+        .subcommand(
+            App::new("refresh-groups")
+                .about("Probe the installed tools and enable the matching check groups"),
         )
         .subcommand(App::new("reset").about("Reset configuration"))
         .subcommand(App::new("challenge").about("Reset configuration"))
         .subcommand(App::new("ignore").about("Ignore command pattern"))
         .subcommand(App::new("deny").about("Deny command pattern"))
+        .subcommand(App::new("enable").about("Turn command interception on"))
+        .subcommand(App::new("disable").about("Turn command interception off"))
+        .subcommand(
+            App::new("doctor").about("Report configuration problems (broken custom checks)"),
+        )
+        .subcommand(
+            App::new("edit")
+                .about("Open a config file in $EDITOR, validating the result before saving")
+                .arg(
+                    Arg::new("file")
+                        .help(
+                            "What to edit: settings, policy, or a custom checks file name (e.g. \
+                             team.yaml)",
+                        )
+                        .default_value("settings"),
+                ),
+        )
+        .subcommand(
+            App::new("schema")
+                .about("Print the JSON Schema of a config file, for editor validation")
+                .arg(
+                    Arg::new("for")
+                        .long("for")
+                        .help("Which file to describe")
+                        .possible_values(["settings", "policy"])
+                        .default_value("settings"),
+                )
+                .arg(
+                    Arg::new("format")
+                        .long("format")
+                        .help("Output format")
+                        .possible_values(["json-schema"])
+                        .default_value("json-schema"),
+                ),
+        )
+        .subcommand(
+            App::new("checks").about("List the active checks").arg(
+                Arg::new("tag")
+                    .long("tag")
+                    .help("Only show checks carrying this tag (e.g. irreversible, cis-1.2.3)")
+                    .takes_value(true),
+            ),
+        )
 }
 
 pub fn run(
@@ -28,13 +88,31 @@ pub fn run(
     match matches.subcommand() {
         None => Err(anyhow!("command not found")),
         Some(tup) => match tup {
-            ("update-groups", _subcommand_matches) => {
-                run_update_groups(config, &config.get_settings_from_file()?, None)
-            }
+            ("update-groups", subcommand_matches) => run_update_groups(
+                config,
+                &config.get_settings_from_file()?,
+                None,
+                subcommand_matches.is_present("dry-run"),
+                subcommand_matches.is_present("yes"),
+            ),
+            ("refresh-groups", _subcommand_matches) => run_refresh_groups(config),
             ("reset", _subcommand_matches) => Ok(run_reset(config, None)),
             ("challenge", _subcommand_matches) => run_challenge(config, None),
             ("ignore", _subcommand_matches) => run_ignore(config, settings, None),
             ("deny", _subcommand_matches) => run_deny(config, settings, None),
+            ("enable", _subcommand_matches) => run_set_enabled(config, true),
+            ("disable", _subcommand_matches) => run_set_enabled(config, false),
+            ("doctor", _subcommand_matches) => run_doctor(config),
+            ("edit", subcommand_matches) => run_edit(
+                config,
+                subcommand_matches.value_of("file").unwrap_or("settings"),
+            ),
+            ("schema", subcommand_matches) => {
+                run_schema(subcommand_matches.value_of("for").unwrap_or("settings"))
+            }
+            ("checks", subcommand_matches) => {
+                run_checks(settings, subcommand_matches.value_of("tag"))
+            }
             _ => unreachable!(),
         },
     }
@@ -44,6 +122,8 @@ pub fn run_update_groups(
     config: &Config,
     settings: &Settings,
     groups: Option<Vec<String>>,
+    dry_run: bool,
+    assume_yes: bool,
 ) -> Result<shellfirm::CmdExit> {
     let check_groups = if let Some(groups) = groups {
         groups
@@ -57,6 +137,34 @@ pub fn run_update_groups(
         )?
     };
 
+    // show what would change in the settings file before touching it
+    let mut updated = settings.clone();
+    updated.includes = check_groups.clone();
+    for line in render_settings_diff_lines(
+        &serde_yaml::to_string(settings)?,
+        &serde_yaml::to_string(&updated)?,
+    ) {
+        let style = if line.starts_with('+') {
+            Style::new().green()
+        } else {
+            Style::new().red()
+        };
+        eprintln!("{}", style.apply_to(line));
+    }
+
+    if dry_run {
+        return Ok(shellfirm::CmdExit {
+            code: exitcode::OK,
+            message: Some("dry run, settings not changed".to_string()),
+        });
+    }
+    if !assume_yes && !dialog::confirm("apply these settings changes?")? {
+        return Ok(shellfirm::CmdExit {
+            code: exitcode::OK,
+            message: Some("aborted, settings not changed".to_string()),
+        });
+    }
+
     match config.update_check_groups(check_groups) {
         Ok(()) => Ok(shellfirm::CmdExit {
             code: exitcode::OK,
@@ -69,6 +177,45 @@ pub fn run_update_groups(
     }
 }
 
+/// Render a line diff between two settings file contents, removed lines
+/// prefixed with `-` and added lines with `+`. Unchanged lines are dropped.
+///
+/// # Arguments
+///
+/// * `before` - settings file content before the change.
+/// * `after` - settings file content after the change.
+fn render_settings_diff_lines(before: &str, after: &str) -> Vec<String> {
+    let before_lines: Vec<&str> = before.lines().collect();
+    let after_lines: Vec<&str> = after.lines().collect();
+    let mut lines: Vec<String> = before_lines
+        .iter()
+        .filter(|line| !after_lines.contains(line))
+        .map(|line| format!("- {line}"))
+        .collect();
+    lines.extend(
+        after_lines
+            .iter()
+            .filter(|line| !before_lines.contains(line))
+            .map(|line| format!("+ {line}")),
+    );
+    lines
+}
+
+pub fn run_refresh_groups(config: &Config) -> Result<shellfirm::CmdExit> {
+    let groups =
+        shellfirm::detect_include_groups(&shellfirm::environment::SystemEnvironment::default());
+    match config.update_check_groups(groups.clone()) {
+        Ok(()) => Ok(shellfirm::CmdExit {
+            code: exitcode::OK,
+            message: Some(format!("enabled check groups: {}", groups.join(", "))),
+        }),
+        Err(e) => Ok(shellfirm::CmdExit {
+            code: exitcode::CONFIG,
+            message: Some(format!("Could not update checks group. error: {e}")),
+        }),
+    }
+}
+
 pub fn run_reset(config: &Config, force_selection: Option<usize>) -> shellfirm::CmdExit {
     match config.reset_config(force_selection) {
         Ok(()) => shellfirm::CmdExit {
@@ -102,6 +249,225 @@ pub fn run_challenge(config: &Config, challenge: Option<Challenge>) -> Result<sh
     }
 }
 
+pub fn run_set_enabled(config: &Config, enabled: bool) -> Result<shellfirm::CmdExit> {
+    match config.set_enabled(enabled) {
+        Ok(()) => Ok(shellfirm::CmdExit {
+            code: exitcode::OK,
+            message: Some(format!(
+                "shellfirm protection is {}",
+                if enabled { "on" } else { "off" }
+            )),
+        }),
+        Err(e) => Ok(shellfirm::CmdExit {
+            code: exitcode::CONFIG,
+            message: Some(format!("could not change protection state: {e:?}")),
+        }),
+    }
+}
+
+pub fn run_schema(target: &str) -> Result<shellfirm::CmdExit> {
+    let schema = match target {
+        "policy" => shellfirm::schema::policy_schema(),
+        _ => shellfirm::schema::settings_schema(),
+    };
+    Ok(shellfirm::CmdExit {
+        code: exitcode::OK,
+        message: Some(serde_json::to_string_pretty(&schema)?),
+    })
+}
+
+pub fn run_checks(settings: &Settings, tag: Option<&str>) -> Result<shellfirm::CmdExit> {
+    let checks = settings.get_active_checks()?;
+    Ok(shellfirm::CmdExit {
+        code: exitcode::OK,
+        message: Some(render_checks_lines(&checks, tag).join("\n")),
+    })
+}
+
+/// Render the check list lines, one check per line with its severity and
+/// tags, optionally filtered to the checks carrying the given tag.
+///
+/// # Arguments
+///
+/// * `checks` - checks to list.
+/// * `tag` - only list checks carrying this tag, when given.
+fn render_checks_lines(checks: &[shellfirm::checks::Check], tag: Option<&str>) -> Vec<String> {
+    let mut lines: Vec<String> = checks
+        .iter()
+        .filter(|check| tag.is_none_or(|tag| check.tags.iter().any(|candidate| candidate == tag)))
+        .map(|check| {
+            if check.tags.is_empty() {
+                format!("{} ({:?})", check.id, check.severity)
+            } else {
+                format!(
+                    "{} ({:?}) [{}]",
+                    check.id,
+                    check.severity,
+                    check.tags.join(", ")
+                )
+            }
+        })
+        .collect();
+    if lines.is_empty() {
+        lines.push(match tag {
+            Some(tag) => format!("no active check carries the tag `{tag}`"),
+            None => "no active checks".to_string(),
+        });
+    }
+    lines
+}
+
+pub fn run_doctor(config: &Config) -> Result<shellfirm::CmdExit> {
+    let (custom_checks, errors) = config.load_custom_checks();
+    Ok(shellfirm::CmdExit {
+        code: if errors.is_empty() {
+            exitcode::OK
+        } else {
+            exitcode::CONFIG
+        },
+        message: Some(render_doctor_lines(custom_checks.len(), &errors).join("\n")),
+    })
+}
+
+/// Render the doctor report lines.
+///
+/// # Arguments
+///
+/// * `loaded` - number of custom checks that loaded successfully.
+/// * `errors` - custom check entries that could not be loaded.
+fn render_doctor_lines(
+    loaded: usize,
+    errors: &[shellfirm::checks::CustomCheckError],
+) -> Vec<String> {
+    let mut lines = vec![format!("custom checks loaded: {loaded}")];
+    if errors.is_empty() {
+        lines.push("no problems found".to_string());
+        return lines;
+    }
+    for error in errors {
+        let mut location = error.file.to_string();
+        if let Some(line) = error.line {
+            location.push_str(&format!(" (line {line})"));
+        }
+        if let Some(id) = &error.id {
+            location.push_str(&format!(" (id: {id})"));
+        }
+        lines.push(format!("broken custom check: {location}: {}", error.error));
+    }
+    lines
+}
+
+/// What a `config edit` invocation is editing, deciding how the saved
+/// content is validated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum EditTarget {
+    Settings,
+    Policy,
+    CustomChecks,
+}
+
+/// Resolve the `config edit` file argument to the target kind and file path.
+///
+/// # Arguments
+///
+/// * `config` - the app config.
+/// * `name` - `settings`, `policy`, or a custom checks file name.
+fn resolve_edit_target(config: &Config, name: &str) -> Result<(EditTarget, std::path::PathBuf)> {
+    Ok(match name {
+        "settings" => (
+            EditTarget::Settings,
+            std::path::PathBuf::from(&config.setting_file_path),
+        ),
+        "policy" => (
+            EditTarget::Policy,
+            std::env::current_dir()?.join(shellfirm::policy::POLICY_FILE_NAME),
+        ),
+        file_name => {
+            let file_name = if std::path::Path::new(file_name).extension().is_some() {
+                file_name.to_string()
+            } else {
+                format!("{file_name}.yaml")
+            };
+            (
+                EditTarget::CustomChecks,
+                std::path::PathBuf::from(&config.root_folder)
+                    .join("checks")
+                    .join(file_name),
+            )
+        }
+    })
+}
+
+/// Validate edited file content against the schema of the edit target, so an
+/// invalid save never reaches the real file.
+///
+/// # Errors
+///
+/// Will return `Err` when the content is not valid YAML for the target.
+fn validate_edit_content(target: EditTarget, content: &str) -> Result<()> {
+    match target {
+        EditTarget::Settings => {
+            serde_yaml::from_str::<Settings>(content)?;
+        }
+        EditTarget::Policy => {
+            serde_yaml::from_str::<shellfirm::policy::Policy>(content)?;
+        }
+        EditTarget::CustomChecks => {
+            serde_yaml::from_str::<Vec<shellfirm::checks::Check>>(content)?;
+        }
+    }
+    Ok(())
+}
+
+pub fn run_edit(config: &Config, name: &str) -> Result<shellfirm::CmdExit> {
+    let (target, path) = resolve_edit_target(config, name)?;
+    let editor = std::env::var("EDITOR")
+        .or_else(|_| std::env::var("VISUAL"))
+        .unwrap_or_else(|_| "vi".to_string());
+
+    // edit a scratch copy so an invalid save never reaches the real file
+    let original = std::fs::read_to_string(&path).unwrap_or_default();
+    let scratch = std::env::temp_dir().join(format!(
+        "shellfirm-edit-{}-{}",
+        std::process::id(),
+        path.file_name()
+            .map_or_else(|| "config".to_string(), |f| f.to_string_lossy().to_string())
+    ));
+    std::fs::write(&scratch, &original)?;
+
+    loop {
+        let status = std::process::Command::new(&editor).arg(&scratch).status()?;
+        if !status.success() {
+            let _ = std::fs::remove_file(&scratch);
+            return Ok(shellfirm::CmdExit {
+                code: exitcode::CONFIG,
+                message: Some(format!("{editor} exited with an error, nothing changed")),
+            });
+        }
+        let edited = std::fs::read_to_string(&scratch)?;
+        match validate_edit_content(target, &edited) {
+            Ok(()) => {
+                std::fs::write(&path, edited)?;
+                let _ = std::fs::remove_file(&scratch);
+                return Ok(shellfirm::CmdExit {
+                    code: exitcode::OK,
+                    message: Some(format!("{} updated", path.display())),
+                });
+            }
+            Err(e) => {
+                eprintln!("invalid {name} file: {e}");
+                if !dialog::confirm("re-open the editor?")? {
+                    let _ = std::fs::remove_file(&scratch);
+                    return Ok(shellfirm::CmdExit {
+                        code: exitcode::CONFIG,
+                        message: Some("invalid YAML, changes discarded".to_string()),
+                    });
+                }
+            }
+        }
+    }
+}
+
 pub fn run_ignore(
     config: &Config,
     settings: &Settings,
@@ -185,6 +551,18 @@ mod test_config_cli_command {
         Config::new(Some(&temp_dir.display().to_string())).unwrap()
     }
 
+    // settings snapshots redact the checks bundle hash, so adding a check
+    // does not rewrite every settings snapshot
+    macro_rules! assert_settings_snapshot {
+        ($settings:expr) => {
+            with_settings!({filters => vec![
+                (r"[0-9a-f]{64}", "[CHECKS_BUNDLE_HASH]"),
+            ]}, {
+                assert_debug_snapshot!($settings);
+            });
+        };
+    }
+
     #[test]
     fn can_run_update_groups() {
         let temp_dir = TempDir::new("config-app").unwrap();
@@ -192,9 +570,11 @@ mod test_config_cli_command {
         assert_debug_snapshot!(run_update_groups(
             &config,
             &config.get_settings_from_file().unwrap(),
-            Some(vec!["test-1".to_string()])
+            Some(vec!["test-1".to_string()]),
+            false,
+            true
         ));
-        assert_debug_snapshot!(config.get_settings_from_file());
+        assert_settings_snapshot!(config.get_settings_from_file());
         temp_dir.close().unwrap();
     }
 
@@ -210,19 +590,53 @@ mod test_config_cli_command {
             assert_debug_snapshot!(run_update_groups(
             &config,
             &settings,
-            Some(vec!["test-1".to_string()])
+            Some(vec!["test-1".to_string()]),
+            false,
+            true
         ));
         });
         temp_dir.close().unwrap();
     }
 
+    #[test]
+    fn update_groups_dry_run_does_not_change_settings() {
+        let temp_dir = TempDir::new("config-app").unwrap();
+        let config = initialize_config_folder(&temp_dir);
+        let before = fs::read_to_string(&config.setting_file_path).unwrap();
+        assert_debug_snapshot!(run_update_groups(
+            &config,
+            &config.get_settings_from_file().unwrap(),
+            Some(vec!["test-1".to_string()]),
+            true,
+            true
+        ));
+        assert_debug_snapshot!(fs::read_to_string(&config.setting_file_path).unwrap() == before);
+        temp_dir.close().unwrap();
+    }
+
+    #[test]
+    fn can_render_settings_diff_lines() {
+        assert_debug_snapshot!(render_settings_diff_lines(
+            "challenge: Math\nincludes:\n- base\nnetwork: false\n",
+            "challenge: Math\nincludes:\n- base\n- kubernetes\nnetwork: false\n"
+        ));
+        assert_debug_snapshot!(render_settings_diff_lines(
+            "includes:\n- base\n- heroku\n",
+            "includes:\n- base\n"
+        ));
+        assert_debug_snapshot!(render_settings_diff_lines(
+            "includes:\n- base\n",
+            "includes:\n- base\n"
+        ));
+    }
+
     #[test]
     fn can_run_reset() {
         let temp_dir = TempDir::new("config-app").unwrap();
         let config = initialize_config_folder(&temp_dir);
         config.update_challenge(Challenge::Yes).unwrap();
         assert_debug_snapshot!(run_reset(&config, Some(1)));
-        assert_debug_snapshot!(config.get_settings_from_file());
+        assert_settings_snapshot!(config.get_settings_from_file());
         temp_dir.close().unwrap();
     }
 
@@ -265,6 +679,109 @@ mod test_config_cli_command {
         temp_dir.close().unwrap();
     }
 
+    #[test]
+    fn can_run_set_enabled() {
+        let temp_dir = TempDir::new("config-app").unwrap();
+        let config = initialize_config_folder(&temp_dir);
+        assert_debug_snapshot!(run_set_enabled(&config, false));
+        assert_debug_snapshot!(config.is_enabled());
+        assert_debug_snapshot!(run_set_enabled(&config, true));
+        assert_debug_snapshot!(config.is_enabled());
+        temp_dir.close().unwrap();
+    }
+
+    #[test]
+    fn can_run_doctor() {
+        let temp_dir = TempDir::new("config-app").unwrap();
+        let config = initialize_config_folder(&temp_dir);
+
+        let custom_checks_dir = std::path::Path::new(&config.root_folder).join("checks");
+        fs::create_dir_all(&custom_checks_dir).unwrap();
+        fs::write(
+            custom_checks_dir.join("custom.yaml"),
+            "- id: custom-ok\n  test: terraform destroy\n  description: destroys infra\n  from: custom\n",
+        )
+        .unwrap();
+
+        assert_debug_snapshot!(run_doctor(&config));
+
+        fs::write(custom_checks_dir.join("broken.yaml"), ": not a list :").unwrap();
+        with_settings!({filters => vec![
+            (r"/.*broken.yaml", "broken.yaml"),
+            (r"error: .+", "error message"),
+        ]}, {
+            assert_debug_snapshot!(run_doctor(&config).map(|e| e.code));
+        });
+        temp_dir.close().unwrap();
+    }
+
+    #[test]
+    fn can_render_checks_lines() {
+        let checks: Vec<shellfirm::checks::Check> = serde_yaml::from_str(
+            r"
+- id: fs:recursively_delete
+  test: rm -rf
+  description: deletes everything
+  from: fs
+  tags: [data-loss, irreversible]
+- id: git:force_push
+  test: git push --force
+  description: rewrites remote history
+  from: git
+",
+        )
+        .unwrap();
+        assert_debug_snapshot!(render_checks_lines(&checks, None));
+        assert_debug_snapshot!(render_checks_lines(&checks, Some("irreversible")));
+        assert_debug_snapshot!(render_checks_lines(&checks, Some("soc2-cc7")));
+    }
+
+    #[test]
+    fn can_resolve_edit_target() {
+        let temp_dir = TempDir::new("config-app").unwrap();
+        let config = initialize_config_folder(&temp_dir);
+
+        let (target, path) = resolve_edit_target(&config, "settings").unwrap();
+        assert_debug_snapshot!((
+            target,
+            path == std::path::Path::new(&config.setting_file_path)
+        ));
+        let (target, path) = resolve_edit_target(&config, "policy").unwrap();
+        assert_debug_snapshot!((target, path.ends_with(shellfirm::policy::POLICY_FILE_NAME)));
+        let (target, path) = resolve_edit_target(&config, "team").unwrap();
+        assert_debug_snapshot!((target, path.ends_with("checks/team.yaml")));
+        let (target, path) = resolve_edit_target(&config, "team.yml").unwrap();
+        assert_debug_snapshot!((target, path.ends_with("checks/team.yml")));
+        temp_dir.close().unwrap();
+    }
+
+    #[test]
+    fn can_validate_edit_content() {
+        let temp_dir = TempDir::new("config-app").unwrap();
+        let config = initialize_config_folder(&temp_dir);
+        let settings_content = fs::read_to_string(&config.setting_file_path).unwrap();
+
+        assert_debug_snapshot!(
+            validate_edit_content(EditTarget::Settings, &settings_content).is_ok()
+        );
+        assert_debug_snapshot!(validate_edit_content(EditTarget::Settings, "challenge: [").is_err());
+        assert_debug_snapshot!(validate_edit_content(
+            EditTarget::CustomChecks,
+            "- id: custom:example\n  test: terraform destroy\n  description: destroys infra\n  from: custom\n"
+        )
+        .is_ok());
+        assert_debug_snapshot!(
+            validate_edit_content(EditTarget::CustomChecks, "not a list").is_err()
+        );
+        assert_debug_snapshot!(validate_edit_content(
+            EditTarget::Policy,
+            "deny_tags: [irreversible]\ntests:\n- command: rm -rf /\n  expect: deny\n"
+        )
+        .is_ok());
+        assert_debug_snapshot!(validate_edit_content(EditTarget::Policy, "extends: nope").is_err());
+        temp_dir.close().unwrap();
+    }
+
     #[test]
     fn can_run_ignore() {
         let temp_dir = TempDir::new("config-app").unwrap();
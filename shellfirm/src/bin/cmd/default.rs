@@ -21,4 +21,14 @@ pub fn command() -> Command<'static> {
                 .ignore_case(true)
                 .takes_value(true),
         )
+        .arg(
+            Arg::new("no-config")
+                .long("no-config")
+                .help(
+                    "Portable mode: run from the compiled-in defaults without touching the \
+                     config folder (also enabled with SHELLFIRM_PORTABLE=1)",
+                )
+                .global(true)
+                .takes_value(false),
+        )
 }
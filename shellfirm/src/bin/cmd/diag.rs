@@ -0,0 +1,105 @@
+use anyhow::Result;
+use clap::{crate_version, App, AppSettings::ArgRequiredElseHelp, ArgMatches, Command};
+use shellfirm::{checks, diagnostics, Config};
+
+pub fn command() -> Command<'static> {
+    Command::new("diag")
+        .about("Diagnostics for bug reports")
+        .setting(ArgRequiredElseHelp)
+        .subcommand(
+            App::new("bundle")
+                .about("Collect crash reports and doctor output into a single file to attach"),
+        )
+}
+
+pub fn run(arg_matches: &ArgMatches, config: &Config) -> Result<shellfirm::CmdExit> {
+    match arg_matches.subcommand() {
+        Some(("bundle", _subcommand_matches)) => run_bundle(config),
+        _ => unreachable!(),
+    }
+}
+
+fn run_bundle(config: &Config) -> Result<shellfirm::CmdExit> {
+    let doctor = super::config::run_doctor(config)?
+        .message
+        .unwrap_or_default();
+    let crash_reports: Vec<(String, String)> = diagnostics::crash_report_files(&config.root_folder)
+        .into_iter()
+        .map(|file| {
+            let content = std::fs::read_to_string(format!("{}/{file}", config.root_folder))
+                .unwrap_or_else(|err| format!("could not read: {err}"));
+            (file, content)
+        })
+        .collect();
+
+    let path = format!("{}/diag-bundle.txt", config.root_folder);
+    std::fs::write(
+        &path,
+        render_bundle(
+            crate_version!(),
+            &checks::bundle_hash(),
+            &doctor,
+            &crash_reports,
+        ),
+    )?;
+    Ok(shellfirm::CmdExit {
+        code: exitcode::OK,
+        message: Some(format!(
+            "diagnostics bundle written to {path} — review it before attaching to a bug report"
+        )),
+    })
+}
+
+/// Render the diagnostics bundle content.
+///
+/// # Arguments
+///
+/// * `version` - crate version.
+/// * `bundle_hash` - content hash of the compiled-in checks bundle.
+/// * `doctor` - output of `shellfirm config doctor`.
+/// * `crash_reports` - crash report file names and their content.
+fn render_bundle(
+    version: &str,
+    bundle_hash: &str,
+    doctor: &str,
+    crash_reports: &[(String, String)],
+) -> String {
+    let mut sections = vec![
+        format!("shellfirm {version}\nchecks bundle hash: {bundle_hash}"),
+        format!("## config doctor\n{doctor}"),
+    ];
+    if crash_reports.is_empty() {
+        sections.push("## crash reports\nnone".to_string());
+    } else {
+        for (file, content) in crash_reports {
+            sections.push(format!("## crash report: {file}\n{content}"));
+        }
+    }
+    sections.join("\n\n")
+}
+
+#[cfg(test)]
+mod test_diag_cli_command {
+    use insta::assert_debug_snapshot;
+
+    use super::*;
+
+    #[test]
+    fn can_render_bundle() {
+        assert_debug_snapshot!(render_bundle(
+            "1.0.0",
+            "abc123",
+            "custom checks loaded: 0\nno problems found",
+            &[]
+        ));
+        assert_debug_snapshot!(render_bundle(
+            "1.0.0",
+            "abc123",
+            "custom checks loaded: 0\nno problems found",
+            &[(
+                "crash-1.txt".to_string(),
+                "shellfirm crash report\nversion: 1.0.0".to_string()
+            )]
+        ));
+    }
+}
@@ -0,0 +1,87 @@
+use anyhow::Result;
+use clap::{Arg, ArgMatches, Command};
+use shellfirm::checks::{self, Check};
+
+pub fn command() -> Command<'static> {
+    Command::new("explain")
+        .about("Show the full rationale, examples and tuning options of a check.")
+        .arg(
+            Arg::new("check-id")
+                .help("Check id, e.g. fs:recursively_delete")
+                .required(true)
+                .takes_value(true),
+        )
+}
+
+pub fn run(arg_matches: &ArgMatches, checks: &[Check]) -> Result<shellfirm::CmdExit> {
+    let id = arg_matches.value_of("check-id").unwrap_or("");
+    // active checks first, so custom checks win; fall back to the full
+    // bundle so checks of disabled groups can be explained too
+    let all_checks = checks::get_all()?;
+    let Some(check) = checks
+        .iter()
+        .chain(all_checks.iter())
+        .find(|check| check.id == id)
+    else {
+        return Ok(shellfirm::CmdExit {
+            code: exitcode::DATAERR,
+            message: Some(format!("check `{id}` not found")),
+        });
+    };
+
+    Ok(shellfirm::CmdExit {
+        code: exitcode::OK,
+        message: Some(render_explain_lines(check).join("\n")),
+    })
+}
+
+/// Render the explain report lines for the given check.
+fn render_explain_lines(check: &Check) -> Vec<String> {
+    let mut lines = vec![
+        format!(
+            "{} (group {}, severity {:?})",
+            check.id, check.from, check.severity
+        ),
+        format!("pattern: {}", check.test),
+        check.description.to_string(),
+    ];
+    if let Some(explanation) = &check.explanation {
+        lines.push(explanation.to_string());
+    }
+    if let Some(url) = &check.docs_url {
+        lines.push(format!("docs: {url}"));
+    }
+    if !check.examples.is_empty() {
+        lines.push("examples:".to_string());
+        for example in &check.examples {
+            let verdict = if check.test.is_match(example) {
+                "matches"
+            } else {
+                "does not match"
+            };
+            lines.push(format!("  {example}  -> {verdict}"));
+        }
+    }
+    lines.push(format!(
+        "tuning: add `{}` to `ignores_patterns_ids` to disable this check, or to `deny_patterns_ids` to deny it outright",
+        check.id
+    ));
+    lines
+}
+
+#[cfg(test)]
+mod test_explain_cli_command {
+    use insta::assert_debug_snapshot;
+
+    use super::*;
+
+    #[test]
+    fn can_render_explain_lines() {
+        let all_checks = checks::get_all().unwrap();
+        let check = all_checks
+            .iter()
+            .find(|check| check.id == "fs:recursively_delete")
+            .unwrap();
+        assert_debug_snapshot!(render_explain_lines(check));
+    }
+}
@@ -0,0 +1,297 @@
+use std::{fs, path::PathBuf, str::FromStr};
+
+use anyhow::{anyhow, Result};
+use clap::{Arg, ArgMatches, Command};
+use shellfirm::{checks::Severity, dialog, hook, Challenge, Config};
+use strum::IntoEnumIterator;
+
+pub fn command() -> Command<'static> {
+    Command::new("init")
+        .about("Install the shellfirm hook into your shell rc file.")
+        .arg(
+            Arg::new("shell")
+                .help("Shell to install the hook for")
+                .possible_values(["bash", "zsh", "fish"])
+                .required(true),
+        )
+        .arg(
+            Arg::new("rc-file")
+                .long("rc-file")
+                .help("Path of the rc file to install into (defaults per shell)")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::new("as-plugin")
+                .long("as-plugin")
+                .help("Write a plugin-manager compatible directory (zsh only) instead of touching the rc file")
+                .value_name("DIR")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::new("dry-run")
+                .long("dry-run")
+                .help("Only print what would change")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::new("setup")
+                .long("setup")
+                .help(
+                    "Walk through check groups, challenge and minimum severity after installing \
+                     the hook",
+                )
+                .takes_value(false),
+        )
+}
+
+pub fn run(arg_matches: &ArgMatches, config: &Config) -> Result<shellfirm::CmdExit> {
+    let shell = hook::Shell::from_str(arg_matches.value_of("shell").unwrap_or(""))?;
+
+    if let Some(plugin_dir) = arg_matches.value_of("as-plugin") {
+        if shell != hook::Shell::Zsh {
+            return Err(anyhow!("--as-plugin is only supported for zsh"));
+        }
+        let written = hook::write_zsh_plugin_dir(std::path::Path::new(plugin_dir))?;
+        return Ok(shellfirm::CmdExit {
+            code: exitcode::OK,
+            message: Some(format!(
+                "shellfirm zsh plugin written:\n{}\nload it with your plugin manager (e.g. add `{}` to your oh-my-zsh custom plugins)",
+                written.join("\n"),
+                plugin_dir
+            )),
+        });
+    }
+
+    let rc_file = match arg_matches.value_of("rc-file") {
+        Some(path) => PathBuf::from(path),
+        None => default_rc_file(shell)?,
+    };
+    let dry_run = arg_matches.is_present("dry-run");
+
+    let rc_content = fs::read_to_string(&rc_file).unwrap_or_default();
+
+    // keep the plugin content in the config folder so the rc file only
+    // carries a single source line
+    let plugin_path = PathBuf::from(&config.root_folder).join(shell.plugin_file_name());
+    let plugin_content = hook::select_plugin(shell, &rc_content);
+
+    let (new_rc_content, analysis) =
+        hook::install_hook(&rc_content, &plugin_path.display().to_string());
+
+    for warning in &analysis.warnings {
+        eprintln!("warning: {warning}");
+    }
+
+    if dry_run {
+        return Ok(shellfirm::CmdExit {
+            code: exitcode::OK,
+            message: Some(format!(
+                "dry run, {} would change to:\n{}",
+                rc_file.display(),
+                new_rc_content
+            )),
+        });
+    }
+
+    fs::write(&plugin_path, plugin_content)?;
+    fs::write(&rc_file, new_rc_content)?;
+
+    if arg_matches.is_present("setup") {
+        run_interactive_setup(config, None)?;
+        return Ok(shellfirm::CmdExit {
+            code: exitcode::OK,
+            message: Some(format!(
+                "shellfirm hook installed in {}. restart your shell to activate it",
+                rc_file.display()
+            )),
+        });
+    }
+
+    // first run: enable the check groups matching the tools on this machine,
+    // keeping whatever groups are already enabled
+    let mut groups = config.get_settings_from_file()?.get_active_groups().clone();
+    for group in
+        shellfirm::detect_include_groups(&shellfirm::environment::SystemEnvironment::default())
+    {
+        if !groups.contains(&group) {
+            groups.push(group);
+        }
+    }
+    config.update_check_groups(groups.clone())?;
+
+    Ok(shellfirm::CmdExit {
+        code: exitcode::OK,
+        message: Some(format!(
+            "shellfirm hook installed in {}. restart your shell to activate it\nenabled check groups: {}",
+            rc_file.display(),
+            groups.join(", ")
+        )),
+    })
+}
+
+/// Selections of the one-pass interactive setup, overridable in tests.
+pub struct SetupSelections {
+    /// Check groups to enable.
+    pub groups: Vec<String>,
+    /// Challenge prompted on a risky command.
+    pub challenge: Challenge,
+    /// Minimum intercepted severity (`None` intercepts everything).
+    pub min_severity: Option<Severity>,
+}
+
+/// One-pass interactive configuration: check groups (pre-checked from the
+/// detected tooling), challenge and minimum severity, so the first run does
+/// not need any YAML editing afterwards.
+pub fn run_interactive_setup(config: &Config, forced: Option<SetupSelections>) -> Result<()> {
+    let selections = if let Some(forced) = forced {
+        forced
+    } else {
+        let all_groups: Vec<String> = super::config::ALL_GROUP_CHECKS
+            .iter()
+            .map(|f| (*f).to_string())
+            .collect();
+        let detected =
+            shellfirm::detect_include_groups(&shellfirm::environment::SystemEnvironment::default());
+        let groups = dialog::multi_choice("select check groups", all_groups, detected, 100)?;
+        let challenges = Challenge::iter().map(|c| c.to_string()).collect::<Vec<_>>();
+        let challenge = Challenge::from_string(&dialog::select("select challenge", &challenges)?)?;
+        let severities: Vec<String> = ["All", "Low", "Medium", "High", "Critical"]
+            .iter()
+            .map(|s| (*s).to_string())
+            .collect();
+        let min_severity =
+            match dialog::select("minimum severity to intercept", &severities)?.as_str() {
+                "Low" => Some(Severity::Low),
+                "Medium" => Some(Severity::Medium),
+                "High" => Some(Severity::High),
+                "Critical" => Some(Severity::Critical),
+                _ => None,
+            };
+        SetupSelections {
+            groups,
+            challenge,
+            min_severity,
+        }
+    };
+    config.update_check_groups(selections.groups)?;
+    config.update_challenge(selections.challenge)?;
+    config.update_min_severity(selections.min_severity)?;
+    Ok(())
+}
+
+/// Return the default rc file of the given shell.
+fn default_rc_file(shell: hook::Shell) -> Result<PathBuf> {
+    let home = dirs::home_dir().ok_or_else(|| anyhow!("could not get home directory"))?;
+    Ok(match shell {
+        hook::Shell::Bash => home.join(".bashrc"),
+        hook::Shell::Zsh => match std::env::var("ZDOTDIR") {
+            Ok(zdotdir) => PathBuf::from(zdotdir).join(".zshrc"),
+            Err(_) => home.join(".zshrc"),
+        },
+        hook::Shell::Fish => home.join(".config").join("fish").join("config.fish"),
+    })
+}
+
+#[cfg(test)]
+mod test_init_cli_command {
+    use insta::assert_debug_snapshot;
+    use tempdir::TempDir;
+
+    use super::*;
+
+    #[test]
+    fn can_install_hook_into_rc_file() {
+        let temp_dir = TempDir::new("init-rc").unwrap();
+        let config = Config::new(Some(&temp_dir.path().join("app").display().to_string())).unwrap();
+        let rc_file = temp_dir.path().join(".zshrc");
+        fs::write(&rc_file, "alias ll='ls -la'\n").unwrap();
+
+        let app = command();
+        let matches = app.get_matches_from(vec![
+            "init",
+            "zsh",
+            "--rc-file",
+            &rc_file.display().to_string(),
+        ]);
+        let result = run(&matches, &config).unwrap();
+        assert_debug_snapshot!(result.code);
+
+        let rc_content = fs::read_to_string(&rc_file).unwrap();
+        assert_debug_snapshot!(rc_content.contains(hook::HOOK_MARKER));
+        assert_debug_snapshot!(PathBuf::from(&config.root_folder)
+            .join("shellfirm.plugin.zsh")
+            .is_file());
+        temp_dir.close().unwrap();
+    }
+
+    #[test]
+    fn can_write_zsh_plugin_dir() {
+        let temp_dir = TempDir::new("init-plugin").unwrap();
+        let config = Config::new(Some(&temp_dir.path().join("app").display().to_string())).unwrap();
+        let plugin_dir = temp_dir.path().join("shellfirm");
+
+        let app = command();
+        let matches = app.get_matches_from(vec![
+            "init",
+            "zsh",
+            "--as-plugin",
+            &plugin_dir.display().to_string(),
+        ]);
+        let result = run(&matches, &config).unwrap();
+        assert_debug_snapshot!(result.code);
+        assert_debug_snapshot!(plugin_dir.join("shellfirm.plugin.zsh").is_file());
+        assert_debug_snapshot!(plugin_dir.join("_shellfirm").is_file());
+        temp_dir.close().unwrap();
+    }
+
+    #[test]
+    fn as_plugin_rejects_non_zsh() {
+        let temp_dir = TempDir::new("init-plugin").unwrap();
+        let config = Config::new(Some(&temp_dir.path().join("app").display().to_string())).unwrap();
+
+        let app = command();
+        let matches = app.get_matches_from(vec!["init", "bash", "--as-plugin", "/tmp/x"]);
+        assert_debug_snapshot!(run(&matches, &config).is_err());
+        temp_dir.close().unwrap();
+    }
+
+    #[test]
+    fn can_run_interactive_setup_with_forced_selections() {
+        let temp_dir = TempDir::new("init-setup").unwrap();
+        let config = Config::new(Some(&temp_dir.path().join("app").display().to_string())).unwrap();
+
+        run_interactive_setup(
+            &config,
+            Some(SetupSelections {
+                groups: vec!["base".to_string(), "kubernetes".to_string()],
+                challenge: Challenge::Yes,
+                min_severity: Some(Severity::High),
+            }),
+        )
+        .unwrap();
+
+        let settings = config.get_settings_from_file().unwrap();
+        assert_debug_snapshot!((settings.includes, settings.challenge, settings.min_severity));
+        temp_dir.close().unwrap();
+    }
+
+    #[test]
+    fn dry_run_does_not_change_rc_file() {
+        let temp_dir = TempDir::new("init-rc").unwrap();
+        let config = Config::new(Some(&temp_dir.path().join("app").display().to_string())).unwrap();
+        let rc_file = temp_dir.path().join(".bashrc");
+        fs::write(&rc_file, "alias ll='ls -la'\n").unwrap();
+
+        let app = command();
+        let matches = app.get_matches_from(vec![
+            "init",
+            "bash",
+            "--rc-file",
+            &rc_file.display().to_string(),
+            "--dry-run",
+        ]);
+        run(&matches, &config).unwrap();
+        assert_debug_snapshot!(fs::read_to_string(&rc_file).unwrap());
+        temp_dir.close().unwrap();
+    }
+}
@@ -0,0 +1,135 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::Result;
+use clap::{Arg, ArgMatches, Command};
+use shellfirm::{checks, checks::Check, Config, LastCommand};
+
+pub fn command() -> Command<'static> {
+    Command::new("last")
+        .about("Show the most recent intercepted command with its analysis.")
+        .arg(
+            Arg::new("edit")
+                .long("edit")
+                .help("Open the command in $EDITOR and print the edited version")
+                .takes_value(false),
+        )
+}
+
+pub fn run(
+    arg_matches: &ArgMatches,
+    config: &Config,
+    checks: &[Check],
+) -> Result<shellfirm::CmdExit> {
+    let Some(last_command) = config.get_last_command()? else {
+        return Ok(shellfirm::CmdExit {
+            code: exitcode::OK,
+            message: Some("no intercepted command recorded yet".to_string()),
+        });
+    };
+
+    if arg_matches.is_present("edit") {
+        let edited = edit_command(&last_command.command)?;
+        return Ok(shellfirm::CmdExit {
+            code: exitcode::OK,
+            // stdout only carries the edited command, so it can feed the
+            // shell buffer
+            message: Some(edited.trim_end().to_string()),
+        });
+    }
+
+    let matches = checks::run_check_on_command(checks, &last_command.command);
+    Ok(shellfirm::CmdExit {
+        code: exitcode::OK,
+        message: Some(render_report_lines(&last_command, &matches, now()).join("\n")),
+    })
+}
+
+/// Open the command in `$EDITOR` (falling back to `vi`) and return the edited
+/// content.
+fn edit_command(command: &str) -> Result<String> {
+    let editor = std::env::var("EDITOR").unwrap_or_else(|_| "vi".to_string());
+    let path = std::env::temp_dir().join(format!("shellfirm-last-{}", std::process::id()));
+    std::fs::write(&path, command)?;
+    std::process::Command::new(&editor).arg(&path).status()?;
+    let edited = std::fs::read_to_string(&path)?;
+    let _ = std::fs::remove_file(&path);
+    Ok(edited)
+}
+
+/// Seconds since epoch.
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+/// Render the last-command report lines.
+///
+/// # Arguments
+///
+/// * `last_command` - the recorded command.
+/// * `matches` - checks matching the command with the current configuration.
+/// * `now` - current time in seconds since epoch.
+fn render_report_lines(last_command: &LastCommand, matches: &[Check], now: u64) -> Vec<String> {
+    let mut lines = vec![
+        format!("command: {}", last_command.command),
+        format!(
+            "intercepted: {} seconds ago",
+            now.saturating_sub(last_command.intercepted_at)
+        ),
+    ];
+    if matches.is_empty() {
+        lines.push("no check matches it with the current configuration".to_string());
+    } else {
+        lines.push("matched checks:".to_string());
+        for check in matches {
+            lines.push(format!("  * {} - {}", check.id, check.description));
+        }
+    }
+    lines
+}
+
+#[cfg(test)]
+mod test_last_cli_command {
+    use insta::assert_debug_snapshot;
+    use shellfirm::Settings;
+    use tempdir::TempDir;
+
+    use super::*;
+
+    #[test]
+    fn can_render_report_lines() {
+        let last_command = LastCommand {
+            command: "rm -rf /".to_string(),
+            check_ids: vec!["fs:recursively_delete".to_string()],
+            intercepted_at: 90,
+        };
+        let checks: Vec<Check> = serde_yaml::from_str(
+            r"
+- id: fs:recursively_delete
+  test: rm.+-rf
+  description: deletes everything
+  from: fs
+",
+        )
+        .unwrap();
+        assert_debug_snapshot!(render_report_lines(&last_command, &checks, 100));
+        assert_debug_snapshot!(render_report_lines(&last_command, &[], 100));
+    }
+
+    #[test]
+    fn can_run_last_without_recorded_command() {
+        let temp_dir = TempDir::new("config-app").unwrap();
+        let config = Config::new(Some(&temp_dir.path().join("app").display().to_string())).unwrap();
+        let checks = config
+            .get_settings_from_file()
+            .map(|settings: Settings| settings.get_active_checks().unwrap())
+            .unwrap();
+
+        let app = command();
+        let matches = app.get_matches_from(vec!["last"]);
+        assert_debug_snapshot!(run(&matches, &config, &checks));
+        temp_dir.close().unwrap();
+    }
+}
@@ -0,0 +1,32 @@
+use anyhow::Result;
+use clap::{ArgMatches, Command};
+use shellfirm::{oidc, Config, Settings};
+
+pub fn command() -> Command<'static> {
+    Command::new("login").about("Log in via the configured OIDC provider (device code flow)")
+}
+
+pub fn run(
+    _matches: &ArgMatches,
+    config: &Config,
+    settings: &Settings,
+) -> Result<shellfirm::CmdExit> {
+    let Some(oidc_settings) = &settings.oidc else {
+        return Ok(shellfirm::CmdExit {
+            code: exitcode::CONFIG,
+            message: Some(
+                "no identity provider configured (set `oidc` in the settings file)".to_string(),
+            ),
+        });
+    };
+    let identity = oidc::login(oidc_settings, settings.network)?;
+    oidc::save(&config.identity_file_path(), &identity)?;
+    Ok(shellfirm::CmdExit {
+        code: exitcode::OK,
+        message: Some(format!(
+            "logged in as {} (roles: {})",
+            identity.subject,
+            identity.roles.join(", ")
+        )),
+    })
+}
@@ -1,3 +1,21 @@
+pub mod alias;
+pub mod analyze;
+pub mod audit;
+pub mod capture;
 pub mod command;
 pub mod config;
 pub mod default;
+pub mod diag;
+pub mod explain;
+pub mod init;
+pub mod last;
+pub mod login;
+pub mod policy;
+pub mod prompt_segment;
+pub mod replay;
+pub mod scan;
+pub mod status;
+pub mod tmux;
+pub mod totp;
+pub mod try_sandbox;
+pub mod version;
@@ -0,0 +1,147 @@
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+use clap::{App, AppSettings::ArgRequiredElseHelp, Arg, ArgMatches, Command};
+use shellfirm::{
+    environment::SystemEnvironment,
+    policy::{self, PolicyTestResult},
+    Settings,
+};
+
+pub fn command() -> Command<'static> {
+    Command::new("policy")
+        .about("Work with project policy files")
+        .setting(ArgRequiredElseHelp)
+        .subcommand(
+            App::new("test")
+                .about("Run the tests declared in a policy file")
+                .arg(
+                    Arg::new("path")
+                        .long("path")
+                        .help("Policy file to test. Defaults to ./.shellfirm.yaml")
+                        .takes_value(true),
+                ),
+        )
+}
+
+pub fn run(matches: &ArgMatches, settings: &Settings) -> Result<shellfirm::CmdExit> {
+    match matches.subcommand() {
+        Some(("test", subcommand_matches)) => {
+            run_test(subcommand_matches.value_of("path"), settings)
+        }
+        _ => Err(anyhow!("command not found")),
+    }
+}
+
+pub fn run_test(path: Option<&str>, settings: &Settings) -> Result<shellfirm::CmdExit> {
+    let path = Path::new(path.unwrap_or(policy::POLICY_FILE_NAME));
+    let policy = policy::load(path, settings.network)?;
+
+    let environment = SystemEnvironment::with_timeout(std::time::Duration::from_millis(
+        settings.max_subprocess_latency_ms,
+    ));
+    let results = policy::run_tests(&policy, settings, &environment)?;
+    let all_passed = results.iter().all(PolicyTestResult::passed);
+
+    Ok(shellfirm::CmdExit {
+        code: if all_passed {
+            exitcode::OK
+        } else {
+            exitcode::DATAERR
+        },
+        message: Some(render_test_lines(&results).join("\n")),
+    })
+}
+
+/// Render the policy test report lines.
+fn render_test_lines(results: &[PolicyTestResult]) -> Vec<String> {
+    if results.is_empty() {
+        return vec!["no policy tests found".to_string()];
+    }
+    let mut lines: Vec<String> = results
+        .iter()
+        .map(|result| {
+            if result.passed() {
+                format!("PASS {}", result.command)
+            } else {
+                format!(
+                    "FAIL {} (expected {:?}, got {:?})",
+                    result.command, result.expected, result.actual
+                )
+            }
+        })
+        .collect();
+    lines.push(format!(
+        "{}/{} policy tests passed",
+        results.iter().filter(|r| r.passed()).count(),
+        results.len()
+    ));
+    lines
+}
+
+#[cfg(test)]
+mod test_policy_cli_command {
+
+    use insta::assert_debug_snapshot;
+    use shellfirm::Config;
+    use tempdir::TempDir;
+
+    use super::*;
+
+    fn initialize_config_folder(temp_dir: &TempDir) -> Config {
+        let temp_dir = temp_dir.path().join("app");
+        Config::new(Some(&temp_dir.display().to_string())).unwrap()
+    }
+
+    #[test]
+    fn can_run_policy_tests() {
+        let temp_dir = TempDir::new("config-app").unwrap();
+        let settings = initialize_config_folder(&temp_dir)
+            .get_settings_from_file()
+            .unwrap();
+
+        let policy_path = temp_dir.path().join(".shellfirm.yaml");
+        std::fs::write(
+            &policy_path,
+            r"
+deny_patterns_ids:
+  - fs:recursively_chmod
+tests:
+  - command: rm -rf /
+    expect: challenge
+  - command: chmod -R /tmp/scratch
+    expect: deny
+  - command: ls -la
+    expect: allow
+",
+        )
+        .unwrap();
+
+        assert_debug_snapshot!(run_test(
+            Some(&policy_path.display().to_string()),
+            &settings
+        ));
+        temp_dir.close().unwrap();
+    }
+
+    #[test]
+    fn failing_policy_tests_change_the_exit_code() {
+        let temp_dir = TempDir::new("config-app").unwrap();
+        let settings = initialize_config_folder(&temp_dir)
+            .get_settings_from_file()
+            .unwrap();
+
+        let policy_path = temp_dir.path().join(".shellfirm.yaml");
+        std::fs::write(
+            &policy_path,
+            "tests:\n  - command: ls -la\n    expect: deny\n",
+        )
+        .unwrap();
+
+        assert_debug_snapshot!(run_test(
+            Some(&policy_path.display().to_string()),
+            &settings
+        ));
+        temp_dir.close().unwrap();
+    }
+}
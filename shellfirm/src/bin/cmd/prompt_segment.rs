@@ -0,0 +1,73 @@
+use anyhow::Result;
+use clap::{Arg, ArgMatches, Command};
+use shellfirm::{Config, Settings};
+
+pub fn command() -> Command<'static> {
+    Command::new("prompt-segment")
+        .about("Print a short protection status for shell prompt integration (e.g. starship).")
+        .arg(
+            Arg::new("format")
+                .long("format")
+                .help("Output format")
+                .possible_values(["starship", "plain"])
+                .default_value("plain")
+                .takes_value(true),
+        )
+}
+
+pub fn run(
+    arg_matches: &ArgMatches,
+    config: &Config,
+    _settings: &Settings,
+) -> Result<shellfirm::CmdExit> {
+    let format = arg_matches.value_of("format").unwrap_or("plain");
+    println!(
+        "{}",
+        render_segment(format, config.is_enabled(), config.get_block_count_today())
+    );
+    Ok(shellfirm::CmdExit {
+        code: exitcode::OK,
+        message: None,
+    })
+}
+
+/// Render the prompt segment.
+///
+/// # Arguments
+///
+/// * `format` - output format (starship / plain).
+/// * `enabled` - is command interception turned on.
+/// * `blocks_today` - how many commands were intercepted today.
+fn render_segment(format: &str, enabled: bool, blocks_today: u64) -> String {
+    let status = if enabled {
+        if blocks_today > 0 {
+            format!("🛡 {blocks_today}")
+        } else {
+            "🛡".to_string()
+        }
+    } else {
+        "🛡 off".to_string()
+    };
+
+    match format {
+        // starship custom commands apply their own style, emit plain text
+        "starship" => status,
+        _ => format!("shellfirm: {status}"),
+    }
+}
+
+#[cfg(test)]
+mod test_prompt_segment_cli_command {
+    use insta::assert_debug_snapshot;
+
+    use super::*;
+
+    #[test]
+    fn can_render_segment() {
+        assert_debug_snapshot!(render_segment("starship", true, 0));
+        assert_debug_snapshot!(render_segment("starship", true, 3));
+        assert_debug_snapshot!(render_segment("starship", false, 3));
+        assert_debug_snapshot!(render_segment("plain", true, 2));
+        assert_debug_snapshot!(render_segment("plain", false, 0));
+    }
+}
@@ -0,0 +1,95 @@
+use anyhow::Result;
+use clap::{Arg, ArgMatches, Command};
+use shellfirm::{capture::CaptureBundle, checks, checks::Check};
+
+pub fn command() -> Command<'static> {
+    Command::new("replay")
+        .about("Re-run the analysis of a captured repro bundle against its recorded environment.")
+        .arg(
+            Arg::new("bundle")
+                .help("Path of a bundle created by `shellfirm capture`")
+                .required(true)
+                .takes_value(true),
+        )
+}
+
+pub fn run(arg_matches: &ArgMatches, checks: &[Check]) -> Result<shellfirm::CmdExit> {
+    let path = arg_matches.value_of("bundle").unwrap_or("");
+    let bundle: CaptureBundle = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+
+    let environment = bundle.environment.to_mock();
+    let replayed_ids: Vec<String> = checks::analyze_command(checks, &bundle.command, &environment)
+        .matches
+        .into_iter()
+        .map(|check| check.id)
+        .collect();
+
+    Ok(shellfirm::CmdExit {
+        code: exitcode::OK,
+        message: Some(
+            render_replay_lines(&bundle, &replayed_ids, &checks::bundle_hash()).join("\n"),
+        ),
+    })
+}
+
+/// Render the replay report lines.
+///
+/// # Arguments
+///
+/// * `bundle` - the captured bundle.
+/// * `replayed_ids` - check ids matching the bundle command now.
+/// * `current_bundle_hash` - hash of the checks bundle replaying the bundle.
+fn render_replay_lines(
+    bundle: &CaptureBundle,
+    replayed_ids: &[String],
+    current_bundle_hash: &str,
+) -> Vec<String> {
+    let mut lines = vec![
+        format!("command: {}", bundle.command),
+        format!(
+            "captured with: shellfirm {} / checks bundle {}",
+            bundle.shellfirm_version, bundle.checks_bundle_hash
+        ),
+    ];
+    if bundle.checks_bundle_hash != current_bundle_hash {
+        lines.push("note: the bundle was captured with a different checks bundle".to_string());
+    }
+    lines.push(format!("captured matches: {:?}", bundle.match_ids));
+    lines.push(format!("replayed matches: {:?}", replayed_ids));
+    if replayed_ids == bundle.match_ids {
+        lines.push("the capture reproduces".to_string());
+    } else {
+        lines.push("the capture does NOT reproduce with the current checks".to_string());
+    }
+    lines
+}
+
+#[cfg(test)]
+mod test_replay_cli_command {
+    use insta::assert_debug_snapshot;
+    use shellfirm::{capture, environment::fixtures};
+
+    use super::*;
+
+    const CHECKS: &str = r"
+- id: git:force_push
+  test: git push.+(-f|--force)
+  description: force push
+  from: git
+";
+
+    #[test]
+    fn can_render_replay_lines() {
+        let checks: Vec<Check> = serde_yaml::from_str(CHECKS).unwrap();
+        let bundle = capture::snapshot("git push --force", &checks, &fixtures::production_ssh());
+
+        insta::with_settings!({filters => vec![(r"[0-9a-f]{64}", "[CHECKS_BUNDLE_HASH]")]}, {
+            assert_debug_snapshot!(render_replay_lines(
+                &bundle,
+                &["git:force_push".to_string()],
+                &bundle.checks_bundle_hash.clone()
+            ));
+            assert_debug_snapshot!(render_replay_lines(&bundle, &[], "other-hash"));
+        });
+    }
+}
@@ -0,0 +1,236 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Result};
+use clap::{Arg, ArgMatches, Command};
+use serde_derive::Serialize;
+use shellfirm::{
+    checks::{run_check_on_command_with_environment, Check, Severity},
+    environment::{Environment, SystemEnvironment},
+    Settings,
+};
+
+/// File extensions treated as shell scripts.
+const SCRIPT_EXTENSIONS: &[&str] = &["sh", "bash", "zsh"];
+
+/// File names without an extension that still carry command lines.
+const SCRIPT_FILE_NAMES: &[&str] = &["Dockerfile", "Makefile"];
+
+pub fn command() -> Command<'static> {
+    Command::new("scan")
+        .about("Lint shell scripts (and Dockerfiles/Makefiles) for risky commands")
+        .arg(
+            Arg::new("path")
+                .help("File or directory to scan")
+                .required(true),
+        )
+        .arg(
+            Arg::new("format")
+                .long("format")
+                .help("Report format")
+                .possible_values(["text", "json"])
+                .default_value("text"),
+        )
+}
+
+pub fn run(
+    arg_matches: &ArgMatches,
+    settings: &Settings,
+    checks: &[Check],
+) -> Result<shellfirm::CmdExit> {
+    let path = Path::new(arg_matches.value_of("path").unwrap_or_default());
+    if !path.exists() {
+        bail!("path not found: {}", path.display());
+    }
+    let environment = SystemEnvironment::with_timeout(std::time::Duration::from_millis(
+        settings.max_subprocess_latency_ms,
+    ));
+
+    let mut findings: Vec<Finding> = Vec::new();
+    for file in collect_files(path) {
+        let content = std::fs::read_to_string(&file)?;
+        findings.extend(scan_content(
+            checks,
+            &file.display().to_string(),
+            &content,
+            &environment,
+        ));
+    }
+
+    let message = if arg_matches.value_of("format") == Some("json") {
+        serde_json::to_string(&findings)?
+    } else {
+        render_report_lines(&findings).join("\n")
+    };
+    Ok(shellfirm::CmdExit {
+        // a non-zero exit code on findings, so CI can gate on the scan
+        code: if findings.is_empty() {
+            exitcode::OK
+        } else {
+            exitcode::DATAERR
+        },
+        message: Some(message),
+    })
+}
+
+/// One risky command line found in a scanned file.
+#[derive(Debug, Serialize)]
+struct Finding {
+    /// The scanned file.
+    file: String,
+    /// Line number the command is on (1-based).
+    line: usize,
+    /// Id of the matched check.
+    check_id: String,
+    /// Severity of the matched check.
+    severity: Severity,
+    /// Suggested safer alternative, when the check carries one.
+    alternative: Option<String>,
+}
+
+/// Collect the scannable files under the given path: shell scripts by
+/// extension, Dockerfiles and Makefiles by name. A single file argument is
+/// scanned as-is, whatever its name.
+fn collect_files(path: &Path) -> Vec<PathBuf> {
+    if path.is_file() {
+        return vec![path.to_path_buf()];
+    }
+    let mut files: Vec<PathBuf> = Vec::new();
+    if let Ok(entries) = std::fs::read_dir(path) {
+        for entry in entries.flatten() {
+            let entry_path = entry.path();
+            if entry_path.is_dir() {
+                files.extend(collect_files(&entry_path));
+                continue;
+            }
+            let by_extension = entry_path
+                .extension()
+                .and_then(|extension| extension.to_str())
+                .is_some_and(|extension| SCRIPT_EXTENSIONS.contains(&extension));
+            let by_name = entry_path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| SCRIPT_FILE_NAMES.contains(&name));
+            if by_extension || by_name {
+                files.push(entry_path);
+            }
+        }
+    }
+    files.sort();
+    files
+}
+
+/// Run every command line of the given file content through the checks,
+/// returning the findings in line order. Comments and empty lines are
+/// skipped; a Dockerfile `RUN ` prefix is stripped so the command behind it
+/// is what gets checked.
+///
+/// # Arguments
+///
+/// * `checks` - the active checks.
+/// * `file` - the scanned file name, only recorded in the findings.
+/// * `content` - the file content.
+/// * `environment` - environment the check filters run against.
+fn scan_content(
+    checks: &[Check],
+    file: &str,
+    content: &str,
+    environment: &dyn Environment,
+) -> Vec<Finding> {
+    let mut findings: Vec<Finding> = Vec::new();
+    for (index, line) in content.lines().enumerate() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        let command = trimmed.strip_prefix("RUN ").unwrap_or(trimmed);
+        for check in run_check_on_command_with_environment(checks, command, environment) {
+            findings.push(Finding {
+                file: file.to_string(),
+                line: index + 1,
+                check_id: check.id.to_string(),
+                severity: check.severity,
+                alternative: check.alternative.clone(),
+            });
+        }
+    }
+    findings
+}
+
+/// Render the text report lines.
+///
+/// # Arguments
+///
+/// * `findings` - the collected findings.
+fn render_report_lines(findings: &[Finding]) -> Vec<String> {
+    if findings.is_empty() {
+        return vec!["no risky commands found".to_string()];
+    }
+    let mut lines: Vec<String> = findings
+        .iter()
+        .map(|finding| {
+            let mut line = format!(
+                "{}:{} {} ({:?})",
+                finding.file, finding.line, finding.check_id, finding.severity
+            );
+            if let Some(alternative) = &finding.alternative {
+                line.push_str(&format!(" — try: `{alternative}`"));
+            }
+            line
+        })
+        .collect();
+    lines.push(format!(
+        "{} risky command{} found",
+        findings.len(),
+        if findings.len() == 1 { "" } else { "s" }
+    ));
+    lines
+}
+
+#[cfg(test)]
+mod test_scan_cli_command {
+    use insta::assert_debug_snapshot;
+    use shellfirm::environment::MockEnvironment;
+
+    use super::*;
+
+    const CHECKS: &str = r"
+- id: fs:recursively_delete
+  test: rm.+-rf
+  description: deletes everything
+  from: fs
+  severity: Critical
+  alternative: rm -ri
+- id: terraform:destroy
+  test: terraform\s*destroy
+  description: destroys the managed infrastructure
+  from: terraform
+";
+
+    #[test]
+    fn can_scan_content() {
+        let checks: Vec<Check> = serde_yaml::from_str(CHECKS).unwrap();
+        let environment = MockEnvironment::default();
+        let content = "#!/bin/sh\n# cleanup\nrm -x -rf ./build\necho done\nRUN rm -q -rf /tmp/cache\nterraform destroy\n";
+        let findings = scan_content(&checks, "deploy.sh", content, &environment);
+        assert_debug_snapshot!(findings);
+        assert_debug_snapshot!(render_report_lines(&findings));
+        assert_debug_snapshot!(render_report_lines(&[]));
+    }
+
+    #[test]
+    fn can_collect_files() {
+        let temp_dir = tempdir::TempDir::new("scan").unwrap();
+        let nested = temp_dir.path().join("scripts");
+        std::fs::create_dir_all(&nested).unwrap();
+        std::fs::write(temp_dir.path().join("deploy.sh"), "").unwrap();
+        std::fs::write(temp_dir.path().join("Dockerfile"), "").unwrap();
+        std::fs::write(temp_dir.path().join("notes.txt"), "").unwrap();
+        std::fs::write(nested.join("cleanup.bash"), "").unwrap();
+        let files: Vec<String> = collect_files(temp_dir.path())
+            .iter()
+            .filter_map(|file| file.strip_prefix(temp_dir.path()).ok())
+            .map(|file| file.display().to_string())
+            .collect();
+        assert_debug_snapshot!(files);
+    }
+}
@@ -0,0 +1,112 @@
+use anyhow::Result;
+use clap::{ArgMatches, Command};
+use shellfirm::{Config, Settings};
+
+pub fn command() -> Command<'static> {
+    Command::new("status").about(
+        "Check that shellfirm is installed, the hook is loaded and commands are intercepted.",
+    )
+}
+
+pub fn run(
+    _arg_matches: &ArgMatches,
+    config: &Config,
+    settings: &Settings,
+) -> Result<shellfirm::CmdExit> {
+    let hook_loaded = std::env::var("SHELLFIRM_HOOK").is_ok();
+    let heartbeat_age = std::env::var("SHELLFIRM_SHELL_PID")
+        .ok()
+        .and_then(|pid| config.get_heartbeat_age(&pid));
+
+    let report = render_report(
+        config.is_enabled(),
+        &settings.challenge.to_string(),
+        hook_loaded,
+        heartbeat_age,
+    );
+    for line in &report {
+        eprintln!("{line}");
+    }
+
+    let healthy = config.is_enabled() && hook_loaded;
+    Ok(shellfirm::CmdExit {
+        code: if healthy {
+            exitcode::OK
+        } else {
+            exitcode::CONFIG
+        },
+        message: None,
+    })
+}
+
+/// Render the status report lines.
+///
+/// # Arguments
+///
+/// * `enabled` - is command interception turned on.
+/// * `challenge` - the active challenge name.
+/// * `hook_loaded` - was the shell plugin sourced in this session.
+/// * `heartbeat_age` - seconds since the hook of this shell last fired.
+fn render_report(
+    enabled: bool,
+    challenge: &str,
+    hook_loaded: bool,
+    heartbeat_age: Option<u64>,
+) -> Vec<String> {
+    let mut report = vec![
+        "shellfirm binary: installed".to_string(),
+        format!("challenge: {challenge}"),
+        if enabled {
+            "protection: on".to_string()
+        } else {
+            "protection: OFF (run `shellfirm config enable`)".to_string()
+        },
+    ];
+
+    if hook_loaded {
+        report.push("shell hook: loaded in this session".to_string());
+        match heartbeat_age {
+            Some(age) => report.push(format!("shell hook: firing (last heartbeat {age}s ago)")),
+            None => report.push(
+                "shell hook: no heartbeat yet in this session, type any command to verify \
+                 interception"
+                    .to_string(),
+            ),
+        }
+    } else {
+        report.push(
+            "shell hook: NOT loaded in this session. source the shellfirm plugin in your shell \
+             rc file (see https://github.com/kaplanelad/shellfirm#installation)"
+                .to_string(),
+        );
+    }
+
+    report
+}
+
+#[cfg(test)]
+mod test_status_cli_command {
+    use insta::assert_debug_snapshot;
+
+    use super::*;
+
+    #[test]
+    fn can_render_healthy_report() {
+        assert_debug_snapshot!(render_report(true, "Math", true, Some(12)));
+    }
+
+    #[test]
+    fn can_render_report_without_heartbeat() {
+        assert_debug_snapshot!(render_report(true, "Math", true, None));
+    }
+
+    #[test]
+    fn can_render_report_without_hook() {
+        assert_debug_snapshot!(render_report(true, "Math", false, None));
+    }
+
+    #[test]
+    fn can_render_disabled_report() {
+        assert_debug_snapshot!(render_report(false, "Yes", true, Some(2)));
+    }
+}
@@ -0,0 +1,50 @@
+use anyhow::Result;
+use clap::{ArgMatches, Command};
+use shellfirm::{Challenge, Config, Settings};
+
+pub fn command() -> Command<'static> {
+    Command::new("tmux-status")
+        .about("Print a compact colored status segment for tmux status-line integration.")
+}
+
+pub fn run(
+    _arg_matches: &ArgMatches,
+    config: &Config,
+    settings: &Settings,
+) -> Result<shellfirm::CmdExit> {
+    println!(
+        "{}",
+        render_segment(config.is_enabled(), &settings.challenge)
+    );
+    Ok(shellfirm::CmdExit {
+        code: exitcode::OK,
+        message: None,
+    })
+}
+
+/// Render the status segment with tmux style directives.
+///
+/// # Arguments
+///
+/// * `enabled` - is command interception turned on.
+/// * `challenge` - the active challenge type.
+fn render_segment(enabled: bool, challenge: &Challenge) -> String {
+    if enabled {
+        format!("#[fg=green]🛡 {challenge}#[default]")
+    } else {
+        "#[fg=red]🛡 off#[default]".to_string()
+    }
+}
+
+#[cfg(test)]
+mod test_tmux_cli_command {
+    use insta::assert_debug_snapshot;
+
+    use super::*;
+
+    #[test]
+    fn can_render_segment() {
+        assert_debug_snapshot!(render_segment(true, &Challenge::Math));
+        assert_debug_snapshot!(render_segment(false, &Challenge::Yes));
+    }
+}
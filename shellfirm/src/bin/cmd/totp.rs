@@ -0,0 +1,66 @@
+use anyhow::Result;
+use clap::{App, AppSettings::ArgRequiredElseHelp, ArgMatches, Command};
+use shellfirm::{totp, Config};
+
+pub fn command() -> Command<'static> {
+    Command::new("totp")
+        .about("Manage the authenticator secret of the Totp challenge")
+        .setting(ArgRequiredElseHelp)
+        .subcommand(
+            App::new("enroll").about(
+                "Generate a new secret, store it in the config dir and show the enrollment URL",
+            ),
+        )
+        .subcommand(App::new("disable").about("Remove the stored secret"))
+}
+
+pub fn run(arg_matches: &ArgMatches, config: &Config) -> Result<shellfirm::CmdExit> {
+    match arg_matches.subcommand() {
+        Some(("enroll", _subcommand_matches)) => run_enroll(config),
+        Some(("disable", _subcommand_matches)) => run_disable(config),
+        _ => unreachable!(),
+    }
+}
+
+fn run_enroll(config: &Config) -> Result<shellfirm::CmdExit> {
+    let secret = totp::generate_secret();
+    config.update_totp_secret(Some(secret.clone()))?;
+    Ok(shellfirm::CmdExit {
+        code: exitcode::OK,
+        message: Some(render_enrollment(&secret).join("\n")),
+    })
+}
+
+fn run_disable(config: &Config) -> Result<shellfirm::CmdExit> {
+    config.update_totp_secret(None)?;
+    Ok(shellfirm::CmdExit {
+        code: exitcode::OK,
+        message: Some("TOTP disabled, the secret was removed".to_string()),
+    })
+}
+
+/// Render the enrollment instructions shown after a new secret is generated.
+///
+/// # Arguments
+///
+/// * `secret` - the new base32 secret.
+fn render_enrollment(secret: &str) -> Vec<String> {
+    vec![
+        "TOTP enrolled. Add the secret to your authenticator app:".to_string(),
+        format!("  secret: {secret}"),
+        format!("  url: {}", totp::otpauth_url(secret)),
+        "then set the challenge with `shellfirm config challenge` and pick `Totp`".to_string(),
+    ]
+}
+
+#[cfg(test)]
+mod test_totp_cli_command {
+    use insta::assert_debug_snapshot;
+
+    use super::*;
+
+    #[test]
+    fn can_render_enrollment() {
+        assert_debug_snapshot!(render_enrollment("NBSWY3DPEB3W64TMMQ"));
+    }
+}
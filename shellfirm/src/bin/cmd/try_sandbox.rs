@@ -0,0 +1,173 @@
+use std::io::{BufRead, Write};
+
+use anyhow::Result;
+use clap::{Arg, ArgMatches, Command};
+use shellfirm::{
+    checks,
+    checks::Check,
+    context,
+    environment::{fixtures, Environment, SystemEnvironment},
+    Settings,
+};
+
+pub fn command() -> Command<'static> {
+    Command::new("try")
+        .about("Interactive what-if sandbox: type commands and see the analysis without executing.")
+        .arg(
+            Arg::new("context")
+                .long("context")
+                .help("Simulate another environment instead of the real one")
+                .possible_values(["local-dev", "prod-ssh"])
+                .takes_value(true),
+        )
+        .arg(
+            Arg::new("assume-context")
+                .long("assume-context")
+                .help("Force context signals, e.g. `k8s=prod,branch=main,ssh=true`")
+                .takes_value(true),
+        )
+}
+
+pub fn run(
+    arg_matches: &ArgMatches,
+    settings: &Settings,
+    checks: &[Check],
+) -> Result<shellfirm::CmdExit> {
+    let mut environment: Box<dyn Environment> = match arg_matches.value_of("context") {
+        Some("prod-ssh") => Box::new(fixtures::production_ssh()),
+        Some("local-dev") => Box::new(fixtures::local_dev()),
+        _ => Box::new(SystemEnvironment::with_timeout(
+            std::time::Duration::from_millis(settings.max_subprocess_latency_ms),
+        )),
+    };
+    if let Some(spec) = arg_matches.value_of("assume-context") {
+        environment = Box::new(context::OverriddenEnvironment::new(
+            environment,
+            context::Overrides::parse(spec)?,
+        ));
+    }
+
+    eprintln!("shellfirm sandbox: nothing you type here is executed.");
+    eprintln!("type `history` to list tried commands, `exit` to leave.");
+
+    let mut history: Vec<String> = Vec::new();
+    let stdin = std::io::stdin();
+    loop {
+        eprint!("try> ");
+        std::io::stderr().flush()?;
+
+        let mut line = String::new();
+        if stdin.lock().read_line(&mut line)? == 0 {
+            break;
+        }
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        match line {
+            "exit" | "quit" => break,
+            "history" => {
+                for (index, entry) in history.iter().enumerate() {
+                    eprintln!("{}: {}", index + 1, entry);
+                }
+            }
+            candidate => {
+                for report_line in analyze(candidate, settings, checks, environment.as_ref()) {
+                    eprintln!("{report_line}");
+                }
+                history.push(candidate.to_string());
+            }
+        }
+    }
+
+    Ok(shellfirm::CmdExit {
+        code: exitcode::OK,
+        message: None,
+    })
+}
+
+/// Analyze one candidate command and return the report lines.
+fn analyze(
+    candidate: &str,
+    settings: &Settings,
+    checks: &[Check],
+    environment: &dyn Environment,
+) -> Vec<String> {
+    let analysis = checks::analyze_command(checks, candidate, environment);
+    render_analysis_lines(
+        &analysis.matches,
+        &settings.active_deny_patterns_ids(environment),
+        &settings.challenge,
+        candidate,
+    )
+}
+
+/// Render the analysis report for one candidate command.
+///
+/// # Arguments
+///
+/// * `matches` - checks matching the candidate.
+/// * `deny_ids` - denied check ids in the simulated environment.
+/// * `challenge` - the configured challenge type.
+/// * `candidate` - the candidate command.
+fn render_analysis_lines(
+    matches: &[Check],
+    deny_ids: &[String],
+    challenge: &shellfirm::Challenge,
+    candidate: &str,
+) -> Vec<String> {
+    if matches.is_empty() {
+        return vec!["no checks matched, the command would run untouched".to_string()];
+    }
+
+    let mut lines = vec![format!("{} check(s) matched:", matches.len())];
+    for check in matches {
+        lines.push(format!(
+            "  * {} ({:?}) - {}",
+            check.id, check.severity, check.description
+        ));
+    }
+    for alternative in checks::render_alternative_lines(matches, candidate) {
+        lines.push(alternative);
+    }
+    if matches.iter().any(|c| deny_ids.contains(&c.id)) {
+        lines.push("would be: denied".to_string());
+    } else {
+        lines.push(format!("would be: challenged ({challenge})"));
+    }
+    lines
+}
+
+#[cfg(test)]
+mod test_try_cli_command {
+    use insta::assert_debug_snapshot;
+
+    use super::*;
+    use shellfirm::Challenge;
+
+    const CHECKS: &str = r"
+- id: fs:recursively_delete
+  test: rm.+-rf
+  description: deletes everything
+  from: fs
+  severity: Critical
+";
+
+    #[test]
+    fn can_render_analysis_lines() {
+        let checks: Vec<Check> = serde_yaml::from_str(CHECKS).unwrap();
+        assert_debug_snapshot!(render_analysis_lines(
+            &checks,
+            &[],
+            &Challenge::Math,
+            "rm -rf /"
+        ));
+        assert_debug_snapshot!(render_analysis_lines(
+            &checks,
+            &["fs:recursively_delete".to_string()],
+            &Challenge::Math,
+            "rm -rf /"
+        ));
+        assert_debug_snapshot!(render_analysis_lines(&[], &[], &Challenge::Math, "ls"));
+    }
+}
@@ -0,0 +1,56 @@
+use anyhow::Result;
+use clap::{crate_version, Arg, ArgMatches, Command};
+use shellfirm::checks;
+
+pub fn command() -> Command<'static> {
+    Command::new("version")
+        .about("Print the shellfirm version.")
+        .arg(
+            Arg::new("verbose")
+                .long("verbose")
+                .help("Also print the content hash of the compiled-in checks bundle")
+                .takes_value(false),
+        )
+}
+
+pub fn run(arg_matches: &ArgMatches) -> Result<shellfirm::CmdExit> {
+    for line in render_version_lines(
+        crate_version!(),
+        &checks::bundle_hash(),
+        arg_matches.is_present("verbose"),
+    ) {
+        println!("{line}");
+    }
+    Ok(shellfirm::CmdExit {
+        code: exitcode::OK,
+        message: None,
+    })
+}
+
+/// Render the version report lines.
+///
+/// # Arguments
+///
+/// * `version` - crate version.
+/// * `bundle_hash` - content hash of the compiled-in checks bundle.
+/// * `verbose` - include the checks bundle hash.
+fn render_version_lines(version: &str, bundle_hash: &str, verbose: bool) -> Vec<String> {
+    let mut lines = vec![format!("shellfirm {version}")];
+    if verbose {
+        lines.push(format!("checks bundle hash: {bundle_hash}"));
+    }
+    lines
+}
+
+#[cfg(test)]
+mod test_version_cli_command {
+    use insta::assert_debug_snapshot;
+
+    use super::*;
+
+    #[test]
+    fn can_render_version_lines() {
+        assert_debug_snapshot!(render_version_lines("1.0.0", "abc123", false));
+        assert_debug_snapshot!(render_version_lines("1.0.0", "abc123", true));
+    }
+}
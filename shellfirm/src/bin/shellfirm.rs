@@ -3,14 +3,32 @@ use std::process::exit;
 
 use anyhow::{anyhow, Result};
 use console::{style, Style};
-use shellfirm::{CmdExit, Config};
+use shellfirm::{CmdExit, Config, Settings};
 
 const DEFAULT_ERR_EXIT_CODE: i32 = 1;
 
 fn main() {
     let app = cmd::default::command()
         .subcommand(cmd::command::command())
-        .subcommand(cmd::config::command());
+        .subcommand(cmd::config::command())
+        .subcommand(cmd::tmux::command())
+        .subcommand(cmd::prompt_segment::command())
+        .subcommand(cmd::status::command())
+        .subcommand(cmd::init::command())
+        .subcommand(cmd::last::command())
+        .subcommand(cmd::capture::command())
+        .subcommand(cmd::analyze::command())
+        .subcommand(cmd::scan::command())
+        .subcommand(cmd::replay::command())
+        .subcommand(cmd::policy::command())
+        .subcommand(cmd::login::command())
+        .subcommand(cmd::explain::command())
+        .subcommand(cmd::diag::command())
+        .subcommand(cmd::totp::command())
+        .subcommand(cmd::alias::command())
+        .subcommand(cmd::try_sandbox::command())
+        .subcommand(cmd::audit::command())
+        .subcommand(cmd::version::command());
 
     let matches = app.clone().get_matches();
 
@@ -20,35 +38,56 @@ fn main() {
     );
     env_logger::init_from_env(env);
 
+    // portable mode: compiled-in defaults, the filesystem is never touched
+    let portable = matches.is_present("no-config")
+        || std::env::var("SHELLFIRM_PORTABLE").is_ok_and(|value| value == "1");
+
     // load configuration
-    let config = match Config::new(None) {
-        Ok(config) => config,
-        Err(err) => {
-            eprintln!("Loading config error: {err}");
-            exit(1)
+    shellfirm::diagnostics::set_stage("load-config");
+    let config = if portable {
+        None
+    } else {
+        match Config::new(None) {
+            Ok(config) => Some(config),
+            Err(err) => {
+                eprintln!("Loading config error: {err}");
+                exit(1)
+            }
         }
     };
 
-    if let Some((command_name, subcommand_matches)) = matches.subcommand() {
+    // crashes from here on leave a redacted report next to the settings
+    if let Some(config) = &config {
+        shellfirm::diagnostics::install_panic_hook(config.root_folder.clone());
+    }
+
+    if let (Some(config), Some((command_name, subcommand_matches))) =
+        (&config, matches.subcommand())
+    {
         if command_name == "config" && subcommand_matches.subcommand_name() == Some("reset") {
-            let c = cmd::config::run_reset(&config, None);
+            let c = cmd::config::run_reset(config, None);
             shellfirm_exit(Ok(c));
         }
     };
 
-    let settings = match config.get_settings_from_file() {
-        Ok(c) => c,
-        Err(e) => {
-            eprintln!(
-                "Could not load setting from file. Try resolving by running `{}`\nError: {}",
-                style("shellfirm config reset").bold().italic().underlined(),
-                e
-            );
-            exit(1)
-        }
+    shellfirm::diagnostics::set_stage("load-settings");
+    let settings = match &config {
+        Some(config) => match config.get_settings_from_file() {
+            Ok(c) => c,
+            Err(e) => {
+                eprintln!(
+                    "Could not load setting from file. Try resolving by running `{}`\nError: {}",
+                    style("shellfirm config reset").bold().italic().underlined(),
+                    e
+                );
+                exit(1)
+            }
+        },
+        None => Settings::default(),
     };
 
-    let checks = match settings.get_active_checks() {
+    shellfirm::diagnostics::set_stage("load-checks");
+    let mut checks = match settings.get_active_checks() {
         Ok(c) => c,
         Err(e) => {
             eprintln!("Could not load checks. err: Error: {e}");
@@ -56,15 +95,90 @@ fn main() {
         }
     };
 
+    // custom checks: broken files are skipped with a warning and reported by
+    // `shellfirm config doctor`
+    if let Some(config) = &config {
+        let (custom_checks, _custom_check_errors) = config.load_custom_checks();
+        checks.extend(
+            custom_checks
+                .into_iter()
+                .filter(|c| !settings.ignores_patterns_ids.contains(&c.id))
+                .filter(|c| c.applies_to_os(std::env::consts::OS)),
+        );
+    }
+
+    shellfirm::diagnostics::set_stage("dispatch");
     let res = matches.subcommand().map_or_else(
         || Err(anyhow!("command not found")),
         |tup| match tup {
             ("pre-command", subcommand_matches) => {
-                cmd::command::run(subcommand_matches, &settings, &checks)
+                if config.as_ref().is_none_or(Config::is_enabled) {
+                    cmd::command::run(subcommand_matches, config.as_ref(), &settings, &checks)
+                } else {
+                    Ok(shellfirm::CmdExit {
+                        code: exitcode::OK,
+                        message: None,
+                    })
+                }
             }
-            ("config", subcommand_matches) => {
-                cmd::config::run(subcommand_matches, &config, &settings)
+            ("config", subcommand_matches) => match &config {
+                Some(config) => cmd::config::run(subcommand_matches, config, &settings),
+                None => portable_unavailable(),
+            },
+            ("version", subcommand_matches) => cmd::version::run(subcommand_matches),
+            ("last", subcommand_matches) => match &config {
+                Some(config) => cmd::last::run(subcommand_matches, config, &checks),
+                None => portable_unavailable(),
+            },
+            ("capture", subcommand_matches) => {
+                cmd::capture::run(subcommand_matches, &settings, &checks)
             }
+            ("analyze", subcommand_matches) => {
+                cmd::analyze::run(subcommand_matches, &settings, &checks)
+            }
+            ("scan", subcommand_matches) => cmd::scan::run(subcommand_matches, &settings, &checks),
+            ("replay", subcommand_matches) => cmd::replay::run(subcommand_matches, &checks),
+            ("policy", subcommand_matches) => cmd::policy::run(subcommand_matches, &settings),
+            ("login", subcommand_matches) => match &config {
+                Some(config) => cmd::login::run(subcommand_matches, config, &settings),
+                None => portable_unavailable(),
+            },
+            ("explain", subcommand_matches) => cmd::explain::run(subcommand_matches, &checks),
+            ("diag", subcommand_matches) => match &config {
+                Some(config) => cmd::diag::run(subcommand_matches, config),
+                None => portable_unavailable(),
+            },
+            ("totp", subcommand_matches) => match &config {
+                Some(config) => cmd::totp::run(subcommand_matches, config),
+                None => portable_unavailable(),
+            },
+            ("alias", subcommand_matches) => match &config {
+                Some(config) => cmd::alias::run(subcommand_matches, config),
+                None => portable_unavailable(),
+            },
+            ("try", subcommand_matches) => {
+                cmd::try_sandbox::run(subcommand_matches, &settings, &checks)
+            }
+            ("tmux-status", subcommand_matches) => match &config {
+                Some(config) => cmd::tmux::run(subcommand_matches, config, &settings),
+                None => portable_unavailable(),
+            },
+            ("prompt-segment", subcommand_matches) => match &config {
+                Some(config) => cmd::prompt_segment::run(subcommand_matches, config, &settings),
+                None => portable_unavailable(),
+            },
+            ("status", subcommand_matches) => match &config {
+                Some(config) => cmd::status::run(subcommand_matches, config, &settings),
+                None => portable_unavailable(),
+            },
+            ("init", subcommand_matches) => match &config {
+                Some(config) => cmd::init::run(subcommand_matches, config),
+                None => portable_unavailable(),
+            },
+            ("audit", subcommand_matches) => match &config {
+                Some(config) => cmd::audit::run(subcommand_matches, config, &checks),
+                None => portable_unavailable(),
+            },
             _ => unreachable!(),
         },
     );
@@ -72,6 +186,18 @@ fn main() {
     shellfirm_exit(res);
 }
 
+/// The exit of a command that needs the config folder while running in
+/// portable mode.
+fn portable_unavailable() -> Result<CmdExit> {
+    Ok(CmdExit {
+        code: exitcode::USAGE,
+        message: Some(
+            "this command needs the config folder and is not available in portable mode"
+                .to_string(),
+        ),
+    })
+}
+
 fn shellfirm_exit(res: Result<CmdExit>) {
     let exit_with = match res {
         Ok(cmd) => {
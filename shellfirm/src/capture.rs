@@ -0,0 +1,232 @@
+//! Record-and-replay bundles for bug reports: capture the environment answers
+//! used while analyzing a command into a redacted bundle, and rebuild a
+//! [`MockEnvironment`] from such a bundle so false-positive/negative reports
+//! can be reproduced on another machine.
+
+use std::{collections::BTreeMap, sync::Mutex};
+
+use serde_derive::{Deserialize, Serialize};
+
+use crate::{
+    checks::{self, Check},
+    context,
+    environment::{Environment, MockEnvironment},
+};
+
+/// Value stored instead of session identifying environment variables.
+const REDACTED_VALUE: &str = "[redacted]";
+
+/// Environment variables whose values are redacted in captured bundles: the
+/// analysis only looks at their presence, the values identify the session.
+const REDACTED_ENV_VARS: &[&str] = &["SSH_CONNECTION", "SSH_TTY", "SSH_CLIENT"];
+
+/// A reproducible snapshot of one command analysis.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CaptureBundle {
+    /// shellfirm version that captured the bundle.
+    pub shellfirm_version: String,
+    /// Hash of the built-in checks bundle at capture time.
+    pub checks_bundle_hash: String,
+    /// The analyzed command.
+    pub command: String,
+    /// Check ids that matched at capture time.
+    pub match_ids: Vec<String>,
+    /// Environment answers observed during the analysis.
+    pub environment: CapturedEnvironment,
+}
+
+/// Environment answers recorded during an analysis. Mirrors the fields of
+/// [`MockEnvironment`], with sorted maps so bundles serialize
+/// deterministically.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct CapturedEnvironment {
+    /// Environment variables that were read, when they were set.
+    pub env_vars: BTreeMap<String, String>,
+    /// Paths that were probed and whether they existed.
+    pub files: BTreeMap<String, bool>,
+    /// Working directory of the analyzed command.
+    pub current_dir: Option<String>,
+    /// Stdout of the subprocesses that were run, when they succeeded.
+    pub command_outputs: BTreeMap<String, String>,
+}
+
+impl CapturedEnvironment {
+    /// Build a [`MockEnvironment`] answering exactly what was captured.
+    #[must_use]
+    pub fn to_mock(&self) -> MockEnvironment {
+        let mut builder = MockEnvironment::builder();
+        for (key, value) in &self.env_vars {
+            builder = builder.env_var(key, value);
+        }
+        for (path, exists) in &self.files {
+            builder = builder.file(path, *exists);
+        }
+        if let Some(current_dir) = &self.current_dir {
+            builder = builder.current_dir(current_dir);
+        }
+        for (command, output) in &self.command_outputs {
+            builder = builder.command_output(command, output);
+        }
+        builder.build()
+    }
+
+    /// Replace session identifying environment variable values, keeping only
+    /// their presence.
+    fn redact(&mut self) {
+        for key in REDACTED_ENV_VARS {
+            if let Some(value) = self.env_vars.get_mut(*key) {
+                *value = REDACTED_VALUE.to_string();
+            }
+        }
+    }
+}
+
+/// [`Environment`] wrapper recording every question the pipeline asks and the
+/// answer it got, while delegating to the base environment.
+pub struct RecordingEnvironment<'a> {
+    base: &'a dyn Environment,
+    captured: Mutex<CapturedEnvironment>,
+}
+
+impl<'a> RecordingEnvironment<'a> {
+    /// Start recording on top of the given base environment.
+    #[must_use]
+    pub fn new(base: &'a dyn Environment) -> Self {
+        Self {
+            base,
+            captured: Mutex::new(CapturedEnvironment::default()),
+        }
+    }
+
+    /// Finish recording and return the captured answers.
+    #[must_use]
+    pub fn into_captured(self) -> CapturedEnvironment {
+        self.captured.into_inner().unwrap_or_default()
+    }
+}
+
+impl Environment for RecordingEnvironment<'_> {
+    fn env_var(&self, key: &str) -> Option<String> {
+        let value = self.base.env_var(key);
+        if let (Some(value), Ok(mut captured)) = (&value, self.captured.lock()) {
+            captured.env_vars.insert(key.to_string(), value.clone());
+        }
+        value
+    }
+
+    fn path_exists(&self, path: &str) -> bool {
+        let exists = self.base.path_exists(path);
+        if let Ok(mut captured) = self.captured.lock() {
+            captured.files.insert(path.to_string(), exists);
+        }
+        exists
+    }
+
+    fn current_dir(&self) -> Option<String> {
+        let current_dir = self.base.current_dir();
+        if let Ok(mut captured) = self.captured.lock() {
+            captured.current_dir = current_dir.clone();
+        }
+        current_dir
+    }
+
+    fn run_command(&self, command: &str) -> Option<String> {
+        let output = self.base.run_command(command);
+        if let (Some(output), Ok(mut captured)) = (&output, self.captured.lock()) {
+            captured
+                .command_outputs
+                .insert(command.to_string(), output.clone());
+        }
+        output
+    }
+}
+
+/// Run the analysis pipeline for the given command while recording the
+/// environment answers, and return the redacted bundle.
+///
+/// # Arguments
+///
+/// * `check_command` - the command to analyze.
+/// * `checks` - List of checks to run against the command.
+/// * `environment` - Environment the command is going to run in.
+#[must_use]
+pub fn snapshot(
+    check_command: &str,
+    checks: &[Check],
+    environment: &dyn Environment,
+) -> CaptureBundle {
+    let recorder = RecordingEnvironment::new(environment);
+    let match_ids: Vec<String> = checks::analyze_command(checks, check_command, &recorder)
+        .matches
+        .into_iter()
+        .map(|check| check.id)
+        .collect();
+    // record the context signals even when no matched check probes them, so
+    // the bundle also reproduces context dependent settings (deny rules)
+    let _ = context::detect(&recorder);
+
+    let mut captured = recorder.into_captured();
+    captured.redact();
+
+    CaptureBundle {
+        shellfirm_version: env!("CARGO_PKG_VERSION").to_string(),
+        checks_bundle_hash: checks::bundle_hash(),
+        command: check_command.to_string(),
+        match_ids,
+        environment: captured,
+    }
+}
+
+#[cfg(test)]
+mod test_capture {
+    use insta::assert_debug_snapshot;
+
+    use super::*;
+    use crate::environment::fixtures;
+
+    const CHECKS: &str = r"
+- id: git:force_push
+  test: git push.+(-f|--force)
+  description: force push
+  from: git
+";
+
+    macro_rules! assert_bundle_snapshot {
+        ($bundle:expr) => {
+            insta::with_settings!({filters => vec![(r"[0-9a-f]{64}", "[CHECKS_BUNDLE_HASH]")]}, {
+                assert_debug_snapshot!($bundle);
+            });
+        };
+    }
+
+    #[test]
+    fn can_snapshot_a_command_analysis() {
+        let checks: Vec<Check> = serde_yaml::from_str(CHECKS).unwrap();
+        assert_bundle_snapshot!(snapshot(
+            "git push --force",
+            &checks,
+            &fixtures::production_ssh()
+        ));
+    }
+
+    #[test]
+    fn snapshot_redacts_session_identifying_values() {
+        let bundle = snapshot("ls", &[], &fixtures::production_ssh());
+        assert_debug_snapshot!(bundle.environment.env_vars);
+    }
+
+    #[test]
+    fn can_replay_a_bundle_against_a_mock_environment() {
+        let checks: Vec<Check> = serde_yaml::from_str(CHECKS).unwrap();
+        let bundle = snapshot("git push --force", &checks, &fixtures::production_ssh());
+
+        let environment = bundle.environment.to_mock();
+        assert_debug_snapshot!(context::detect(&environment));
+        let replayed: Vec<String> =
+            checks::run_check_on_command_with_environment(&checks, &bundle.command, &environment)
+                .into_iter()
+                .map(|check| check.id)
+                .collect();
+        assert_debug_snapshot!(replayed == bundle.match_ids);
+    }
+}
@@ -0,0 +1,385 @@
+//! Parse the user command before running the checks on it
+
+use lazy_static::lazy_static;
+use regex::Regex;
+
+lazy_static! {
+    static ref REGEX_STRING_COMMAND_REPLACE: Regex = Regex::new(r#"('|")([\s\S]*?)('|")"#).unwrap();
+    /// A shell (or `su`) running a quoted command via `-c`: the payload is
+    /// itself a command and should be analyzed as one.
+    static ref REGEX_SHELL_PAYLOAD: Regex =
+        Regex::new(r#"\b(?:sh|bash|zsh|dash|su)\b[^'"&|;]*\s-c\s+(?:'([^']*)'|"([^"]*)")"#)
+            .unwrap();
+    /// A command substitution: the inner command runs first and its output
+    /// feeds the outer command.
+    static ref REGEX_COMMAND_SUBSTITUTION: Regex =
+        Regex::new(r"\$\(([^()]*)\)|`([^`]*)`").unwrap();
+    /// Wrappers that run their payload repeatedly or in parallel, so a risky
+    /// command amplifies into a very different blast profile.
+    static ref AMPLIFIER_PATTERNS: Vec<Regex> = vec![
+        Regex::new(r"^\s*(?:sudo\s+)?watch\b(?:\s+-\S+)*").unwrap(),
+        Regex::new(r"\bxargs\b[^|;&]*?-P\s*\d+").unwrap(),
+        Regex::new(r"\bfor\s+\w+\s+in\b").unwrap(),
+        Regex::new(r"\bparallel\b").unwrap(),
+    ];
+}
+
+/// Privilege escalators whose inner command should be analyzed as if it ran
+/// directly.
+const PRIVILEGE_ESCALATORS: &[&str] = &["sudo", "doas", "run0", "pkexec", "su"];
+
+/// Escalator flags that take a value (e.g. `sudo -u root`), so the value is
+/// not mistaken for the inner command.
+const ESCALATOR_VALUE_FLAGS: &[&str] = &["-u", "-g", "--user", "--group"];
+
+/// Strip leading privilege escalator prefixes (`sudo`, `doas`, `run0`,
+/// `pkexec`, `su`), including nested combinations and their flags, returning
+/// the inner command and whether an escalator was present.
+///
+/// # Arguments
+///
+/// * `command` - a single command segment.
+#[must_use]
+pub fn strip_privilege_escalation(command: &str) -> (String, bool) {
+    let tokens: Vec<&str> = command.split_whitespace().collect();
+    let mut index = 0;
+    let mut privileged = false;
+    while index < tokens.len() && PRIVILEGE_ESCALATORS.contains(&tokens[index]) {
+        privileged = true;
+        index += 1;
+        while index < tokens.len() && tokens[index].starts_with('-') {
+            if ESCALATOR_VALUE_FLAGS.contains(&tokens[index]) {
+                index += 1;
+            }
+            index += 1;
+        }
+    }
+    if !privileged {
+        return (command.to_string(), false);
+    }
+    (tokens[index..].join(" "), true)
+}
+
+lazy_static! {
+    /// A kubernetes namespace flag (`-n` / `--namespace`, space or `=`
+    /// separated).
+    static ref REGEX_KUBE_NAMESPACE: Regex =
+        Regex::new(r"(?:^|\s)(?:-n|--namespace)[=\s]+([^\s;|&]+)").expect("invalid namespace flag pattern");
+}
+
+/// Return the kubernetes namespace the command targets, when one can be
+/// extracted from a `-n` / `--namespace` flag of a `kubectl` or `helm`
+/// invocation.
+///
+/// # Arguments
+///
+/// * `command` - Command that the user typed.
+#[must_use]
+pub fn extract_kubernetes_namespace(command: &str) -> Option<String> {
+    if !command.contains("kubectl") && !command.contains("helm") {
+        return None;
+    }
+    REGEX_KUBE_NAMESPACE
+        .captures(command)
+        .map(|captures| captures[1].to_string())
+}
+
+/// Tokens after which the next token is in command position, so an alias
+/// there should be expanded.
+const COMMAND_SEPARATORS: &[&str] = &["&&", "||", "|", "&", ";"];
+
+/// Expand the known aliases in the command, so `alias rmrf='rm -rf'` cannot
+/// bypass the checks. Only tokens in command position are expanded (start of
+/// the line, after `&&`/`||`/`|`/`;` and after privilege escalators), and a
+/// single pass is made — an alias expanding to itself does not loop.
+///
+/// # Arguments
+///
+/// * `command` - Command that the user typed.
+/// * `aliases` - alias name to expansion, from the settings file.
+#[must_use]
+pub fn expand_aliases(
+    command: &str,
+    aliases: &std::collections::BTreeMap<String, String>,
+) -> String {
+    if aliases.is_empty() {
+        return command.to_string();
+    }
+    let mut expanded: Vec<String> = Vec::new();
+    let mut command_position = true;
+    for token in command.split_whitespace() {
+        if command_position {
+            if let Some(expansion) = aliases.get(token) {
+                expanded.push(expansion.clone());
+                command_position = false;
+                continue;
+            }
+        }
+        command_position = COMMAND_SEPARATORS.contains(&token)
+            || PRIVILEGE_ESCALATORS.contains(&token) && command_position;
+        expanded.push(token.to_string());
+    }
+    expanded.join(" ")
+}
+
+/// Return the amplifier wrapping the command, when one is present: a
+/// construct like `watch -n1`, `xargs -P8`, a `for … in` loop or GNU
+/// `parallel` that runs its payload repeatedly or in parallel.
+///
+/// # Arguments
+///
+/// * `command` - Command that the user typed.
+#[must_use]
+pub fn detect_amplifier(command: &str) -> Option<String> {
+    AMPLIFIER_PATTERNS
+        .iter()
+        .find_map(|pattern| pattern.find(command))
+        .map(|found| found.as_str().trim().to_string())
+}
+
+/// Does any part of the command run under a privilege escalator.
+///
+/// # Arguments
+///
+/// * `command` - Command that the user typed.
+#[must_use]
+pub fn is_privileged(command: &str) -> bool {
+    split_command(&strip_quoted_strings(command))
+        .iter()
+        .any(|segment| strip_privilege_escalation(segment).1)
+}
+
+/// Remove quoted strings from the given command. Text between quotes is user
+/// data (commit messages, file content and so on) and should not trigger
+/// checks.
+///
+/// # Arguments
+///
+/// * `command` - Command to strip.
+#[must_use]
+pub fn strip_quoted_strings(command: &str) -> String {
+    REGEX_STRING_COMMAND_REPLACE
+        .replace_all(command, "")
+        .to_string()
+}
+
+/// Split the given command to all the commands that going to run in the same
+/// line (separated by `&`, `&&` or `|`).
+///
+/// # Arguments
+///
+/// * `command` - Command to split.
+#[must_use]
+pub fn split_command(command: &str) -> Vec<String> {
+    command
+        .split(['&', '|'])
+        .map(std::string::ToString::to_string)
+        .collect()
+}
+
+/// Prepare the user command to checks run: remove the quoted strings and
+/// split the command line to the single commands it executes.
+///
+/// # Arguments
+///
+/// * `command` - Command that the user typed.
+#[must_use]
+pub fn parse_and_split_command(command: &str) -> Vec<String> {
+    let mut segments = split_command(&strip_quoted_strings(command));
+    // a quoted `sh -c` / `su -c` payload is a command of its own:
+    // `sudo sh -c 'rm -rf /'` must be analyzed like `rm -rf /`
+    for payload in extract_shell_payloads(command) {
+        segments.extend(parse_and_split_command(&payload));
+    }
+    // so is a command substitution: `rm -rf $(ls | grep tmp)` runs
+    // `ls | grep tmp` first
+    for substitution in extract_command_substitutions(command) {
+        segments.extend(parse_and_split_command(&substitution));
+    }
+    segments
+}
+
+/// Return the inner commands of the `$(...)` and backtick command
+/// substitutions in the command — the commands that run first and feed their
+/// output into the rest of the line.
+///
+/// # Arguments
+///
+/// * `command` - Command that the user typed.
+#[must_use]
+pub fn extract_command_substitutions(command: &str) -> Vec<String> {
+    REGEX_COMMAND_SUBSTITUTION
+        .captures_iter(command)
+        .filter_map(|captures| {
+            captures
+                .get(1)
+                .or_else(|| captures.get(2))
+                .map(|inner| inner.as_str().trim().to_string())
+        })
+        .filter(|inner| !inner.is_empty())
+        .collect()
+}
+
+/// Return the quoted `-c` payloads of shell (or `su`) invocations in the
+/// command.
+fn extract_shell_payloads(command: &str) -> Vec<String> {
+    REGEX_SHELL_PAYLOAD
+        .captures_iter(command)
+        .filter_map(|captures| {
+            captures
+                .get(1)
+                .or_else(|| captures.get(2))
+                .map(|payload| payload.as_str().to_string())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test_command {
+    use insta::assert_debug_snapshot;
+    use proptest::prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn can_strip_quoted_strings() {
+        assert_debug_snapshot!(strip_quoted_strings(
+            "git commit -m 'some | message & more'"
+        ));
+        assert_debug_snapshot!(strip_quoted_strings(r#"echo "rm -rf /" file"#));
+    }
+
+    #[test]
+    fn can_parse_and_split_command() {
+        assert_debug_snapshot!(parse_and_split_command("rm -rf / && reboot"));
+        assert_debug_snapshot!(parse_and_split_command("cat file | grep token"));
+    }
+
+    #[test]
+    fn can_strip_privilege_escalation() {
+        assert_debug_snapshot!(strip_privilege_escalation("sudo rm -rf /"));
+        assert_debug_snapshot!(strip_privilege_escalation("doas rm -rf /"));
+        assert_debug_snapshot!(strip_privilege_escalation("run0 systemctl reboot"));
+        assert_debug_snapshot!(strip_privilege_escalation("pkexec rm -rf /"));
+        assert_debug_snapshot!(strip_privilege_escalation("sudo -u root rm -rf /"));
+        assert_debug_snapshot!(strip_privilege_escalation("sudo doas rm -rf /"));
+        assert_debug_snapshot!(strip_privilege_escalation("rm -rf /"));
+    }
+
+    #[test]
+    fn can_parse_and_split_shell_payloads() {
+        assert_debug_snapshot!(parse_and_split_command("sudo sh -c 'rm -rf /'"));
+        assert_debug_snapshot!(parse_and_split_command(r#"su -c "rm -rf /""#));
+        assert_debug_snapshot!(parse_and_split_command("bash -c 'rm -rf a && reboot'"));
+    }
+
+    #[test]
+    fn can_extract_command_substitutions() {
+        assert_debug_snapshot!(extract_command_substitutions("rm -rf $(ls | grep tmp)"));
+        assert_debug_snapshot!(extract_command_substitutions(
+            "kubectl delete pod $(kubectl get pods -o name)"
+        ));
+        assert_debug_snapshot!(extract_command_substitutions("rm -rf `find . -name tmp`"));
+        assert_debug_snapshot!(extract_command_substitutions("rm -rf /"));
+    }
+
+    #[test]
+    fn can_parse_and_split_command_substitutions() {
+        assert_debug_snapshot!(parse_and_split_command("rm -rf $(ls | grep tmp)"));
+    }
+
+    #[test]
+    fn can_detect_amplifiers() {
+        assert_debug_snapshot!(detect_amplifier("watch -n1 kubectl delete pod x"));
+        assert_debug_snapshot!(detect_amplifier("cat hosts | xargs -I{} -P8 ssh {} reboot"));
+        assert_debug_snapshot!(detect_amplifier("for f in $(ls); do rm -rf $f; done"));
+        assert_debug_snapshot!(detect_amplifier("seq 100 | parallel terraform destroy"));
+        assert_debug_snapshot!(detect_amplifier("rm -rf ./build"));
+        // sequential xargs is not an amplifier
+        assert_debug_snapshot!(detect_amplifier("find . -name '*.log' | xargs rm"));
+    }
+
+    #[test]
+    fn can_expand_aliases() {
+        let aliases: std::collections::BTreeMap<String, String> = [
+            ("k".to_string(), "kubectl".to_string()),
+            ("rmrf".to_string(), "rm -rf".to_string()),
+        ]
+        .into();
+        assert_debug_snapshot!(expand_aliases("rmrf /tmp/build", &aliases));
+        assert_debug_snapshot!(expand_aliases("ls && k delete ns payments", &aliases));
+        assert_debug_snapshot!(expand_aliases("sudo rmrf /", &aliases));
+        // an alias name in argument position is user data, not a command
+        assert_debug_snapshot!(expand_aliases("echo k", &aliases));
+        assert_debug_snapshot!(expand_aliases("rm -rf /", &aliases));
+    }
+
+    #[test]
+    fn can_extract_kubernetes_namespace() {
+        assert_debug_snapshot!(extract_kubernetes_namespace(
+            "kubectl delete pods --all -n kube-system"
+        ));
+        assert_debug_snapshot!(extract_kubernetes_namespace(
+            "helm uninstall app --namespace=preview-42"
+        ));
+        assert_debug_snapshot!(extract_kubernetes_namespace("kubectl delete ns payments"));
+        // the flag only counts on a kubernetes tool
+        assert_debug_snapshot!(extract_kubernetes_namespace("sort -n numbers.txt"));
+    }
+
+    #[test]
+    fn can_detect_privileged_command() {
+        assert_debug_snapshot!(is_privileged("sudo rm -rf /"));
+        assert_debug_snapshot!(is_privileged("ls && doas reboot"));
+        assert_debug_snapshot!(is_privileged("rm -rf /"));
+    }
+
+    /// chars that never interact with the quote stripping or the command
+    /// splitting.
+    fn plain_token() -> impl Strategy<Value = String> {
+        "[a-z0-9 ./_-]{1,20}"
+    }
+
+    fn delimiter() -> impl Strategy<Value = String> {
+        prop_oneof![
+            Just("&".to_string()),
+            Just("&&".to_string()),
+            Just("|".to_string()),
+            Just("||".to_string()),
+        ]
+    }
+
+    proptest! {
+        #[test]
+        fn strip_is_identity_without_quotes(command in "[a-z0-9 ./_&|-]{0,50}") {
+            prop_assert_eq!(strip_quoted_strings(&command), command);
+        }
+
+        #[test]
+        fn strip_never_removes_unquoted_delimiters(
+            prefix in plain_token(),
+            quoted in "[a-z0-9 ./_&|-]{0,20}",
+            suffix in plain_token(),
+            delimiter in delimiter(),
+        ) {
+            let command = format!("{prefix}{delimiter}'{quoted}'{delimiter}{suffix}");
+            let stripped = strip_quoted_strings(&command);
+            let count_delimiters = |s: &str| s.matches(['&', '|']).count();
+            prop_assert_eq!(
+                count_delimiters(&stripped),
+                count_delimiters(&format!("{prefix}{delimiter}{delimiter}{suffix}"))
+            );
+        }
+
+        #[test]
+        fn split_and_rejoin_preserves_non_quoted_tokens(
+            tokens in prop::collection::vec(plain_token(), 1..5),
+            delimiter in delimiter(),
+        ) {
+            let command = tokens.join(&delimiter);
+            let splitted = parse_and_split_command(&command);
+            let rejoined: Vec<String> = splitted.into_iter().filter(|t| !t.is_empty()).collect();
+            prop_assert_eq!(rejoined, tokens);
+        }
+    }
+}
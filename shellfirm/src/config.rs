@@ -4,7 +4,7 @@
 use std::{
     env, fmt, fs,
     io::{Read, Write},
-    path::PathBuf,
+    path::{Path, PathBuf},
     time::{SystemTime, UNIX_EPOCH},
 };
 
@@ -13,13 +13,69 @@ use log::debug;
 use serde_derive::{Deserialize, Serialize};
 use strum::EnumIter;
 
-use crate::{checks, dialog};
+use crate::{checks, dialog, network::NetworkMode};
 
 const DEFAULT_SETTING_FILE_NAME: &str = "settings.yaml";
 
+/// Marker file that turns all command interception off when present in the
+/// config folder.
+const DISABLED_FILE_NAME: &str = "disabled";
+
+/// File keeping the per-day count of intercepted commands, used by the
+/// prompt/tmux status segments.
+const STATS_FILE_NAME: &str = "stats.yaml";
+
+/// Folder keeping one heartbeat file per shell PID, written every time the
+/// hook calls pre-command and read by `shellfirm status`.
+const HEARTBEATS_FOLDER_NAME: &str = "heartbeats";
+
+/// Heartbeat files older than this are pruned.
+const HEARTBEAT_TTL_SECONDS: u64 = 60 * 60 * 24;
+
+/// Folder (inside the config folder) with user custom check files.
+const CUSTOM_CHECKS_FOLDER_NAME: &str = "checks";
+
+/// File keeping the most recent intercepted command, shown by
+/// `shellfirm last`.
+const LAST_COMMAND_FILE_NAME: &str = "last-command.yaml";
+
 pub const DEFAULT_CHALLENGE: Challenge = Challenge::Math;
 
-pub const DEFAULT_INCLUDE_CHECKS: [&str; 3] = ["base", "fs", "git"];
+pub const DEFAULT_INCLUDE_CHECKS: [&str; 5] = ["base", "fs", "git", "terraform", "disk"];
+
+/// Check groups enabled on top of the defaults when the matching tool is
+/// found on the machine, probed at first run and by `config refresh-groups`.
+pub const TOOL_DRIVEN_GROUPS: [(&str, &str); 4] = [
+    ("kubectl", "kubernetes"),
+    ("terraform", "terraform"),
+    ("psql", "database-migrations"),
+    ("heroku", "heroku"),
+];
+
+/// Return the default include groups plus every group whose tool responds in
+/// the given environment, so the initial configuration matches the tools the
+/// machine actually has instead of a static set.
+///
+/// # Arguments
+///
+/// * `environment` - environment to probe the tools in.
+#[must_use]
+pub fn detect_include_groups(environment: &dyn crate::environment::Environment) -> Vec<String> {
+    let mut groups: Vec<String> = DEFAULT_INCLUDE_CHECKS
+        .iter()
+        .map(std::string::ToString::to_string)
+        .collect();
+    for (tool, group) in TOOL_DRIVEN_GROUPS {
+        if environment
+            .run_command(&format!("{tool} --version"))
+            .is_some()
+            && !groups.contains(&group.to_string())
+        {
+            groups.push(group.to_string());
+        }
+    }
+    groups
+}
 
 /// The user challenge when user need to confirm the command.
 #[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq, EnumIter)]
@@ -30,6 +86,15 @@ pub enum Challenge {
     Enter,
     /// only yes typing will approve the command.
     Yes,
+    /// Typing a random word from the configured wordlist will approve the
+    /// command.
+    Word,
+    /// Typing the current code from an enrolled authenticator app will
+    /// approve the command (`shellfirm totp enroll`).
+    Totp,
+    /// Waiting out a visible countdown will approve the command: friction
+    /// without a cognitive puzzle, for incident response.
+    Delay,
 }
 
 #[derive(Debug)]
@@ -52,6 +117,644 @@ pub struct Settings {
     pub ignores_patterns_ids: Vec<String>,
     /// List of pattens id to prevent
     pub deny_patterns_ids: Vec<String>,
+    /// Check tags to deny (e.g. `irreversible`), denying every check carrying
+    /// one of them without listing ids one by one.
+    #[serde(default)]
+    pub deny_tags: Vec<String>,
+    /// Escalate the challenge by tag (e.g. `irreversible: Yes`): commands
+    /// whose matched checks carry the tag get the stronger challenge, without
+    /// enumerating check ids.
+    #[serde(default)]
+    pub tag_escalation: std::collections::BTreeMap<String, Challenge>,
+    /// Max added latency (in milliseconds) a single check subprocess may
+    /// spend before it is killed.
+    #[serde(default = "default_max_subprocess_latency_ms")]
+    pub max_subprocess_latency_ms: u64,
+    /// Whether network features (remote policy, self-update) are allowed.
+    #[serde(default)]
+    pub network: NetworkMode,
+    /// Content hash of the checks bundle that was active when this settings
+    /// file was written, verified on load for attestation.
+    #[serde(default)]
+    pub checks_bundle_hash: Option<String>,
+    /// Conditional deny rules, denying a check only when the condition holds
+    /// (e.g. only in certain kube contexts).
+    #[serde(default)]
+    pub deny_rules: Vec<DenyRule>,
+    /// Allowlist entries: commands that never challenge, optionally only
+    /// inside a directory, so safe ritual commands stop prompting without
+    /// disabling whole check groups.
+    #[serde(default)]
+    pub allow: Vec<AllowRule>,
+    /// Keep the most recent intercepted command for `shellfirm last`.
+    #[serde(default = "default_true")]
+    pub save_last_command: bool,
+    /// Copy the intercepted command to the clipboard, so a long command line
+    /// is not lost when the challenge is cancelled.
+    #[serde(default)]
+    pub copy_blocked_command_to_clipboard: bool,
+    /// Words the `Word` challenge picks from, overridable for non-English
+    /// users.
+    #[serde(default = "default_challenge_wordlist")]
+    pub challenge_wordlist: Vec<String>,
+    /// Tuning of the generated challenges.
+    #[serde(default)]
+    pub challenge_tuning: ChallengeTuning,
+    /// Base32 secret of the `Totp` challenge, written by `shellfirm totp
+    /// enroll`. `None` until enrolled.
+    #[serde(default)]
+    pub totp_secret: Option<String>,
+    /// Shell aliases to expand before the checks run, imported with
+    /// `shellfirm alias import`, so `alias rmrf='rm -rf'` cannot bypass the
+    /// checks.
+    #[serde(default)]
+    pub aliases: std::collections::BTreeMap<String, String>,
+    /// Summarize the matched checks by group when more than this many match
+    /// (`0` always shows the full list).
+    #[serde(default = "default_summarize_matches_above")]
+    pub summarize_matches_above: usize,
+    /// Challenge to escalate to when the blast radius of a matched command
+    /// could not be computed (e.g. a mount probe timed out). `None` keeps the
+    /// configured challenge.
+    #[serde(default)]
+    pub escalate_on_unknown_impact: Option<Challenge>,
+    /// Blast radius thresholds that bump the challenge (e.g. `files: ">10000"`,
+    /// `size: ">50GB"`, `commits: ">100"`), connecting the measured impact to
+    /// enforcement instead of being informational only.
+    #[serde(default)]
+    pub escalate_if: EscalateIf,
+    /// Only intercept checks with at least this (effective) severity. `None`
+    /// intercepts everything.
+    #[serde(default)]
+    pub min_severity: Option<checks::Severity>,
+    /// Raise the effective severity of every check to the given floor when a
+    /// context label matches (e.g. `k8s=prod-*: High`), so low-severity
+    /// checks that are normally filtered out still fire in production.
+    #[serde(default)]
+    pub context_severity_floor: std::collections::BTreeMap<String, checks::Severity>,
+    /// Audit log settings (sinks beyond the local JSONL log).
+    #[serde(default)]
+    pub audit: AuditSettings,
+    /// Identity provider for `shellfirm login`. `None` disables role-based
+    /// policies.
+    #[serde(default)]
+    pub oidc: Option<crate::oidc::OidcSettings>,
+    /// Break-glass settings: whether a denied command may still run once with
+    /// a recorded justification.
+    #[serde(default)]
+    pub break_glass: BreakGlassSettings,
+    /// Local host allow/deny lists shown as a reputation verdict in the
+    /// challenge when a command fetches a URL.
+    #[serde(default)]
+    pub url_reputation: UrlReputation,
+    /// Namespace allow/deny lists of the kubernetes checks, evaluated when
+    /// the namespace can be extracted from the command.
+    #[serde(default)]
+    pub kubernetes_namespaces: KubernetesNamespaces,
+}
+
+impl Default for Settings {
+    /// The compiled-in defaults: what a fresh settings file is created from,
+    /// and what portable mode (`--no-config`) runs with directly.
+    fn default() -> Self {
+        Self {
+            challenge: DEFAULT_CHALLENGE,
+            includes: DEFAULT_INCLUDE_CHECKS
+                .iter()
+                .map(std::string::ToString::to_string)
+                .collect::<_>(),
+            ignores_patterns_ids: vec![],
+            deny_patterns_ids: vec![],
+            deny_tags: vec![],
+            tag_escalation: std::collections::BTreeMap::new(),
+            max_subprocess_latency_ms: default_max_subprocess_latency_ms(),
+            network: NetworkMode::default(),
+            checks_bundle_hash: None,
+            deny_rules: vec![],
+            allow: vec![],
+            save_last_command: true,
+            copy_blocked_command_to_clipboard: false,
+            challenge_wordlist: default_challenge_wordlist(),
+            challenge_tuning: ChallengeTuning::default(),
+            totp_secret: None,
+            aliases: std::collections::BTreeMap::new(),
+            summarize_matches_above: 3,
+            escalate_on_unknown_impact: None,
+            escalate_if: EscalateIf::default(),
+            min_severity: None,
+            context_severity_floor: std::collections::BTreeMap::new(),
+            audit: AuditSettings::default(),
+            oidc: None,
+            break_glass: BreakGlassSettings::default(),
+            url_reputation: UrlReputation::default(),
+            kubernetes_namespaces: KubernetesNamespaces::default(),
+        }
+    }
+}
+
+/// Settings of the audit log.
+#[derive(Debug, Default, Deserialize, Serialize, Clone)]
+pub struct AuditSettings {
+    /// Central collector events are uploaded to. `None` keeps the audit log
+    /// local only.
+    #[serde(default)]
+    pub remote: Option<crate::audit::remote::RemoteAudit>,
+}
+
+/// Local URL host reputation lists (`url_reputation` in the settings file),
+/// so a `curl | bash` challenge can say whether the host is one the
+/// organization trusts. Fully offline: no lookup is ever made.
+#[derive(Debug, Default, Deserialize, Serialize, Clone)]
+pub struct UrlReputation {
+    /// Hosts the organization trusts, `*` wildcards supported
+    /// (e.g. `*.github.com`).
+    #[serde(default)]
+    pub allow: Vec<String>,
+    /// Hosts the organization flags, `*` wildcards supported.
+    #[serde(default)]
+    pub deny: Vec<String>,
+}
+
+impl UrlReputation {
+    /// Whether any list is configured, i.e. a verdict is worth showing.
+    #[must_use]
+    pub fn is_configured(&self) -> bool {
+        !self.allow.is_empty() || !self.deny.is_empty()
+    }
+
+    /// The verdict line suffix for the given host. The deny list wins over
+    /// the allow list.
+    #[must_use]
+    pub fn verdict(&self, host: &str) -> &'static str {
+        if self
+            .deny
+            .iter()
+            .any(|pattern| wildcard_match(pattern, host))
+        {
+            "denied by your org"
+        } else if self
+            .allow
+            .iter()
+            .any(|pattern| wildcard_match(pattern, host))
+        {
+            "allowed by your org"
+        } else {
+            "not on your org allowlist"
+        }
+    }
+}
+
+/// Namespace allow/deny lists of the kubernetes checks
+/// (`kubernetes_namespaces` in the settings file), evaluated when the
+/// namespace can be extracted from the command: deny-listed namespaces
+/// (`kube-system`, `prod-*`) are always denied, allow-listed ones (ephemeral
+/// preview namespaces) never challenge.
+#[derive(Debug, Default, Deserialize, Serialize, Clone)]
+pub struct KubernetesNamespaces {
+    /// Namespaces where kubernetes checks never challenge, `*` wildcards
+    /// supported (e.g. `preview-*`).
+    #[serde(default)]
+    pub allow: Vec<String>,
+    /// Namespaces where kubernetes checks are always denied, `*` wildcards
+    /// supported (e.g. `kube-system`, `prod-*`). The deny list wins over the
+    /// allow list.
+    #[serde(default)]
+    pub deny: Vec<String>,
+}
+
+impl KubernetesNamespaces {
+    /// Is the given namespace on the deny list.
+    #[must_use]
+    pub fn is_denied(&self, namespace: &str) -> bool {
+        self.deny
+            .iter()
+            .any(|pattern| wildcard_match(pattern, namespace))
+    }
+
+    /// Is the given namespace on the allow list (and not on the deny list,
+    /// which wins).
+    #[must_use]
+    pub fn is_allowed(&self, namespace: &str) -> bool {
+        !self.is_denied(namespace)
+            && self
+                .allow
+                .iter()
+                .any(|pattern| wildcard_match(pattern, namespace))
+    }
+}
+
+/// Break-glass settings (`break_glass` in the settings file): a pragmatic
+/// middle ground between a hard deny and no control, for teams where the
+/// denied command is sometimes the right call during an incident.
+#[derive(Debug, Default, Deserialize, Serialize, Clone)]
+pub struct BreakGlassSettings {
+    /// Allow running a denied command once after breaking the glass.
+    #[serde(default)]
+    pub allowed: bool,
+    /// What the user has to provide to break the glass. `justification` is
+    /// the only supported requirement today.
+    #[serde(default)]
+    pub requires: BreakGlassRequirement,
+    /// Webhook receiving every break-glass event for review, as a `POST` of
+    /// the audit record. `None` keeps the record in the audit log only.
+    #[serde(default)]
+    pub webhook: Option<String>,
+}
+
+/// What breaking the glass requires from the user.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum BreakGlassRequirement {
+    /// A free-form, non-empty justification, recorded in the audit log.
+    #[default]
+    Justification,
+}
+
+/// Tuning of the generated challenges: some users find the defaults trivially
+/// auto-answerable, others find them too slow under incident pressure.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct ChallengeTuning {
+    /// Lower bound (inclusive) of the math operands.
+    #[serde(default)]
+    pub math_operand_min: u32,
+    /// Upper bound (exclusive) of the math operands.
+    #[serde(default = "default_math_operand_max")]
+    pub math_operand_max: u32,
+    /// Operations the math challenge picks from.
+    #[serde(default = "default_math_operations")]
+    pub math_operations: Vec<MathOperation>,
+    /// Length of the generated word when the wordlist is empty.
+    #[serde(default = "default_word_length")]
+    pub word_length: usize,
+    /// Characters the generated word is built from.
+    #[serde(default = "default_word_charset")]
+    pub word_charset: String,
+    /// Seconds the `Delay` challenge counts down before the command can
+    /// proceed.
+    #[serde(default = "default_delay_seconds")]
+    pub delay_seconds: u64,
+    /// Per-severity overrides of `delay_seconds` (e.g. `Critical: 15`).
+    #[serde(default)]
+    pub delay_seconds_by_severity: std::collections::BTreeMap<checks::Severity, u64>,
+}
+
+impl Default for ChallengeTuning {
+    fn default() -> Self {
+        Self {
+            math_operand_min: 0,
+            math_operand_max: default_math_operand_max(),
+            math_operations: default_math_operations(),
+            word_length: default_word_length(),
+            word_charset: default_word_charset(),
+            delay_seconds: default_delay_seconds(),
+            delay_seconds_by_severity: std::collections::BTreeMap::new(),
+        }
+    }
+}
+
+impl ChallengeTuning {
+    /// The countdown of the `Delay` challenge for a command of the given
+    /// severity: the per-severity override when set, the base otherwise.
+    #[must_use]
+    pub fn delay_for(&self, severity: Option<checks::Severity>) -> u64 {
+        severity
+            .and_then(|severity| self.delay_seconds_by_severity.get(&severity).copied())
+            .unwrap_or(self.delay_seconds)
+    }
+}
+
+/// Default countdown of the `Delay` challenge.
+const fn default_delay_seconds() -> u64 {
+    5
+}
+
+/// Operation of the math challenge.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+pub enum MathOperation {
+    /// Addition.
+    Add,
+    /// Subtraction (operands are ordered so the answer is never negative).
+    Sub,
+    /// Multiplication.
+    Mul,
+}
+
+const fn default_math_operand_max() -> u32 {
+    10
+}
+
+fn default_math_operations() -> Vec<MathOperation> {
+    vec![MathOperation::Add]
+}
+
+const fn default_word_length() -> usize {
+    4
+}
+
+fn default_word_charset() -> String {
+    "abcdefghijklmnopqrstuvwxyz".to_string()
+}
+
+const fn default_summarize_matches_above() -> usize {
+    3
+}
+
+const fn default_true() -> bool {
+    true
+}
+
+/// Default wordlist of the `Word` challenge.
+fn default_challenge_wordlist() -> Vec<String> {
+    ["confirm", "proceed", "approve", "execute"]
+        .iter()
+        .map(ToString::to_string)
+        .collect()
+}
+
+/// The most recent intercepted command and its analysis.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct LastCommand {
+    /// The command that was intercepted.
+    pub command: String,
+    /// Ids of the checks that matched it.
+    pub check_ids: Vec<String>,
+    /// When it was intercepted (seconds since epoch).
+    pub intercepted_at: u64,
+}
+
+/// An allowlist entry: a command the user declared safe, optionally only
+/// inside a directory. The pattern must cover the whole command segment, so
+/// `rm -rf \./node_modules` does not also allow `rm -rf ./node_modules /`.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct AllowRule {
+    /// Pattern the whole command segment must match.
+    #[serde(with = "serde_regex")]
+    pub pattern: regex::Regex,
+    /// Only apply when the working directory is this directory or below
+    /// (`~` is expanded). `None` applies everywhere.
+    #[serde(default)]
+    pub directory: Option<String>,
+}
+
+impl AllowRule {
+    /// Does the pattern cover the given command segment entirely.
+    fn covers(&self, segment: &str) -> bool {
+        self.pattern
+            .find(segment)
+            .is_some_and(|found| found.start() == 0 && found.end() == segment.len())
+    }
+
+    /// Does the directory scope include the working directory of the given
+    /// environment. A rule without scope applies everywhere.
+    fn applies_in(&self, environment: &dyn crate::environment::Environment) -> bool {
+        let Some(directory) = &self.directory else {
+            return true;
+        };
+        let (Some(scope), Some(current_dir)) = (
+            environment.canonicalize(directory),
+            environment
+                .current_dir()
+                .and_then(|current_dir| environment.canonicalize(&current_dir)),
+        ) else {
+            return false;
+        };
+        let scope = scope.trim_end_matches('/');
+        current_dir == scope || current_dir.starts_with(&format!("{scope}/"))
+    }
+}
+
+/// Blast radius thresholds connecting the measured impact numbers to
+/// enforcement: when a probe counts more than a threshold allows, the
+/// challenge escalates instead of the numbers staying informational only.
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct EscalateIf {
+    /// Counted files threshold, e.g. `">10000"`.
+    #[serde(default)]
+    pub files: Option<String>,
+    /// Measured size threshold, e.g. `">50GB"`.
+    #[serde(default)]
+    pub size: Option<String>,
+    /// Counted commits threshold, e.g. `">100"`.
+    #[serde(default)]
+    pub commits: Option<String>,
+    /// Challenge applied when a threshold is crossed. `None` escalates to
+    /// `Yes`.
+    #[serde(default)]
+    pub challenge: Option<Challenge>,
+}
+
+impl EscalateIf {
+    /// The challenge applied when a threshold is crossed.
+    #[must_use]
+    pub fn challenge(&self) -> Challenge {
+        self.challenge.clone().unwrap_or(Challenge::Yes)
+    }
+
+    /// Is any threshold crossed by the given measured blast radius.
+    #[must_use]
+    pub fn exceeded_by(&self, radius: &checks::BlastRadius) -> bool {
+        let crossed = |threshold: &Option<String>, measured: Option<u64>, size: bool| {
+            let (Some(threshold), Some(measured)) = (threshold, measured) else {
+                return false;
+            };
+            parse_threshold(threshold, size).is_some_and(|limit| measured > limit)
+        };
+        crossed(&self.files, radius.files, false)
+            || crossed(&self.size, radius.size_bytes, true)
+            || crossed(&self.commits, radius.commits, false)
+    }
+}
+
+/// Parse a `">N"` threshold, with an optional `KB` / `MB` / `GB` / `TB`
+/// suffix when `size` thresholds are parsed. Returns `None` for anything
+/// malformed, which disables the threshold instead of guessing.
+fn parse_threshold(spec: &str, size: bool) -> Option<u64> {
+    let spec = spec.trim().strip_prefix('>')?.trim();
+    if !size {
+        return spec.parse().ok();
+    }
+    let unit_start = spec
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(spec.len());
+    let number: f64 = spec[..unit_start].trim().parse().ok()?;
+    let multiplier: f64 = match spec[unit_start..].trim().to_uppercase().as_str() {
+        "" | "B" => 1.0,
+        "K" | "KB" => 1024.0,
+        "M" | "MB" => 1024.0 * 1024.0,
+        "G" | "GB" => 1024.0 * 1024.0 * 1024.0,
+        "T" | "TB" => 1024.0 * 1024.0 * 1024.0 * 1024.0,
+        _ => return None,
+    };
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    Some((number * multiplier) as u64)
+}
+
+/// Deny a check only when a condition holds, so a deny does not have to be
+/// all-or-nothing across clusters.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct DenyRule {
+    /// Check id to deny.
+    pub id: String,
+    /// Condition limiting the rule. `None` always denies.
+    #[serde(default)]
+    pub when: Option<DenyCondition>,
+}
+
+/// Condition of a [`DenyRule`].
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct DenyCondition {
+    /// Active kubectl context, `*` wildcards supported (e.g. `prod-*`).
+    #[serde(default)]
+    pub k8s_context: Option<String>,
+    /// Typing identity on a shared account (ssh-agent key fingerprint or tty
+    /// origin), `*` wildcards supported, so a deny can escalate per person.
+    #[serde(default)]
+    pub identity: Option<String>,
+    /// Skip the deny when the logged in identity (see `shellfirm login`) has
+    /// this role, e.g. deny `db:*` unless `dba`.
+    #[serde(default)]
+    pub unless_role: Option<String>,
+    /// Command provenance (`interactive`, `script`, `ci`, `agent:<name>`),
+    /// `*` wildcards supported, so automation can be denied outright while a
+    /// person gets a challenge.
+    #[serde(default)]
+    pub source: Option<String>,
+}
+
+impl DenyCondition {
+    /// Check if the condition holds in the given environment.
+    fn holds(&self, environment: &dyn crate::environment::Environment) -> bool {
+        if let Some(pattern) = &self.k8s_context {
+            let context = environment
+                .run_command("kubectl config current-context")
+                .unwrap_or_default();
+            if !wildcard_match(pattern, &context) {
+                return false;
+            }
+        }
+        if let Some(pattern) = &self.identity {
+            let identity = crate::context::detect_identity(environment).unwrap_or_default();
+            if !wildcard_match(pattern, &identity) {
+                return false;
+            }
+        }
+        if let Some(pattern) = &self.source {
+            let source = crate::context::detect_source(environment).to_string();
+            if !wildcard_match(pattern, &source) {
+                return false;
+            }
+        }
+        if let Some(role) = &self.unless_role {
+            let exempt = crate::oidc::cached_identity(environment)
+                .is_some_and(|identity| identity.roles.iter().any(|r| wildcard_match(role, r)));
+            if exempt {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// Match a value against a pattern where `*` matches any substring.
+fn wildcard_match(pattern: &str, value: &str) -> bool {
+    regex::Regex::new(&format!(
+        "^{}$",
+        regex::escape(pattern).replace(r"\*", ".*")
+    ))
+    .is_ok_and(|re| re.is_match(value))
+}
+
+/// Every key the settings file recognizes at the top level, kept in the
+/// declaration order of [`Settings`], for typo detection on load and
+/// cross-checked against the exported JSON Schema.
+pub(crate) const SETTINGS_KEYS: &[&str] = &[
+    "challenge",
+    "includes",
+    "ignores_patterns_ids",
+    "deny_patterns_ids",
+    "deny_tags",
+    "tag_escalation",
+    "max_subprocess_latency_ms",
+    "network",
+    "checks_bundle_hash",
+    "deny_rules",
+    "allow",
+    "save_last_command",
+    "copy_blocked_command_to_clipboard",
+    "challenge_wordlist",
+    "challenge_tuning",
+    "totp_secret",
+    "aliases",
+    "summarize_matches_above",
+    "escalate_on_unknown_impact",
+    "escalate_if",
+    "min_severity",
+    "context_severity_floor",
+    "audit",
+    "oidc",
+    "break_glass",
+    "url_reputation",
+    "kubernetes_namespaces",
+];
+
+/// Parse the settings file content. A mistyped key fails loudly with a
+/// nearest-key suggestion instead of being silently ignored (a recurring
+/// cause of "my customization does nothing"), and parse errors keep serde's
+/// expected-type and line/column report.
+fn parse_settings(content: &str) -> AnyResult<Settings> {
+    let value: serde_yaml::Value = serde_yaml::from_str(content)?;
+    let unknown_key = value.as_mapping().and_then(|mapping| {
+        mapping
+            .iter()
+            .filter_map(|(key, _)| key.as_str())
+            .find(|key| !SETTINGS_KEYS.contains(key))
+    });
+    if let Some(key) = unknown_key {
+        match closest_settings_key(key) {
+            Some(suggestion) => {
+                bail!("unknown settings key `{key}` (did you mean `{suggestion}`?)")
+            }
+            None => bail!("unknown settings key `{key}`"),
+        }
+    }
+    serde_yaml::from_str(content)
+        .map_err(|err| anyhow::anyhow!("could not parse the settings file: {err}"))
+}
+
+/// Return the known settings key closest to the given one, when one is close
+/// enough to look like a typo.
+fn closest_settings_key(key: &str) -> Option<&'static str> {
+    SETTINGS_KEYS
+        .iter()
+        .map(|candidate| (edit_distance(key, candidate), *candidate))
+        .min()
+        .filter(|(distance, _)| *distance <= 3)
+        .map(|(_, candidate)| candidate)
+}
+
+/// Levenshtein distance between the two strings.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b_chars: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b_chars.len()).collect();
+    for (row, a_char) in a.chars().enumerate() {
+        let mut current = vec![row + 1];
+        for (column, b_char) in b_chars.iter().enumerate() {
+            let substitution = previous[column] + usize::from(a_char != *b_char);
+            current.push(
+                substitution
+                    .min(previous[column + 1] + 1)
+                    .min(current[column] + 1),
+            );
+        }
+        previous = current;
+    }
+    previous[b_chars.len()]
+}
+
+/// Default subprocess latency budget, used when the field is missing from an
+/// existing settings file.
+fn default_max_subprocess_latency_ms() -> u64 {
+    crate::environment::DEFAULT_SUBPROCESS_TIMEOUT.as_millis() as u64
 }
 
 impl fmt::Display for Challenge {
@@ -60,6 +763,9 @@ impl fmt::Display for Challenge {
             Self::Math => write!(f, "Math"),
             Self::Enter => write!(f, "Enter"),
             Self::Yes => write!(f, "Yes"),
+            Self::Word => write!(f, "Word"),
+            Self::Totp => write!(f, "Totp"),
+            Self::Delay => write!(f, "Delay"),
         }
     }
 }
@@ -71,6 +777,33 @@ impl Default for Challenge {
 }
 
 impl Challenge {
+    /// How demanding the challenge is, for escalation: a per-check or
+    /// per-tag challenge only ever replaces a less demanding one.
+    #[must_use]
+    pub const fn strength(&self) -> u8 {
+        match self {
+            Self::Enter => 0,
+            Self::Delay => 1,
+            Self::Math => 2,
+            Self::Yes => 3,
+            Self::Word => 4,
+            Self::Totp => 5,
+        }
+    }
+
+    /// The next stronger challenge, used when a command's blast profile
+    /// steps up (e.g. a risky command wrapped in an amplifier). The
+    /// strongest challenge escalates to itself.
+    #[must_use]
+    pub const fn escalated(&self) -> Self {
+        match self {
+            Self::Enter | Self::Delay => Self::Math,
+            Self::Math => Self::Yes,
+            Self::Yes => Self::Word,
+            Self::Word | Self::Totp => Self::Totp,
+        }
+    }
+
     /// Convert challenge string to enum
     ///
     /// # Errors
@@ -80,6 +813,9 @@ impl Challenge {
             "math" => Ok(Self::Math),
             "enter" => Ok(Self::Enter),
             "yes" => Ok(Self::Yes),
+            "word" => Ok(Self::Word),
+            "totp" => Ok(Self::Totp),
+            "delay" => Ok(Self::Delay),
             _ => bail!("given challenge name not found"),
         }
     }
@@ -136,7 +872,111 @@ impl Config {
     ///
     /// Will return `Err` has an error when loading the config file
     pub fn get_settings_from_file(&self) -> AnyResult<Settings> {
-        Ok(serde_yaml::from_str(&self.read_config_file()?)?)
+        let settings = parse_settings(&self.read_config_file()?)?;
+        if let Some(recorded_hash) = &settings.checks_bundle_hash {
+            let active_hash = checks::bundle_hash();
+            if recorded_hash != &active_hash {
+                log::warn!(
+                    "checks bundle changed since the settings file was written (recorded {}, \
+                     active {}). run `shellfirm config reset` to re-record it",
+                    recorded_hash,
+                    active_hash
+                );
+            }
+        }
+        Ok(settings)
+    }
+
+    /// Load user custom checks from the `checks` folder inside the config
+    /// folder. Failures are isolated per file and per entry: the valid checks
+    /// are returned together with the load errors, and one broken file never
+    /// aborts the whole pipeline.
+    #[must_use]
+    pub fn load_custom_checks(&self) -> (Vec<checks::Check>, Vec<checks::CustomCheckError>) {
+        let mut custom_checks: Vec<checks::Check> = Vec::new();
+        let mut errors: Vec<checks::CustomCheckError> = Vec::new();
+
+        let folder = Path::new(&self.root_folder).join(CUSTOM_CHECKS_FOLDER_NAME);
+        let Ok(entries) = fs::read_dir(&folder) else {
+            return (custom_checks, errors);
+        };
+
+        let mut files: Vec<PathBuf> = entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| {
+                path.extension()
+                    .is_some_and(|extension| extension == "yaml" || extension == "yml")
+            })
+            .collect();
+        files.sort();
+
+        for file in files {
+            let file_name = file.display().to_string();
+            match fs::read_to_string(&file) {
+                Ok(content) => {
+                    let (file_checks, file_errors) =
+                        checks::parse_custom_checks(&content, &file_name);
+                    custom_checks.extend(file_checks);
+                    errors.extend(file_errors);
+                }
+                Err(err) => errors.push(checks::CustomCheckError {
+                    file: file_name,
+                    line: None,
+                    id: None,
+                    error: err.to_string(),
+                }),
+            }
+        }
+
+        for error in &errors {
+            log::warn!(
+                "skipping broken custom check (file: {}, id: {:?}, line: {:?}): {}",
+                error.file,
+                error.id,
+                error.line,
+                error.error
+            );
+        }
+
+        (custom_checks, errors)
+    }
+
+    /// Keep the intercepted command so `shellfirm last` can show it.
+    ///
+    /// # Arguments
+    ///
+    /// * `command` - the intercepted command.
+    /// * `check_ids` - ids of the checks that matched it.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` when the file could not be written.
+    pub fn record_last_command(&self, command: &str, check_ids: Vec<String>) -> AnyResult<()> {
+        let last_command = LastCommand {
+            command: command.to_string(),
+            check_ids,
+            intercepted_at: SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs(),
+        };
+        fs::write(
+            Path::new(&self.root_folder).join(LAST_COMMAND_FILE_NAME),
+            serde_yaml::to_string(&last_command)?,
+        )?;
+        Ok(())
+    }
+
+    /// Return the most recent intercepted command, or `None` when nothing was
+    /// intercepted yet.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` when the file exists but could not be parsed.
+    pub fn get_last_command(&self) -> AnyResult<Option<LastCommand>> {
+        let path = Path::new(&self.root_folder).join(LAST_COMMAND_FILE_NAME);
+        if !path.exists() {
+            return Ok(None);
+        }
+        Ok(Some(serde_yaml::from_str(&fs::read_to_string(path)?)?))
     }
 
     /// Manage setting folder & file.
@@ -187,6 +1027,46 @@ impl Config {
         self.save_settings_file_from_struct(&settings)?;
         Ok(())
     }
+
+    /// Update the TOTP secret of the `Totp` challenge (`None` un-enrolls).
+    ///
+    /// # Arguments
+    ///
+    /// * `totp_secret` - the new base32 secret
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` error return on load/save config
+    pub fn update_totp_secret(&self, totp_secret: Option<String>) -> AnyResult<()> {
+        let mut settings = self.get_settings_from_file()?;
+        settings.totp_secret = totp_secret;
+        self.save_settings_file_from_struct(&settings)?;
+        Ok(())
+    }
+
+    /// Merge the given aliases into the stored alias map, keeping entries that
+    /// are not re-imported.
+    ///
+    /// # Arguments
+    ///
+    /// * `aliases` - alias name to expansion
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` error return on load/save config
+    pub fn update_aliases(
+        &self,
+        aliases: &std::collections::BTreeMap<String, String>,
+    ) -> AnyResult<()> {
+        let mut settings = self.get_settings_from_file()?;
+        settings.aliases.extend(
+            aliases
+                .iter()
+                .map(|(name, expansion)| (name.clone(), expansion.clone())),
+        );
+        self.save_settings_file_from_struct(&settings)?;
+        Ok(())
+    }
     /// Reset user configuration to the default app.
     ///
     /// # Errors
@@ -210,6 +1090,143 @@ impl Config {
         Ok(())
     }
 
+    /// Check if command interception is turned on. Protection can be turned
+    /// off with [`Config::set_enabled`] or per session with the
+    /// `SHELLFIRM_DISABLE` environment variable.
+    #[must_use]
+    pub fn is_enabled(&self) -> bool {
+        if env::var("SHELLFIRM_DISABLE").is_ok() {
+            return false;
+        }
+        !self.disabled_file_path().exists()
+    }
+
+    /// Turn command interception on / off by managing the disabled marker
+    /// file.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` when the marker file could not be created / deleted
+    pub fn set_enabled(&self, enabled: bool) -> AnyResult<()> {
+        let disabled_file = self.disabled_file_path();
+        if enabled {
+            if disabled_file.exists() {
+                fs::remove_file(&disabled_file)?;
+            }
+        } else {
+            fs::File::create(&disabled_file)?;
+        }
+        Ok(())
+    }
+
+    fn disabled_file_path(&self) -> PathBuf {
+        PathBuf::from(&self.root_folder).join(DISABLED_FILE_NAME)
+    }
+
+    /// Increment the intercepted commands counter of the current day.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` when the stats file could not be saved
+    pub fn increment_block_counter(&self) -> AnyResult<()> {
+        let mut stats = self.read_stats_file();
+        let today = current_day_stamp();
+        *stats.entry(today).or_insert(0) += 1;
+        let content = serde_yaml::to_string(&stats)?;
+        fs::write(self.stats_file_path(), content)?;
+        Ok(())
+    }
+
+    /// Return how many commands were intercepted today.
+    #[must_use]
+    pub fn get_block_count_today(&self) -> u64 {
+        *self
+            .read_stats_file()
+            .get(&current_day_stamp())
+            .unwrap_or(&0)
+    }
+
+    fn read_stats_file(&self) -> std::collections::BTreeMap<String, u64> {
+        fs::read_to_string(self.stats_file_path())
+            .ok()
+            .and_then(|content| serde_yaml::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    fn stats_file_path(&self) -> PathBuf {
+        PathBuf::from(&self.root_folder).join(STATS_FILE_NAME)
+    }
+
+    /// Record a heartbeat for the given shell PID, showing that the hook in
+    /// that shell session is firing. Old heartbeat files are pruned.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` when the heartbeat file could not be written
+    pub fn record_heartbeat(&self, shell_pid: &str) -> AnyResult<()> {
+        let heartbeat_folder = self.heartbeats_folder_path();
+        fs::create_dir_all(&heartbeat_folder)?;
+
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        fs::write(heartbeat_folder.join(shell_pid), now.to_string())?;
+
+        // best effort pruning of heartbeats from dead shells
+        if let Ok(entries) = fs::read_dir(&heartbeat_folder) {
+            for entry in entries.filter_map(std::result::Result::ok) {
+                let expired = fs::read_to_string(entry.path())
+                    .ok()
+                    .and_then(|c| c.trim().parse::<u64>().ok())
+                    .is_none_or(|t| now.saturating_sub(t) > HEARTBEAT_TTL_SECONDS);
+                if expired {
+                    let _ = fs::remove_file(entry.path());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Return how many seconds ago the hook of the given shell PID last
+    /// fired, or `None` when it never did.
+    #[must_use]
+    pub fn get_heartbeat_age(&self, shell_pid: &str) -> Option<u64> {
+        let recorded = fs::read_to_string(self.heartbeats_folder_path().join(shell_pid))
+            .ok()?
+            .trim()
+            .parse::<u64>()
+            .ok()?;
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).ok()?.as_secs();
+        Some(now.saturating_sub(recorded))
+    }
+
+    fn heartbeats_folder_path(&self) -> PathBuf {
+        PathBuf::from(&self.root_folder).join(HEARTBEATS_FOLDER_NAME)
+    }
+
+    /// Path of the audit log file.
+    #[must_use]
+    pub fn audit_file_path(&self) -> PathBuf {
+        PathBuf::from(&self.root_folder).join(crate::audit::AUDIT_FILE_NAME)
+    }
+
+    /// Path of the offline spool of the remote audit collector.
+    #[must_use]
+    pub fn audit_spool_path(&self) -> PathBuf {
+        PathBuf::from(&self.root_folder).join(crate::audit::remote::SPOOL_FILE_NAME)
+    }
+
+    /// Path of the cached login identity.
+    #[must_use]
+    pub fn identity_file_path(&self) -> PathBuf {
+        PathBuf::from(&self.root_folder).join(crate::oidc::IDENTITY_FILE_NAME)
+    }
+
+    /// Path of the SQLite audit database.
+    #[cfg(feature = "audit-sqlite")]
+    #[must_use]
+    pub fn audit_sqlite_path(&self) -> PathBuf {
+        PathBuf::from(&self.root_folder).join(crate::audit::sqlite::SQLITE_FILE_NAME)
+    }
+
     /// Create config folder if not exists.
     fn create_config_folder(&self) -> AnyResult<()> {
         if let Err(err) = fs::create_dir(&self.root_folder) {
@@ -226,13 +1243,8 @@ impl Config {
     /// Create config file from default template.
     fn create_default_settings_file(&self) -> AnyResult<()> {
         self.save_settings_file_from_struct(&Settings {
-            challenge: DEFAULT_CHALLENGE,
-            includes: DEFAULT_INCLUDE_CHECKS
-                .iter()
-                .map(std::string::ToString::to_string)
-                .collect::<_>(),
-            ignores_patterns_ids: vec![],
-            deny_patterns_ids: vec![],
+            checks_bundle_hash: Some(checks::bundle_hash()),
+            ..Settings::default()
         })
     }
 
@@ -299,6 +1311,32 @@ impl Config {
         self.save_settings_file_from_struct(&settings)?;
         Ok(())
     }
+
+    /// Update the minimum intercepted severity (`None` intercepts
+    /// everything).
+    ///
+    /// # Arguments
+    /// * `min_severity` - new severity floor.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` when could not load/save config
+    pub fn update_min_severity(&self, min_severity: Option<checks::Severity>) -> AnyResult<()> {
+        let mut settings = self.get_settings_from_file()?;
+        settings.min_severity = min_severity;
+        self.save_settings_file_from_struct(&settings)?;
+        Ok(())
+    }
+}
+
+/// Return the current day as `YYYY-MM-DD`-like stamp (days since epoch is
+/// enough for bucketing, no timezone dependency).
+fn current_day_stamp() -> String {
+    let days = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() / (60 * 60 * 24))
+        .unwrap_or(0);
+    format!("day-{days}")
 }
 
 impl Settings {
@@ -312,6 +1350,7 @@ impl Settings {
             .iter()
             .filter(|&c| self.includes.contains(&c.from))
             .filter(|&c| !self.ignores_patterns_ids.contains(&c.id))
+            .filter(|&c| c.applies_to_os(std::env::consts::OS))
             .cloned()
             .collect::<Vec<_>>())
     }
@@ -320,6 +1359,256 @@ impl Settings {
     pub fn get_active_groups(&self) -> &Vec<String> {
         &self.includes
     }
+
+    /// Return all denied check ids that apply in the given environment: the
+    /// always-denied self-protection check, the flat deny list and every
+    /// conditional deny rule whose condition holds.
+    ///
+    /// # Arguments
+    ///
+    /// * `environment` - environment the command is going to run in.
+    #[must_use]
+    pub fn active_deny_patterns_ids(
+        &self,
+        environment: &dyn crate::environment::Environment,
+    ) -> Vec<String> {
+        // shellfirm's own config directory is protected by default:
+        // deleting it silently disables every guardrail
+        let mut deny_ids = vec![checks::SELF_PROTECTION_CHECK_ID.to_string()];
+        deny_ids.extend(self.deny_patterns_ids.iter().cloned());
+        for rule in &self.deny_rules {
+            if deny_ids.contains(&rule.id) {
+                continue;
+            }
+            let applies = rule
+                .when
+                .as_ref()
+                .is_none_or(|condition| condition.holds(environment));
+            if applies {
+                deny_ids.push(rule.id.to_string());
+            }
+        }
+        deny_ids
+    }
+
+    /// Is the given check denied through its tags, i.e. does it carry one of
+    /// the `deny_tags`.
+    ///
+    /// # Arguments
+    ///
+    /// * `check` - the check to test.
+    #[must_use]
+    pub fn is_denied_by_tag(&self, check: &checks::Check) -> bool {
+        check.tags.iter().any(|tag| self.deny_tags.contains(tag))
+    }
+
+    /// Return the challenge to prompt for the given matched checks: the
+    /// configured challenge, escalated by the `tag_escalation` entries of
+    /// the matched tags. Escalation only ever raises the challenge, never
+    /// lowers it.
+    ///
+    /// # Arguments
+    ///
+    /// * `checks` - matched checks.
+    #[must_use]
+    pub fn challenge_for(&self, checks: &[checks::Check]) -> Challenge {
+        let mut strongest = self.challenge.clone();
+        for candidate in checks.iter().flat_map(|check| {
+            check
+                .tags
+                .iter()
+                .filter_map(|tag| self.tag_escalation.get(tag))
+        }) {
+            if candidate.strength() > strongest.strength() {
+                strongest = candidate.clone();
+            }
+        }
+        strongest
+    }
+
+    /// Return the severity floor the current context imposes: the highest
+    /// floor among the configured labels that match the detected context.
+    ///
+    /// # Arguments
+    ///
+    /// * `environment` - environment the command is going to run in.
+    #[must_use]
+    pub fn active_severity_floor(
+        &self,
+        environment: &dyn crate::environment::Environment,
+    ) -> Option<checks::Severity> {
+        if self.context_severity_floor.is_empty() {
+            return None;
+        }
+        let context = crate::context::detect(environment);
+        self.context_severity_floor
+            .iter()
+            .filter(|(label, _)| context_label_matches(label, &context))
+            .map(|(_, floor)| *floor)
+            .max()
+    }
+
+    /// Drop matched checks whose effective severity (raised to the context
+    /// floor) is below `min_severity`, keeping the sites and spans
+    /// consistent with the remaining matches.
+    ///
+    /// # Arguments
+    ///
+    /// * `analysis` - analysis to filter in place.
+    /// * `environment` - environment the command is going to run in.
+    pub fn apply_severity_filter(
+        &self,
+        analysis: &mut checks::Analysis,
+        environment: &dyn crate::environment::Environment,
+    ) {
+        let Some(min_severity) = self.min_severity else {
+            return;
+        };
+        let floor = self.active_severity_floor(environment);
+        analysis.matches.retain(|check| {
+            floor.map_or(check.severity, |f| check.severity.max(f)) >= min_severity
+        });
+        let kept: Vec<String> = analysis
+            .matches
+            .iter()
+            .map(|check| check.id.to_string())
+            .collect();
+        analysis
+            .match_sites
+            .retain(|site| kept.contains(&site.check_id));
+        analysis
+            .matched_spans
+            .retain(|span| kept.contains(&span.check_id));
+    }
+
+    /// Drop matches the allowlist covers: a command segment an applicable
+    /// [`AllowRule`] matches entirely is treated as safe, and checks that
+    /// only matched allowed segments no longer challenge.
+    ///
+    /// # Arguments
+    ///
+    /// * `analysis` - the analysis to filter.
+    /// * `command` - the original command line.
+    /// * `environment` - environment the command is going to run in.
+    pub fn apply_allow_rules(
+        &self,
+        analysis: &mut checks::Analysis,
+        command: &str,
+        environment: &dyn crate::environment::Environment,
+    ) {
+        if self.allow.is_empty() || analysis.matches.is_empty() {
+            return;
+        }
+        let applicable: Vec<&AllowRule> = self
+            .allow
+            .iter()
+            .filter(|rule| rule.applies_in(environment))
+            .collect();
+        if applicable.is_empty() {
+            return;
+        }
+        let risky_segments: Vec<String> = crate::command::parse_and_split_command(command)
+            .iter()
+            .map(|segment| {
+                crate::command::strip_privilege_escalation(segment)
+                    .0
+                    .trim()
+                    .to_string()
+            })
+            .filter(|segment| !applicable.iter().any(|rule| rule.covers(segment)))
+            .collect();
+        analysis.matches.retain(|check| {
+            risky_segments
+                .iter()
+                .any(|segment| check.test.is_match(segment))
+        });
+        let kept: Vec<String> = analysis
+            .matches
+            .iter()
+            .map(|check| check.id.to_string())
+            .collect();
+        analysis
+            .match_sites
+            .retain(|site| kept.contains(&site.check_id));
+        analysis
+            .matched_spans
+            .retain(|span| kept.contains(&span.check_id));
+    }
+
+    /// Drop kubernetes matches when the command targets an allow-listed
+    /// namespace (e.g. an ephemeral preview namespace), keeping the sites and
+    /// spans consistent with the remaining matches.
+    ///
+    /// # Arguments
+    ///
+    /// * `analysis` - the analysis to filter.
+    /// * `command` - the original command line.
+    pub fn apply_namespace_rules(&self, analysis: &mut checks::Analysis, command: &str) {
+        if self.kubernetes_namespaces.allow.is_empty() || analysis.matches.is_empty() {
+            return;
+        }
+        let Some(namespace) = crate::command::extract_kubernetes_namespace(command) else {
+            return;
+        };
+        if !self.kubernetes_namespaces.is_allowed(&namespace) {
+            return;
+        }
+        analysis
+            .matches
+            .retain(|check| !check.from.starts_with("kubernetes"));
+        let kept: Vec<String> = analysis
+            .matches
+            .iter()
+            .map(|check| check.id.to_string())
+            .collect();
+        analysis
+            .match_sites
+            .retain(|site| kept.contains(&site.check_id));
+        analysis
+            .matched_spans
+            .retain(|span| kept.contains(&span.check_id));
+    }
+
+    /// The namespace the command targets when it is on the deny list, so the
+    /// caller can deny the matched kubernetes checks.
+    ///
+    /// # Arguments
+    ///
+    /// * `command` - the original command line.
+    #[must_use]
+    pub fn denied_namespace(&self, command: &str) -> Option<String> {
+        if self.kubernetes_namespaces.deny.is_empty() {
+            return None;
+        }
+        crate::command::extract_kubernetes_namespace(command)
+            .filter(|namespace| self.kubernetes_namespaces.is_denied(namespace))
+    }
+}
+
+/// Check if a context label like `k8s=prod-*`, `branch=main`, `ssh=true`,
+/// `user=SHA256:*` or `source=agent:*` matches the detected context (`*`
+/// wildcards supported in the value).
+fn context_label_matches(label: &str, context: &crate::context::Context) -> bool {
+    let Some((key, pattern)) = label.split_once('=') else {
+        return false;
+    };
+    match key.trim() {
+        "k8s" => context
+            .k8s_context
+            .as_ref()
+            .is_some_and(|value| wildcard_match(pattern.trim(), value)),
+        "branch" => context
+            .git_branch
+            .as_ref()
+            .is_some_and(|value| wildcard_match(pattern.trim(), value)),
+        "ssh" => context.ssh.to_string() == pattern.trim(),
+        "user" => context
+            .identity
+            .as_ref()
+            .is_some_and(|value| wildcard_match(pattern.trim(), value)),
+        "source" => wildcard_match(pattern.trim(), &context.source.to_string()),
+        _ => false,
+    }
 }
 
 #[cfg(test)]
@@ -336,6 +1625,18 @@ mod test_config {
         Config::new(Some(&temp_dir.display().to_string())).unwrap()
     }
 
+    // settings snapshots redact the checks bundle hash, so adding a check
+    // does not rewrite every settings snapshot
+    macro_rules! assert_settings_snapshot {
+        ($settings:expr) => {
+            insta::with_settings!({filters => vec![
+                (r"[0-9a-f]{64}", "[CHECKS_BUNDLE_HASH]"),
+            ]}, {
+                assert_debug_snapshot!($settings);
+            });
+        };
+    }
+
     #[test]
     fn can_crate_new_config() {
         let temp_dir = TempDir::new("config-app").unwrap();
@@ -350,10 +1651,33 @@ mod test_config {
         let temp_dir = TempDir::new("config-app").unwrap();
         let config = initialize_config_folder(&temp_dir);
 
-        assert_debug_snapshot!(config.get_settings_from_file());
+        assert_settings_snapshot!(config.get_settings_from_file());
         temp_dir.close().unwrap();
     }
 
+    #[test]
+    fn can_suggest_settings_key_on_typo() {
+        assert_debug_snapshot!(parse_settings(
+            "challenge: Math\nincludes: []\nignores_patterns_ids: []\ndeny_pattern_ids: []\n"
+        )
+        .unwrap_err()
+        .to_string());
+        assert_debug_snapshot!(parse_settings(
+            "challenge: Math\nincludes: []\nignores_patterns_ids: []\ndeny_patterns_ids: []\ntotally_made_up_key: 1\n"
+        )
+        .unwrap_err()
+        .to_string());
+    }
+
+    #[test]
+    fn can_report_settings_type_errors_with_location() {
+        assert_debug_snapshot!(parse_settings(
+            "challenge: Math\nincludes: []\nignores_patterns_ids: []\ndeny_patterns_ids: []\nsummarize_matches_above: banana\n"
+        )
+        .unwrap_err()
+        .to_string());
+    }
+
     #[test]
     fn can_manage_config_file() {
         let temp_dir = TempDir::new("config-app").unwrap();
@@ -376,11 +1700,11 @@ mod test_config {
     fn can_add_check_groups() {
         let temp_dir = TempDir::new("config-app").unwrap();
         let config = initialize_config_folder(&temp_dir);
-        assert_debug_snapshot!(config.get_settings_from_file());
+        assert_settings_snapshot!(config.get_settings_from_file());
         config
             .update_check_groups(vec!["group-1".to_string(), "group-2".to_string()])
             .unwrap();
-        assert_debug_snapshot!(config.get_settings_from_file());
+        assert_settings_snapshot!(config.get_settings_from_file());
         temp_dir.close().unwrap();
     }
 
@@ -389,9 +1713,9 @@ mod test_config {
         let temp_dir = TempDir::new("config-app").unwrap();
         let config = initialize_config_folder(&temp_dir);
 
-        assert_debug_snapshot!(config.get_settings_from_file());
+        assert_settings_snapshot!(config.get_settings_from_file());
         config.update_challenge(Challenge::Yes).unwrap();
-        assert_debug_snapshot!(config.get_settings_from_file());
+        assert_settings_snapshot!(config.get_settings_from_file());
         temp_dir.close().unwrap();
     }
 
@@ -400,11 +1724,11 @@ mod test_config {
         let temp_dir = TempDir::new("config-app").unwrap();
         let config = initialize_config_folder(&temp_dir);
 
-        assert_debug_snapshot!(config.get_settings_from_file());
+        assert_settings_snapshot!(config.get_settings_from_file());
         config
             .update_ignores_pattern_ids(vec!["id-1".to_string(), "id-2".to_string()])
             .unwrap();
-        assert_debug_snapshot!(config.get_settings_from_file());
+        assert_settings_snapshot!(config.get_settings_from_file());
         temp_dir.close().unwrap();
     }
 
@@ -413,11 +1737,520 @@ mod test_config {
         let temp_dir = TempDir::new("config-app").unwrap();
         let config = initialize_config_folder(&temp_dir);
 
-        assert_debug_snapshot!(config.get_settings_from_file());
+        assert_settings_snapshot!(config.get_settings_from_file());
         config
             .update_deny_pattern_ids(vec!["id-1".to_string(), "id-2".to_string()])
             .unwrap();
-        assert_debug_snapshot!(config.get_settings_from_file());
+        assert_settings_snapshot!(config.get_settings_from_file());
+        temp_dir.close().unwrap();
+    }
+
+    #[test]
+    fn can_set_enabled() {
+        let temp_dir = TempDir::new("config-app").unwrap();
+        let config = initialize_config_folder(&temp_dir);
+        assert_debug_snapshot!(config.is_enabled());
+        config.set_enabled(false).unwrap();
+        assert_debug_snapshot!(config.is_enabled());
+        config.set_enabled(true).unwrap();
+        assert_debug_snapshot!(config.is_enabled());
+        temp_dir.close().unwrap();
+    }
+
+    #[test]
+    fn can_count_blocks() {
+        let temp_dir = TempDir::new("config-app").unwrap();
+        let config = initialize_config_folder(&temp_dir);
+        assert_debug_snapshot!(config.get_block_count_today());
+        config.increment_block_counter().unwrap();
+        config.increment_block_counter().unwrap();
+        assert_debug_snapshot!(config.get_block_count_today());
+        temp_dir.close().unwrap();
+    }
+
+    #[test]
+    fn can_record_last_command() {
+        let temp_dir = TempDir::new("config-app").unwrap();
+        let config = initialize_config_folder(&temp_dir);
+        assert_debug_snapshot!(config.get_last_command().unwrap().is_none());
+        config
+            .record_last_command("rm -rf /", vec!["fs:recursively_delete".to_string()])
+            .unwrap();
+        let last_command = config.get_last_command().unwrap().unwrap();
+        assert_debug_snapshot!((last_command.command, last_command.check_ids));
+        temp_dir.close().unwrap();
+    }
+
+    #[test]
+    fn can_resolve_conditional_deny_rules() {
+        use crate::environment::MockEnvironment;
+
+        let settings = Settings {
+            challenge: DEFAULT_CHALLENGE,
+            includes: vec![],
+            ignores_patterns_ids: vec![],
+            deny_patterns_ids: vec!["fs:recursively_delete".to_string()],
+            deny_tags: vec![],
+            tag_escalation: std::collections::BTreeMap::new(),
+            max_subprocess_latency_ms: 500,
+            network: NetworkMode::default(),
+            checks_bundle_hash: None,
+            save_last_command: true,
+            copy_blocked_command_to_clipboard: false,
+            challenge_wordlist: default_challenge_wordlist(),
+            challenge_tuning: ChallengeTuning::default(),
+            totp_secret: None,
+            aliases: std::collections::BTreeMap::new(),
+            summarize_matches_above: 3,
+            escalate_on_unknown_impact: None,
+            escalate_if: EscalateIf::default(),
+            min_severity: None,
+            context_severity_floor: std::collections::BTreeMap::new(),
+            audit: AuditSettings::default(),
+            oidc: None,
+            break_glass: BreakGlassSettings::default(),
+            url_reputation: UrlReputation::default(),
+            kubernetes_namespaces: KubernetesNamespaces::default(),
+            allow: vec![],
+            deny_rules: vec![DenyRule {
+                id: "kubernetes:delete_namespace".to_string(),
+                when: Some(DenyCondition {
+                    k8s_context: Some("prod-*".to_string()),
+                    identity: None,
+                    unless_role: None,
+                    source: None,
+                }),
+            }],
+        };
+
+        let production = MockEnvironment::builder().k8s("prod-eu-1").build();
+        let staging = MockEnvironment::builder().k8s("staging").build();
+        assert_debug_snapshot!(settings.active_deny_patterns_ids(&production));
+        assert_debug_snapshot!(settings.active_deny_patterns_ids(&staging));
+    }
+
+    #[test]
+    fn can_resolve_identity_deny_rules() {
+        use crate::environment::MockEnvironment;
+
+        let settings = Settings {
+            challenge: DEFAULT_CHALLENGE,
+            includes: vec![],
+            ignores_patterns_ids: vec![],
+            deny_patterns_ids: vec![],
+            deny_tags: vec![],
+            tag_escalation: std::collections::BTreeMap::new(),
+            max_subprocess_latency_ms: 500,
+            network: NetworkMode::default(),
+            checks_bundle_hash: None,
+            save_last_command: true,
+            copy_blocked_command_to_clipboard: false,
+            challenge_wordlist: default_challenge_wordlist(),
+            challenge_tuning: ChallengeTuning::default(),
+            totp_secret: None,
+            aliases: std::collections::BTreeMap::new(),
+            summarize_matches_above: 3,
+            escalate_on_unknown_impact: None,
+            escalate_if: EscalateIf::default(),
+            min_severity: None,
+            context_severity_floor: std::collections::BTreeMap::new(),
+            audit: AuditSettings::default(),
+            oidc: None,
+            break_glass: BreakGlassSettings::default(),
+            url_reputation: UrlReputation::default(),
+            kubernetes_namespaces: KubernetesNamespaces::default(),
+            allow: vec![],
+            deny_rules: vec![DenyRule {
+                id: "git:force_push".to_string(),
+                when: Some(DenyCondition {
+                    k8s_context: None,
+                    identity: Some("SHA256:intern-*".to_string()),
+                    unless_role: None,
+                    source: None,
+                }),
+            }],
+        };
+
+        let intern = MockEnvironment::builder()
+            .env_var("SSH_AUTH_SOCK", "/tmp/agent.sock")
+            .command_output(
+                "ssh-add -l",
+                "256 SHA256:intern-key intern@laptop (ED25519)",
+            )
+            .build();
+        let senior = MockEnvironment::builder()
+            .env_var("SSH_AUTH_SOCK", "/tmp/agent.sock")
+            .command_output(
+                "ssh-add -l",
+                "256 SHA256:senior-key senior@laptop (ED25519)",
+            )
+            .build();
+        assert_debug_snapshot!(settings.active_deny_patterns_ids(&intern));
+        assert_debug_snapshot!(settings.active_deny_patterns_ids(&senior));
+    }
+
+    #[test]
+    fn can_resolve_source_deny_rules() {
+        use crate::environment::MockEnvironment;
+
+        let settings = Settings {
+            challenge: DEFAULT_CHALLENGE,
+            includes: vec![],
+            ignores_patterns_ids: vec![],
+            deny_patterns_ids: vec![],
+            deny_tags: vec![],
+            tag_escalation: std::collections::BTreeMap::new(),
+            max_subprocess_latency_ms: 500,
+            network: NetworkMode::default(),
+            checks_bundle_hash: None,
+            save_last_command: true,
+            copy_blocked_command_to_clipboard: false,
+            challenge_wordlist: default_challenge_wordlist(),
+            challenge_tuning: ChallengeTuning::default(),
+            totp_secret: None,
+            aliases: std::collections::BTreeMap::new(),
+            summarize_matches_above: 3,
+            escalate_on_unknown_impact: None,
+            escalate_if: EscalateIf::default(),
+            min_severity: None,
+            context_severity_floor: std::collections::BTreeMap::new(),
+            audit: AuditSettings::default(),
+            oidc: None,
+            break_glass: BreakGlassSettings::default(),
+            url_reputation: UrlReputation::default(),
+            kubernetes_namespaces: KubernetesNamespaces::default(),
+            allow: vec![],
+            deny_rules: vec![DenyRule {
+                id: "k8s:delete".to_string(),
+                when: Some(DenyCondition {
+                    k8s_context: None,
+                    identity: None,
+                    unless_role: None,
+                    source: Some("agent:*".to_string()),
+                }),
+            }],
+        };
+
+        let agent = MockEnvironment::builder()
+            .env_var(crate::context::SOURCE_ENV_VAR, "agent:deploy-bot")
+            .build();
+        // a person typing only gets the regular challenge
+        assert_debug_snapshot!(settings.active_deny_patterns_ids(&agent));
+        assert_debug_snapshot!(settings.active_deny_patterns_ids(&MockEnvironment::default()));
+    }
+
+    #[test]
+    fn can_detect_include_groups() {
+        use crate::environment::MockEnvironment;
+
+        // no tools found, only the static defaults
+        assert_debug_snapshot!(detect_include_groups(&MockEnvironment::default()));
+        assert_debug_snapshot!(detect_include_groups(
+            &MockEnvironment::builder()
+                .command_output("kubectl --version", "v1.30.0")
+                .command_output("psql --version", "psql (PostgreSQL) 16.3")
+                .build()
+        ));
+    }
+
+    #[test]
+    fn can_escalate_challenge_by_tag() {
+        let mut settings = Settings {
+            challenge: DEFAULT_CHALLENGE,
+            includes: vec![],
+            ignores_patterns_ids: vec![],
+            deny_patterns_ids: vec![],
+            deny_tags: vec![],
+            tag_escalation: std::collections::BTreeMap::new(),
+            max_subprocess_latency_ms: 500,
+            network: NetworkMode::default(),
+            checks_bundle_hash: None,
+            save_last_command: true,
+            copy_blocked_command_to_clipboard: false,
+            challenge_wordlist: default_challenge_wordlist(),
+            challenge_tuning: ChallengeTuning::default(),
+            totp_secret: None,
+            aliases: std::collections::BTreeMap::new(),
+            summarize_matches_above: 3,
+            escalate_on_unknown_impact: None,
+            escalate_if: EscalateIf::default(),
+            min_severity: None,
+            context_severity_floor: std::collections::BTreeMap::new(),
+            audit: AuditSettings::default(),
+            oidc: None,
+            break_glass: BreakGlassSettings::default(),
+            url_reputation: UrlReputation::default(),
+            kubernetes_namespaces: KubernetesNamespaces::default(),
+            deny_rules: vec![],
+            allow: vec![],
+        };
+        settings
+            .tag_escalation
+            .insert("irreversible".to_string(), Challenge::Word);
+
+        let checks: Vec<checks::Check> = serde_yaml::from_str(
+            r"
+- id: fs:recursively_delete
+  test: rm -rf
+  description: deletes everything
+  from: fs
+  tags: [data-loss, irreversible]
+- id: git:checkout
+  test: git checkout
+  description: drops local changes
+  from: git
+",
+        )
+        .unwrap();
+
+        // the tagged check escalates, the untagged one keeps the default
+        assert_debug_snapshot!(settings.challenge_for(&checks));
+        assert_debug_snapshot!(settings.challenge_for(&checks[1..]));
+    }
+
+    #[test]
+    fn can_apply_context_severity_floor() {
+        use crate::environment::MockEnvironment;
+
+        let mut settings = Settings {
+            challenge: DEFAULT_CHALLENGE,
+            includes: vec![],
+            ignores_patterns_ids: vec![],
+            deny_patterns_ids: vec![],
+            deny_tags: vec![],
+            tag_escalation: std::collections::BTreeMap::new(),
+            max_subprocess_latency_ms: 500,
+            network: NetworkMode::default(),
+            checks_bundle_hash: None,
+            save_last_command: true,
+            copy_blocked_command_to_clipboard: false,
+            challenge_wordlist: default_challenge_wordlist(),
+            challenge_tuning: ChallengeTuning::default(),
+            totp_secret: None,
+            aliases: std::collections::BTreeMap::new(),
+            summarize_matches_above: 3,
+            escalate_on_unknown_impact: None,
+            escalate_if: EscalateIf::default(),
+            min_severity: Some(checks::Severity::High),
+            context_severity_floor: std::collections::BTreeMap::new(),
+            audit: AuditSettings::default(),
+            oidc: None,
+            break_glass: BreakGlassSettings::default(),
+            url_reputation: UrlReputation::default(),
+            kubernetes_namespaces: KubernetesNamespaces::default(),
+            deny_rules: vec![],
+            allow: vec![],
+        };
+        settings
+            .context_severity_floor
+            .insert("k8s=prod-*".to_string(), checks::Severity::High);
+
+        let checks: Vec<checks::Check> = serde_yaml::from_str(
+            r"
+- id: git:checkout
+  test: git checkout
+  description: drops local changes
+  from: git
+  severity: Low
+",
+        )
+        .unwrap();
+
+        let production = MockEnvironment::builder().k8s("prod-eu-1").build();
+        let staging = MockEnvironment::builder().k8s("staging").build();
+
+        // the low severity check is filtered in staging, but the production
+        // floor raises it above the minimum
+        let mut analysis = checks::analyze_command(&checks, "git checkout main", &staging);
+        settings.apply_severity_filter(&mut analysis, &staging);
+        assert_debug_snapshot!(analysis
+            .matches
+            .iter()
+            .map(|c| c.id.to_string())
+            .collect::<Vec<_>>());
+
+        let mut analysis = checks::analyze_command(&checks, "git checkout main", &production);
+        settings.apply_severity_filter(&mut analysis, &production);
+        assert_debug_snapshot!(analysis
+            .matches
+            .iter()
+            .map(|c| c.id.to_string())
+            .collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn can_apply_allow_rules() {
+        use crate::environment::MockEnvironment;
+
+        let settings = Settings {
+            allow: vec![AllowRule {
+                pattern: regex::Regex::new(r"rm -rf \./node_modules").unwrap(),
+                directory: Some("~/work/frontend".to_string()),
+            }],
+            ..Settings::default()
+        };
+        let checks: Vec<checks::Check> = serde_yaml::from_str(
+            r"
+- id: fs:recursively_delete
+  test: rm.+-rf
+  description: deletes everything
+  from: fs
+",
+        )
+        .unwrap();
+
+        let inside = MockEnvironment::builder()
+            .env_var("HOME", "/home/dev")
+            .current_dir("/home/dev/work/frontend/app")
+            .build();
+        let outside = MockEnvironment::builder()
+            .env_var("HOME", "/home/dev")
+            .current_dir("/home/dev/other")
+            .build();
+
+        // the ritual command stops prompting inside the scoped directory
+        let mut analysis = checks::analyze_command(&checks, "rm -rf ./node_modules", &inside);
+        settings.apply_allow_rules(&mut analysis, "rm -rf ./node_modules", &inside);
+        assert_debug_snapshot!(analysis
+            .matches
+            .iter()
+            .map(|c| c.id.to_string())
+            .collect::<Vec<_>>());
+
+        // outside the scope the rule does not apply
+        let mut analysis = checks::analyze_command(&checks, "rm -rf ./node_modules", &outside);
+        settings.apply_allow_rules(&mut analysis, "rm -rf ./node_modules", &outside);
+        assert_debug_snapshot!(analysis
+            .matches
+            .iter()
+            .map(|c| c.id.to_string())
+            .collect::<Vec<_>>());
+
+        // a compound line keeps the segment the rule does not cover
+        let command = "rm -rf ./node_modules && rm -rf /";
+        let mut analysis = checks::analyze_command(&checks, command, &inside);
+        settings.apply_allow_rules(&mut analysis, command, &inside);
+        assert_debug_snapshot!(analysis
+            .matches
+            .iter()
+            .map(|c| c.id.to_string())
+            .collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn can_apply_namespace_rules() {
+        use crate::environment::MockEnvironment;
+
+        let settings = Settings {
+            kubernetes_namespaces: KubernetesNamespaces {
+                allow: vec!["preview-*".to_string()],
+                deny: vec!["kube-system".to_string(), "prod-*".to_string()],
+            },
+            ..Settings::default()
+        };
+        let checks: Vec<checks::Check> = serde_yaml::from_str(
+            r"
+- id: kubernetes:delete_all_pods
+  test: kubectl\s+delete\s+pods\s+--all
+  description: deletes every pod
+  from: kubernetes
+",
+        )
+        .unwrap();
+        let environment = MockEnvironment::default();
+        let matched_ids = |analysis: &checks::Analysis| {
+            analysis
+                .matches
+                .iter()
+                .map(|c| c.id.to_string())
+                .collect::<Vec<_>>()
+        };
+
+        // an ephemeral preview namespace never challenges
+        let command = "kubectl delete pods --all -n preview-42";
+        let mut analysis = checks::analyze_command(&checks, command, &environment);
+        settings.apply_namespace_rules(&mut analysis, command);
+        assert_debug_snapshot!(matched_ids(&analysis));
+
+        // any other namespace keeps the match
+        let command = "kubectl delete pods --all -n payments";
+        let mut analysis = checks::analyze_command(&checks, command, &environment);
+        settings.apply_namespace_rules(&mut analysis, command);
+        assert_debug_snapshot!(matched_ids(&analysis));
+
+        // a deny-listed namespace is never treated as allowed
+        let command = "kubectl delete pods --all -n kube-system";
+        let mut analysis = checks::analyze_command(&checks, command, &environment);
+        settings.apply_namespace_rules(&mut analysis, command);
+        assert_debug_snapshot!(matched_ids(&analysis));
+    }
+
+    #[test]
+    fn can_find_the_denied_namespace() {
+        let settings = Settings {
+            kubernetes_namespaces: KubernetesNamespaces {
+                allow: vec!["preview-*".to_string()],
+                deny: vec!["kube-system".to_string(), "prod-*".to_string()],
+            },
+            ..Settings::default()
+        };
+        assert_debug_snapshot!(
+            settings.denied_namespace("kubectl delete pods --all -n kube-system")
+        );
+        assert_debug_snapshot!(
+            settings.denied_namespace("kubectl drain node-1 --namespace prod-eu1")
+        );
+        assert_debug_snapshot!(settings.denied_namespace("kubectl delete pods --all -n preview-42"));
+        assert_debug_snapshot!(settings.denied_namespace("kubectl delete pods --all"));
+    }
+
+    #[test]
+    fn can_escalate_on_blast_radius_thresholds() {
+        let escalate_if = EscalateIf {
+            files: Some(">10000".to_string()),
+            size: Some(">50GB".to_string()),
+            commits: Some(">100".to_string()),
+            challenge: None,
+        };
+        let radius = |files, size_bytes, commits| checks::BlastRadius {
+            files,
+            size_bytes,
+            commits,
+        };
+        assert_debug_snapshot!(escalate_if.exceeded_by(&radius(Some(20_000), None, None)));
+        assert_debug_snapshot!(escalate_if.exceeded_by(&radius(Some(10_000), None, None)));
+        assert_debug_snapshot!(escalate_if.exceeded_by(&radius(
+            None,
+            Some(60 * 1024 * 1024 * 1024),
+            None
+        )));
+        assert_debug_snapshot!(escalate_if.exceeded_by(&radius(None, None, Some(101))));
+        assert_debug_snapshot!(escalate_if.exceeded_by(&radius(None, None, None)));
+        assert_debug_snapshot!(escalate_if.challenge());
+
+        // a malformed threshold is disabled instead of guessed at
+        let malformed = EscalateIf {
+            files: Some("10000".to_string()),
+            size: Some(">fifty GB".to_string()),
+            commits: None,
+            challenge: Some(Challenge::Word),
+        };
+        assert_debug_snapshot!(malformed.exceeded_by(&radius(
+            Some(u64::MAX),
+            Some(u64::MAX),
+            None
+        )));
+        assert_debug_snapshot!(malformed.challenge());
+    }
+
+    #[test]
+    fn can_record_heartbeat() {
+        let temp_dir = TempDir::new("config-app").unwrap();
+        let config = initialize_config_folder(&temp_dir);
+        assert_debug_snapshot!(config.get_heartbeat_age("4242"));
+        config.record_heartbeat("4242").unwrap();
+        assert_debug_snapshot!(config.get_heartbeat_age("4242").is_some());
+        assert_debug_snapshot!(config.get_heartbeat_age("9999"));
         temp_dir.close().unwrap();
     }
 
@@ -426,9 +2259,9 @@ mod test_config {
         let temp_dir = TempDir::new("config-app").unwrap();
         let config = initialize_config_folder(&temp_dir);
         config.update_challenge(Challenge::Yes).unwrap();
-        assert_debug_snapshot!(config.get_settings_from_file());
+        assert_settings_snapshot!(config.get_settings_from_file());
         config.reset_config(Some(0)).unwrap();
-        assert_debug_snapshot!(config.get_settings_from_file());
+        assert_settings_snapshot!(config.get_settings_from_file());
         assert_debug_snapshot!(read_dir(config.root_folder).unwrap().count());
         temp_dir.close().unwrap();
     }
@@ -438,12 +2271,24 @@ mod test_config {
         let temp_dir = TempDir::new("config-app").unwrap();
         let config = initialize_config_folder(&temp_dir);
         config.update_challenge(Challenge::Yes).unwrap();
-        assert_debug_snapshot!(config.get_settings_from_file());
+        assert_settings_snapshot!(config.get_settings_from_file());
         config.reset_config(Some(1)).unwrap();
-        assert_debug_snapshot!(config.get_settings_from_file());
+        assert_settings_snapshot!(config.get_settings_from_file());
         assert_debug_snapshot!(read_dir(config.root_folder).unwrap().count());
         temp_dir.close().unwrap();
     }
+
+    #[test]
+    fn can_resolve_the_delay_for_a_severity() {
+        let tuning = ChallengeTuning {
+            delay_seconds_by_severity: [(checks::Severity::Critical, 15)].into_iter().collect(),
+            ..ChallengeTuning::default()
+        };
+        assert_debug_snapshot!(tuning.delay_for(Some(checks::Severity::Critical)));
+        // no override for this severity: the base countdown applies
+        assert_debug_snapshot!(tuning.delay_for(Some(checks::Severity::Low)));
+        assert_debug_snapshot!(tuning.delay_for(None));
+    }
 }
 
 #[cfg(test)]
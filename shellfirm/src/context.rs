@@ -0,0 +1,343 @@
+//! Detect context signals (SSH session, kubectl context, git branch) from an
+//! [`Environment`], with support for explicit overrides so tests, demos and
+//! wrapper scripts can force specific signals.
+
+use std::fmt;
+
+use anyhow::{bail, Result};
+use serde_derive::Serialize;
+
+use crate::environment::Environment;
+
+/// Environment variable carrying the command provenance, set by the
+/// `--source` flag of `pre-command` or exported by the calling hook/CI job.
+pub const SOURCE_ENV_VAR: &str = "SHELLFIRM_COMMAND_SOURCE";
+
+/// Context signals of the environment a command is going to run in.
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+pub struct Context {
+    /// Shell is running inside an SSH session.
+    pub ssh: bool,
+    /// Active kubectl context, when kubectl is configured.
+    pub k8s_context: Option<String>,
+    /// Checked out git branch, when inside a repository.
+    pub git_branch: Option<String>,
+    /// Who is really typing on a shared account, when it can be told apart
+    /// (ssh-agent key fingerprint or tty origin).
+    pub identity: Option<String>,
+    /// Where the command came from, when the hook reported it.
+    pub source: Source,
+}
+
+/// Where a command came from, as reported with `--source`: a person typing is
+/// not the same risk as a script replaying, and policies can differ by it.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum Source {
+    /// Typed by a person at an interactive prompt (the default).
+    #[default]
+    Interactive,
+    /// Run from a shell script.
+    Script,
+    /// Run by a CI job.
+    Ci,
+    /// Issued by a named automation agent.
+    Agent(String),
+}
+
+impl Source {
+    /// Parse a source spec like `interactive`, `script`, `ci` or
+    /// `agent:<name>`.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` on an unknown source.
+    pub fn parse(value: &str) -> Result<Self> {
+        match value.trim() {
+            "interactive" => Ok(Self::Interactive),
+            "script" => Ok(Self::Script),
+            "ci" => Ok(Self::Ci),
+            other => {
+                if let Some(name) = other.strip_prefix("agent:") {
+                    return Ok(Self::Agent(name.to_string()));
+                }
+                bail!("unknown command source `{other}` (expected interactive/script/ci/agent:<name>)");
+            }
+        }
+    }
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Interactive => write!(f, "interactive"),
+            Self::Script => write!(f, "script"),
+            Self::Ci => write!(f, "ci"),
+            Self::Agent(name) => write!(f, "agent:{name}"),
+        }
+    }
+}
+
+impl serde::Serialize for Source {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// Detect the context signals from the given environment.
+///
+/// # Arguments
+///
+/// * `environment` - environment the command is going to run in.
+#[must_use]
+pub fn detect(environment: &dyn Environment) -> Context {
+    Context {
+        ssh: environment.env_var("SSH_CONNECTION").is_some()
+            || environment.env_var("SSH_TTY").is_some(),
+        k8s_context: environment.run_command("kubectl config current-context"),
+        git_branch: environment.run_command("git symbolic-ref --short HEAD"),
+        identity: detect_identity(environment),
+        source: detect_source(environment),
+    }
+}
+
+/// Return the kubectl context (or kubens namespace) the command is about to
+/// switch into, when it is a `kubectl config use-context` or `kubens`
+/// invocation. The switch takes effect before any later command runs, so the
+/// caller should evaluate against the target context, not the current one.
+///
+/// # Arguments
+///
+/// * `command` - the original command line.
+#[must_use]
+pub fn pending_k8s_context(command: &str) -> Option<String> {
+    let tokens: Vec<&str> = command.split_whitespace().collect();
+    for (index, token) in tokens.iter().enumerate() {
+        let target = match *token {
+            "use-context" if index >= 2 && tokens[index - 2..index] == ["kubectl", "config"] => {
+                tokens.get(index + 1)
+            }
+            "kubens" => tokens.get(index + 1),
+            _ => None,
+        };
+        if let Some(target) = target.filter(|target| !target.starts_with('-')) {
+            return Some((*target).to_string());
+        }
+    }
+    None
+}
+
+/// Detect where the command came from: the source reported through
+/// [`SOURCE_ENV_VAR`], interactive otherwise.
+#[must_use]
+pub fn detect_source(environment: &dyn Environment) -> Source {
+    environment
+        .env_var(SOURCE_ENV_VAR)
+        .and_then(|value| Source::parse(&value).ok())
+        .unwrap_or_default()
+}
+
+/// Identify who is really typing on a shared `deploy`/`root` account: the
+/// fingerprint of the first ssh-agent key when an agent is present, otherwise
+/// the tty origin reported by `who am i`.
+#[must_use]
+pub fn detect_identity(environment: &dyn Environment) -> Option<String> {
+    if environment.env_var("SSH_AUTH_SOCK").is_some() {
+        if let Some(fingerprint) = environment
+            .run_command("ssh-add -l")
+            .as_deref()
+            .and_then(|listing| listing.lines().next()?.split_whitespace().nth(1))
+        {
+            return Some(fingerprint.to_string());
+        }
+    }
+    environment.run_command("who am i").and_then(|line| {
+        let origin = line
+            .split_whitespace()
+            .last()
+            .and_then(|token| token.strip_prefix('(').and_then(|t| t.strip_suffix(')')))
+            .map(ToString::to_string);
+        origin.or_else(|| line.split_whitespace().next().map(ToString::to_string))
+    })
+}
+
+/// Explicit context overrides parsed from `--assume-context`.
+#[derive(Debug, Default, Clone)]
+pub struct Overrides {
+    /// Force the SSH signal on or off.
+    pub ssh: Option<bool>,
+    /// Force the active kubectl context.
+    pub k8s_context: Option<String>,
+    /// Force the checked out git branch.
+    pub git_branch: Option<String>,
+    /// Force the typing identity.
+    pub identity: Option<String>,
+    /// Force the command source.
+    pub source: Option<Source>,
+}
+
+impl Overrides {
+    /// Parse an override spec like `k8s=prod,branch=main,ssh=true`.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` on an unknown key or a malformed pair.
+    pub fn parse(spec: &str) -> Result<Self> {
+        let mut overrides = Self::default();
+        for pair in spec.split(',').filter(|pair| !pair.is_empty()) {
+            let Some((key, value)) = pair.split_once('=') else {
+                bail!("malformed context override `{pair}`, expected key=value");
+            };
+            match key.trim() {
+                "ssh" => overrides.ssh = Some(value.trim() == "true"),
+                "k8s" => overrides.k8s_context = Some(value.trim().to_string()),
+                "branch" => overrides.git_branch = Some(value.trim().to_string()),
+                "user" => overrides.identity = Some(value.trim().to_string()),
+                "source" => overrides.source = Some(Source::parse(value)?),
+                unknown => {
+                    bail!(
+                        "unknown context override `{unknown}` (expected ssh/k8s/branch/user/source)"
+                    );
+                }
+            }
+        }
+        Ok(overrides)
+    }
+}
+
+/// [`Environment`] layering explicit context overrides over a base
+/// environment: overridden signals answer from the overrides, everything else
+/// delegates to the base.
+pub struct OverriddenEnvironment {
+    base: Box<dyn Environment>,
+    overrides: Overrides,
+}
+
+impl OverriddenEnvironment {
+    /// Layer the given overrides over the base environment.
+    #[must_use]
+    pub fn new(base: Box<dyn Environment>, overrides: Overrides) -> Self {
+        Self { base, overrides }
+    }
+}
+
+impl Environment for OverriddenEnvironment {
+    fn env_var(&self, key: &str) -> Option<String> {
+        if matches!(key, "SSH_CONNECTION" | "SSH_TTY") {
+            if let Some(ssh) = self.overrides.ssh {
+                return ssh.then(|| "10.0.0.1 22 10.0.0.2 22".to_string());
+            }
+        }
+        // a forced identity hides the agent, so it wins over the fingerprint
+        if key == "SSH_AUTH_SOCK" && self.overrides.identity.is_some() {
+            return None;
+        }
+        if key == SOURCE_ENV_VAR {
+            if let Some(source) = &self.overrides.source {
+                return Some(source.to_string());
+            }
+        }
+        self.base.env_var(key)
+    }
+
+    fn path_exists(&self, path: &str) -> bool {
+        self.base.path_exists(path)
+    }
+
+    fn current_dir(&self) -> Option<String> {
+        self.base.current_dir()
+    }
+
+    fn run_command(&self, command: &str) -> Option<String> {
+        if command == "kubectl config current-context" {
+            if let Some(context) = &self.overrides.k8s_context {
+                return Some(context.to_string());
+            }
+        }
+        if command == "git symbolic-ref --short HEAD" {
+            if let Some(branch) = &self.overrides.git_branch {
+                return Some(branch.to_string());
+            }
+        }
+        if command == "who am i" {
+            if let Some(identity) = &self.overrides.identity {
+                return Some(format!("{identity} pts/0"));
+            }
+        }
+        self.base.run_command(command)
+    }
+}
+
+#[cfg(test)]
+mod test_context {
+    use insta::assert_debug_snapshot;
+
+    use super::*;
+    use crate::environment::{fixtures, MockEnvironment};
+
+    #[test]
+    fn can_detect_identity() {
+        let agent = MockEnvironment::builder()
+            .env_var("SSH_AUTH_SOCK", "/tmp/agent.sock")
+            .command_output("ssh-add -l", "256 SHA256:AbCdEf person@laptop (ED25519)")
+            .build();
+        assert_debug_snapshot!(detect_identity(&agent));
+
+        let remote_tty = MockEnvironment::builder()
+            .command_output("who am i", "deploy   pts/0  2026-08-31 10:00 (192.168.1.5)")
+            .build();
+        assert_debug_snapshot!(detect_identity(&remote_tty));
+
+        let local_tty = MockEnvironment::builder()
+            .command_output("who am i", "deploy tty1")
+            .build();
+        assert_debug_snapshot!(detect_identity(&local_tty));
+
+        assert_debug_snapshot!(detect_identity(&MockEnvironment::default()));
+    }
+
+    #[test]
+    fn can_detect_source() {
+        let agent = MockEnvironment::builder()
+            .env_var(SOURCE_ENV_VAR, "agent:deploy-bot")
+            .build();
+        assert_debug_snapshot!(detect_source(&agent));
+        assert_debug_snapshot!(detect_source(&MockEnvironment::default()));
+        assert_debug_snapshot!(Source::parse("robot").unwrap_err().to_string());
+    }
+
+    #[test]
+    fn can_detect_context() {
+        assert_debug_snapshot!(detect(&fixtures::local_dev()));
+        assert_debug_snapshot!(detect(&fixtures::production_ssh()));
+    }
+
+    #[test]
+    fn can_find_the_pending_k8s_context() {
+        assert_debug_snapshot!(pending_k8s_context("kubectl config use-context prod-eu1"));
+        assert_debug_snapshot!(pending_k8s_context("kubens prod"));
+        assert_debug_snapshot!(pending_k8s_context(
+            "sudo kubectl config use-context staging"
+        ));
+        // listing and flag-only forms switch nothing
+        assert_debug_snapshot!(pending_k8s_context("kubectl config get-contexts"));
+        assert_debug_snapshot!(pending_k8s_context("kubens --help"));
+        assert_debug_snapshot!(pending_k8s_context("kubectl get pods"));
+    }
+
+    #[test]
+    fn can_parse_overrides() {
+        let overrides = Overrides::parse("k8s=prod,branch=main,ssh=true").unwrap();
+        assert_debug_snapshot!(overrides);
+        assert_debug_snapshot!(Overrides::parse("k8s").is_err());
+        assert_debug_snapshot!(Overrides::parse("cluster=prod").is_err());
+    }
+
+    #[test]
+    fn overrides_win_over_the_base_environment() {
+        let environment = OverriddenEnvironment::new(
+            Box::new(fixtures::production_ssh()),
+            Overrides::parse("k8s=staging,ssh=false").unwrap(),
+        );
+        assert_debug_snapshot!(detect(&environment));
+    }
+}
@@ -0,0 +1,182 @@
+//! Crash reporting: a panic hook writing a redacted report under the config
+//! folder so users have something concrete to attach to a bug report, and
+//! helpers `shellfirm diag bundle` uses to collect the reports.
+//!
+//! Reports are redacted before they touch the disk: home directories are
+//! collapsed to `~` and the intercepted command line is never included —
+//! only the last pipeline stage is recorded, so a report is safe to share.
+
+use std::{
+    path::Path,
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use lazy_static::lazy_static;
+use regex::Regex;
+
+/// File name prefix of crash reports under the config folder.
+pub const CRASH_REPORT_PREFIX: &str = "crash-";
+
+lazy_static! {
+    /// Home directories of other users leaking through backtrace paths.
+    static ref REGEX_HOME_PATH: Regex =
+        Regex::new(r"(/home|/Users)/[^/\s'\x22]+").unwrap();
+    /// The pipeline stage the process reached last, recorded in the report
+    /// instead of the command line itself.
+    static ref LAST_STAGE: Mutex<&'static str> = Mutex::new("startup");
+}
+
+/// Record the pipeline stage the process is entering, so a crash report can
+/// say how far it got without including what was typed.
+pub fn set_stage(stage: &'static str) {
+    if let Ok(mut last_stage) = LAST_STAGE.lock() {
+        *last_stage = stage;
+    }
+}
+
+/// Return the last recorded pipeline stage.
+#[must_use]
+pub fn last_stage() -> &'static str {
+    LAST_STAGE.lock().map_or("startup", |stage| *stage)
+}
+
+/// Redact the given text: the given home directory and any other user home
+/// directory are collapsed, everything else is left alone.
+///
+/// # Arguments
+///
+/// * `text` - text to redact.
+/// * `home` - home directory of the current user.
+#[must_use]
+pub fn redact(text: &str, home: Option<&str>) -> String {
+    let text = match home {
+        Some(home) if !home.is_empty() => text.replace(home.trim_end_matches('/'), "~"),
+        _ => text.to_string(),
+    };
+    REGEX_HOME_PATH.replace_all(&text, "$1/~").to_string()
+}
+
+/// Render a crash report. Pure so the exact on-disk format is covered by
+/// tests; the panic hook fills in the live values.
+///
+/// # Arguments
+///
+/// * `version` - crate version.
+/// * `bundle_hash` - content hash of the compiled-in checks bundle.
+/// * `stage` - last recorded pipeline stage.
+/// * `panic_message` - what the panic said.
+/// * `backtrace` - captured backtrace.
+/// * `home` - home directory of the current user, redacted from the report.
+#[must_use]
+pub fn render_crash_report(
+    version: &str,
+    bundle_hash: &str,
+    stage: &str,
+    panic_message: &str,
+    backtrace: &str,
+    home: Option<&str>,
+) -> String {
+    format!(
+        "shellfirm crash report\nversion: {version}\nchecks bundle hash: {bundle_hash}\nlast \
+         pipeline stage: {stage}\npanic: {}\nbacktrace:\n{}",
+        redact(panic_message, home),
+        redact(backtrace, home),
+    )
+}
+
+/// Install the panic hook. On panic a redacted crash report is written under
+/// the given folder and a one-line pointer is printed; the default hook is
+/// not chained so the raw (unredacted) panic does not reach the terminal of
+/// a user who is going to copy-paste it.
+///
+/// # Arguments
+///
+/// * `root_folder` - the config folder the report is written under.
+pub fn install_panic_hook(root_folder: String) {
+    std::panic::set_hook(Box::new(move |panic_info| {
+        let home = dirs::home_dir().map(|home| home.display().to_string());
+        let report = render_crash_report(
+            env!("CARGO_PKG_VERSION"),
+            &crate::checks::bundle_hash(),
+            last_stage(),
+            &panic_info.to_string(),
+            &std::backtrace::Backtrace::force_capture().to_string(),
+            home.as_deref(),
+        );
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_secs());
+        let path = format!("{root_folder}/{CRASH_REPORT_PREFIX}{timestamp}.txt");
+        if std::fs::write(&path, report).is_ok() {
+            eprintln!(
+                "shellfirm crashed; a redacted report was written to {path} — attach it to a bug \
+                 report with `shellfirm diag bundle`"
+            );
+        } else {
+            eprintln!("shellfirm crashed and could not write a crash report to {path}");
+        }
+    }));
+}
+
+/// Return the crash report file names under the given folder, oldest first.
+///
+/// # Arguments
+///
+/// * `root_folder` - the config folder crash reports are written under.
+#[must_use]
+pub fn crash_report_files(root_folder: &str) -> Vec<String> {
+    let mut files: Vec<String> = Path::new(root_folder)
+        .read_dir()
+        .map(|entries| {
+            entries
+                .filter_map(std::result::Result::ok)
+                .filter_map(|entry| entry.file_name().into_string().ok())
+                .filter(|name| name.starts_with(CRASH_REPORT_PREFIX) && name.ends_with(".txt"))
+                .collect()
+        })
+        .unwrap_or_default();
+    files.sort();
+    files
+}
+
+#[cfg(test)]
+mod test_diagnostics {
+    use insta::assert_debug_snapshot;
+    use tempdir::TempDir;
+
+    use super::*;
+
+    #[test]
+    fn can_redact_home_directories() {
+        assert_debug_snapshot!(redact(
+            "thread panicked at /home/dev/work/src/main.rs:1",
+            Some("/home/dev")
+        ));
+        assert_debug_snapshot!(redact("at /Users/someone/.cargo/registry/src/lib.rs", None));
+        assert_debug_snapshot!(redact("no paths here", Some("/home/dev")));
+    }
+
+    #[test]
+    fn can_render_crash_report() {
+        assert_debug_snapshot!(render_crash_report(
+            "1.0.0",
+            "abc123",
+            "analyze",
+            "panicked at 'boom', /home/dev/work/src/checks.rs:10",
+            "0: shellfirm::checks::analyze\n1: main",
+            Some("/home/dev"),
+        ));
+    }
+
+    #[test]
+    fn can_list_crash_report_files() {
+        let temp_dir = TempDir::new("diagnostics").unwrap();
+        let root = temp_dir.path().to_str().unwrap();
+        std::fs::write(temp_dir.path().join("crash-2.txt"), "b").unwrap();
+        std::fs::write(temp_dir.path().join("crash-1.txt"), "a").unwrap();
+        std::fs::write(temp_dir.path().join("settings.yaml"), "x").unwrap();
+        assert_debug_snapshot!(crash_report_files(root));
+        assert_debug_snapshot!(crash_report_files("/nonexistent"));
+    }
+}
@@ -55,6 +55,18 @@ pub fn reset_config() -> Result<usize> {
     }
 }
 
+/// prompt yes/no confirmation
+///
+/// # Errors
+///
+/// Will return `Err` when interact error
+pub fn confirm(message: &str) -> Result<bool> {
+    let answer = requestty::prompt_one(Question::confirm("confirm").message(message).build())?;
+    answer
+        .as_bool()
+        .map_or_else(|| bail!("confirm answer is empty"), Ok)
+}
+
 /// prompt select option
 ///
 /// # Errors
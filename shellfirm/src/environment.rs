@@ -0,0 +1,596 @@
+//! Abstract the machine environment (environment variables, filesystem and
+//! subprocesses) so command analysis can run against the real machine in the
+//! CLI and against a mock in tests and downstream crates.
+
+use std::{
+    collections::HashMap,
+    env,
+    io::Read,
+    path::Path,
+    process::{Command, Stdio},
+    time::{Duration, Instant},
+};
+
+/// Access to the environment the checked command is going to run in.
+///
+/// `Sync` is required so checks can run on the environment in parallel.
+pub trait Environment: Sync {
+    /// Return the value of the given environment variable.
+    fn env_var(&self, key: &str) -> Option<String>;
+
+    /// Check if the given path exists (file or folder).
+    fn path_exists(&self, path: &str) -> bool;
+
+    /// Return the current working directory.
+    fn current_dir(&self) -> Option<String>;
+
+    /// Run the given command line and return its stdout, or `None` when the
+    /// command could not run or exited with an error.
+    fn run_command(&self, command: &str) -> Option<String>;
+
+    /// Canonicalize the given path — `~` / `$HOME` expansion, resolution
+    /// against the working directory and `.` / `..` collapsing — so
+    /// path-sensitive checks see the real target of e.g. `~/foo/../../`.
+    /// The default is the purely lexical [`normalize_path`], safe for mocks;
+    /// [`SystemEnvironment`] additionally resolves symlinks.
+    fn canonicalize(&self, path: &str) -> Option<String> {
+        Some(normalize_path(
+            path,
+            self.env_var("HOME").as_deref(),
+            self.current_dir().as_deref(),
+        ))
+    }
+}
+
+/// Lexically normalize a path: expand a leading `~` or `$HOME` with the
+/// given home directory, resolve a relative path against the given working
+/// directory, and collapse `.` and `..` components. Purely textual, no
+/// filesystem access — symlinks are left alone.
+///
+/// # Arguments
+///
+/// * `path` - the path to normalize.
+/// * `home` - the home directory `~` expands to.
+/// * `current_dir` - the directory relative paths resolve against.
+#[must_use]
+pub fn normalize_path(path: &str, home: Option<&str>, current_dir: Option<&str>) -> String {
+    let expanded = if let Some(stripped) = path
+        .strip_prefix("~/")
+        .or_else(|| path.strip_prefix("$HOME/"))
+    {
+        home.map_or_else(
+            || path.to_string(),
+            |home| format!("{}/{stripped}", home.trim_end_matches('/')),
+        )
+    } else if path == "~" || path == "$HOME" {
+        home.map_or_else(|| path.to_string(), ToString::to_string)
+    } else if !path.starts_with('/') {
+        current_dir.map_or_else(
+            || path.to_string(),
+            |dir| format!("{}/{path}", dir.trim_end_matches('/')),
+        )
+    } else {
+        path.to_string()
+    };
+
+    let mut parts: Vec<&str> = Vec::new();
+    for part in expanded.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop();
+            }
+            part => parts.push(part),
+        }
+    }
+    if expanded.starts_with('/') {
+        format!("/{}", parts.join("/"))
+    } else {
+        parts.join("/")
+    }
+}
+
+/// Default hard timeout for a single subprocess ran by the checks pipeline.
+pub const DEFAULT_SUBPROCESS_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Run the given command line with a hard kill-after-timeout. This is the
+/// single subprocess entry point of the pipeline: every context / filter
+/// probe goes through here so a hung binary can not block the prompt path.
+/// Concurrency is bounded by the rayon pool running the checks.
+///
+/// # Arguments
+///
+/// * `command` - command line to run.
+/// * `timeout` - hard deadline, the process is killed when it passes.
+#[must_use]
+pub fn run_command_with_timeout(command: &str, timeout: Duration) -> Option<String> {
+    let mut parts = command.split_whitespace();
+    let program = parts.next()?;
+    let args: Vec<&str> = parts.collect();
+    run_args_with_timeout(program, &args, timeout)
+}
+
+/// Run the given program with an explicit argument vector and the same hard
+/// kill-after-timeout. [`run_command_with_timeout`] splits its command line
+/// on whitespace and runs no shell, so an argument that itself contains
+/// whitespace (an HTTP header, a quoted value) must come through here — it
+/// would otherwise be split into several bogus arguments.
+///
+/// # Arguments
+///
+/// * `program` - program to run.
+/// * `args` - argument vector, passed through untouched.
+/// * `timeout` - hard deadline, the process is killed when it passes.
+#[must_use]
+pub fn run_args_with_timeout(program: &str, args: &[&str], timeout: Duration) -> Option<String> {
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .ok()?;
+
+    let deadline = Instant::now() + timeout;
+    loop {
+        match child.try_wait() {
+            Ok(Some(status)) => {
+                if !status.success() {
+                    return None;
+                }
+                break;
+            }
+            Ok(None) => {
+                if Instant::now() >= deadline {
+                    log::debug!(
+                        "command `{} {}` passed its {:?} budget, killing",
+                        program,
+                        args.join(" "),
+                        timeout
+                    );
+                    let _ = child.kill();
+                    let _ = child.wait();
+                    return None;
+                }
+                std::thread::sleep(Duration::from_millis(10));
+            }
+            Err(_) => return None,
+        }
+    }
+
+    let mut output = String::new();
+    child.stdout.take()?.read_to_string(&mut output).ok()?;
+    Some(output.trim().to_string())
+}
+
+/// [`Environment`] implementation backed by the real machine.
+pub struct SystemEnvironment {
+    /// Hard timeout applied to every subprocess.
+    timeout: Duration,
+}
+
+impl SystemEnvironment {
+    /// Create a system environment with the given subprocess latency budget.
+    #[must_use]
+    pub const fn with_timeout(timeout: Duration) -> Self {
+        Self { timeout }
+    }
+}
+
+impl Default for SystemEnvironment {
+    fn default() -> Self {
+        Self::with_timeout(DEFAULT_SUBPROCESS_TIMEOUT)
+    }
+}
+
+impl Environment for SystemEnvironment {
+    fn env_var(&self, key: &str) -> Option<String> {
+        env::var(key).ok()
+    }
+
+    fn path_exists(&self, path: &str) -> bool {
+        Path::new(path).exists()
+    }
+
+    fn current_dir(&self) -> Option<String> {
+        env::current_dir().ok().map(|p| p.display().to_string())
+    }
+
+    fn run_command(&self, command: &str) -> Option<String> {
+        run_command_with_timeout(command, self.timeout)
+    }
+
+    fn canonicalize(&self, path: &str) -> Option<String> {
+        let normalized = normalize_path(
+            path,
+            Environment::env_var(self, "HOME").as_deref(),
+            Environment::current_dir(self).as_deref(),
+        );
+        // resolve symlinks when the path exists, keep the lexical form
+        // otherwise
+        std::fs::canonicalize(&normalized)
+            .map_or(Some(normalized), |path| Some(path.display().to_string()))
+    }
+}
+
+/// Non-blocking counterpart of [`Environment`] for embedders driving
+/// shellfirm from an async runtime — a daemon or MCP server answering
+/// several agents at once, where one slow `kubectl` probe must not stall the
+/// event loop. The CLI stays on the sync trait.
+///
+/// Enabled with the `async-environment` feature.
+#[cfg(feature = "async-environment")]
+pub mod non_blocking {
+    use std::{env, path::Path, process::Stdio, time::Duration};
+
+    use super::{normalize_path, Environment, DEFAULT_SUBPROCESS_TIMEOUT};
+
+    /// Async variant of [`Environment`], awaited instead of blocking.
+    #[allow(async_fn_in_trait)] // embedders hold the concrete type, no boxing needed
+    pub trait AsyncEnvironment: Sync {
+        /// Return the value of the given environment variable.
+        async fn env_var(&self, key: &str) -> Option<String>;
+
+        /// Check if the given path exists (file or folder).
+        async fn path_exists(&self, path: &str) -> bool;
+
+        /// Return the current working directory.
+        async fn current_dir(&self) -> Option<String>;
+
+        /// Run the given command line and return its stdout, or `None` when the
+        /// command could not run or exited with an error.
+        async fn run_command(&self, command: &str) -> Option<String>;
+
+        /// Canonicalize the given path, see [`Environment::canonicalize`].
+        async fn canonicalize(&self, path: &str) -> Option<String> {
+            Some(normalize_path(
+                path,
+                self.env_var("HOME").await.as_deref(),
+                self.current_dir().await.as_deref(),
+            ))
+        }
+    }
+
+    /// Every sync [`Environment`] is also an [`AsyncEnvironment`], with every
+    /// call running inline on the event loop. This keeps [`MockEnvironment`]
+    /// usable in async tests; server code should prefer [`TokioEnvironment`] so
+    /// subprocesses and filesystem probes do not block the loop.
+    impl<E: Environment> AsyncEnvironment for E {
+        async fn env_var(&self, key: &str) -> Option<String> {
+            Environment::env_var(self, key)
+        }
+
+        async fn path_exists(&self, path: &str) -> bool {
+            Environment::path_exists(self, path)
+        }
+
+        async fn current_dir(&self) -> Option<String> {
+            Environment::current_dir(self)
+        }
+
+        async fn run_command(&self, command: &str) -> Option<String> {
+            Environment::run_command(self, command)
+        }
+
+        async fn canonicalize(&self, path: &str) -> Option<String> {
+            Environment::canonicalize(self, path)
+        }
+    }
+
+    /// [`AsyncEnvironment`] implementation backed by the real machine through
+    /// tokio: subprocesses run under `tokio::process` with the same hard timeout
+    /// as [`SystemEnvironment`], filesystem probes are moved off the event loop
+    /// with `spawn_blocking`.
+    #[cfg(feature = "async-environment")]
+    pub struct TokioEnvironment {
+        /// Hard timeout applied to every subprocess.
+        timeout: Duration,
+    }
+
+    impl TokioEnvironment {
+        /// Create a tokio environment with the given subprocess latency budget.
+        #[must_use]
+        pub const fn with_timeout(timeout: Duration) -> Self {
+            Self { timeout }
+        }
+    }
+
+    impl Default for TokioEnvironment {
+        fn default() -> Self {
+            Self::with_timeout(DEFAULT_SUBPROCESS_TIMEOUT)
+        }
+    }
+
+    impl AsyncEnvironment for TokioEnvironment {
+        async fn env_var(&self, key: &str) -> Option<String> {
+            env::var(key).ok()
+        }
+
+        async fn path_exists(&self, path: &str) -> bool {
+            let path = path.to_string();
+            tokio::task::spawn_blocking(move || Path::new(&path).exists())
+                .await
+                .unwrap_or(false)
+        }
+
+        async fn current_dir(&self) -> Option<String> {
+            env::current_dir().ok().map(|p| p.display().to_string())
+        }
+
+        async fn run_command(&self, command: &str) -> Option<String> {
+            let mut parts = command.split_whitespace();
+            let program = parts.next()?;
+            let child = tokio::process::Command::new(program)
+                .args(parts)
+                .stdin(Stdio::null())
+                .stdout(Stdio::piped())
+                .stderr(Stdio::null())
+                // dropping the future on timeout must not leak the process
+                .kill_on_drop(true)
+                .spawn()
+                .ok()?;
+
+            match tokio::time::timeout(self.timeout, child.wait_with_output()).await {
+                Ok(Ok(output)) if output.status.success() => {
+                    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+                }
+                Ok(_) => None,
+                Err(_) => {
+                    log::debug!(
+                        "command `{}` passed its {:?} budget, killing",
+                        command,
+                        self.timeout
+                    );
+                    None
+                }
+            }
+        }
+
+        async fn canonicalize(&self, path: &str) -> Option<String> {
+            let normalized = normalize_path(
+                path,
+                self.env_var("HOME").await.as_deref(),
+                self.current_dir().await.as_deref(),
+            );
+            tokio::task::spawn_blocking(move || {
+                std::fs::canonicalize(&normalized)
+                    .map_or(Some(normalized), |path| Some(path.display().to_string()))
+            })
+            .await
+            .ok()?
+        }
+    }
+}
+
+/// [`Environment`] implementation for tests, returning only the values it was
+/// configured with. Use [`MockEnvironment::builder`] to construct it.
+#[derive(Debug, Default, Clone)]
+pub struct MockEnvironment {
+    /// Environment variables visible to the checks.
+    pub env_vars: HashMap<String, String>,
+    /// Paths and whether they exist.
+    pub files: HashMap<String, bool>,
+    /// Working directory of the checked command.
+    pub current_dir: Option<String>,
+    /// Canned stdout per command line.
+    pub command_outputs: HashMap<String, String>,
+}
+
+impl MockEnvironment {
+    /// Start building a mock environment.
+    #[must_use]
+    pub fn builder() -> MockEnvironmentBuilder {
+        MockEnvironmentBuilder::default()
+    }
+}
+
+impl Environment for MockEnvironment {
+    fn env_var(&self, key: &str) -> Option<String> {
+        self.env_vars.get(key).cloned()
+    }
+
+    fn path_exists(&self, path: &str) -> bool {
+        *self.files.get(path).unwrap_or(&false)
+    }
+
+    fn current_dir(&self) -> Option<String> {
+        self.current_dir.clone()
+    }
+
+    fn run_command(&self, command: &str) -> Option<String> {
+        self.command_outputs.get(command).cloned()
+    }
+}
+
+/// Fluent builder for [`MockEnvironment`], avoiding hand-constructed HashMaps
+/// in every test file.
+#[derive(Debug, Default)]
+pub struct MockEnvironmentBuilder {
+    environment: MockEnvironment,
+}
+
+impl MockEnvironmentBuilder {
+    /// Set an environment variable.
+    #[must_use]
+    pub fn env_var(mut self, key: &str, value: &str) -> Self {
+        self.environment
+            .env_vars
+            .insert(key.to_string(), value.to_string());
+        self
+    }
+
+    /// Mark the given path as exists / not exists.
+    #[must_use]
+    pub fn file(mut self, path: &str, exists: bool) -> Self {
+        self.environment.files.insert(path.to_string(), exists);
+        self
+    }
+
+    /// Set the working directory of the checked command.
+    #[must_use]
+    pub fn current_dir(mut self, path: &str) -> Self {
+        self.environment.current_dir = Some(path.to_string());
+        self
+    }
+
+    /// Set the stdout returned for the given command line.
+    #[must_use]
+    pub fn command_output(mut self, command: &str, output: &str) -> Self {
+        self.environment
+            .command_outputs
+            .insert(command.to_string(), output.to_string());
+        self
+    }
+
+    /// Simulate a shell running inside an SSH session.
+    #[must_use]
+    pub fn ssh(self) -> Self {
+        self.env_var("SSH_CONNECTION", "10.0.0.1 22 10.0.0.2 22")
+            .env_var("SSH_TTY", "/dev/pts/0")
+    }
+
+    /// Simulate an active kubectl context with the given name.
+    #[must_use]
+    pub fn k8s(self, context: &str) -> Self {
+        self.command_output("kubectl config current-context", context)
+    }
+
+    /// Simulate a git repository checked out on the given branch.
+    #[must_use]
+    pub fn branch(self, branch: &str) -> Self {
+        self.command_output("git symbolic-ref --short HEAD", branch)
+    }
+
+    /// Finish and return the mock environment.
+    #[must_use]
+    pub fn build(self) -> MockEnvironment {
+        self.environment
+    }
+}
+
+/// Shared mock environment presets for shellfirm tests and for downstream
+/// crates writing integration tests against shellfirm.
+pub mod fixtures {
+    use super::MockEnvironment;
+
+    /// A local interactive shell on a developer machine.
+    #[must_use]
+    pub fn local_dev() -> MockEnvironment {
+        MockEnvironment::builder()
+            .current_dir("/home/dev/work")
+            .branch("feature/new-thing")
+            .build()
+    }
+
+    /// An SSH session on a machine with a production kubectl context.
+    #[must_use]
+    pub fn production_ssh() -> MockEnvironment {
+        MockEnvironment::builder()
+            .ssh()
+            .k8s("prod")
+            .branch("main")
+            .build()
+    }
+}
+
+#[cfg(test)]
+mod test_environment {
+    use insta::assert_debug_snapshot;
+
+    use super::*;
+
+    #[test]
+    fn can_build_mock_environment() {
+        let environment = MockEnvironment::builder()
+            .ssh()
+            .k8s("prod")
+            .branch("main")
+            .file("/x", true)
+            .build();
+
+        assert_debug_snapshot!(environment.env_var("SSH_TTY"));
+        assert_debug_snapshot!(environment.run_command("kubectl config current-context"));
+        assert_debug_snapshot!(environment.run_command("git symbolic-ref --short HEAD"));
+        assert_debug_snapshot!(environment.path_exists("/x"));
+        assert_debug_snapshot!(environment.path_exists("/y"));
+    }
+
+    #[test]
+    fn run_command_with_timeout_kills_slow_commands() {
+        assert_debug_snapshot!(run_command_with_timeout(
+            "sleep 5",
+            Duration::from_millis(50)
+        ));
+        assert_debug_snapshot!(run_command_with_timeout(
+            "echo done",
+            Duration::from_secs(5)
+        ));
+    }
+
+    #[test]
+    fn run_command_splits_on_whitespace_without_a_shell() {
+        // quotes, variables and pipes are literal argv bytes, not shell
+        // syntax: any probe command relying on them is broken
+        assert_debug_snapshot!(run_command_with_timeout(
+            "echo '$HOME' | cat",
+            Duration::from_secs(5)
+        ));
+        // an argument that itself contains whitespace must go through the
+        // explicit argv entry point to survive in one piece
+        assert_debug_snapshot!(run_args_with_timeout(
+            "echo",
+            &["Content-Type: application/json"],
+            Duration::from_secs(5)
+        ));
+    }
+
+    #[test]
+    fn can_canonicalize_paths() {
+        let environment = MockEnvironment::builder()
+            .env_var("HOME", "/home/dev")
+            .current_dir("/home/dev/work")
+            .build();
+        assert_debug_snapshot!(environment.canonicalize("~/foo/../../"));
+        assert_debug_snapshot!(environment.canonicalize("./build//../cache"));
+        assert_debug_snapshot!(environment.canonicalize("$HOME/.ssh"));
+        assert_debug_snapshot!(environment.canonicalize("/var/log/../lib"));
+        // nothing to resolve against: the path is only collapsed
+        assert_debug_snapshot!(MockEnvironment::builder().build().canonicalize("a/b/../c"));
+    }
+
+    #[test]
+    fn mock_environment_returns_only_configured_values() {
+        let environment = MockEnvironment::builder().build();
+        assert_debug_snapshot!(environment.env_var("SSH_TTY"));
+        assert_debug_snapshot!(environment.current_dir());
+        assert_debug_snapshot!(environment.run_command("kubectl config current-context"));
+    }
+}
+
+#[cfg(all(test, feature = "async-environment"))]
+mod test_async_environment {
+    use insta::assert_debug_snapshot;
+
+    use super::{non_blocking::*, *};
+
+    #[tokio::test]
+    async fn tokio_environment_runs_commands_with_timeout() {
+        let environment = TokioEnvironment::with_timeout(Duration::from_millis(50));
+        assert_debug_snapshot!(environment.run_command("sleep 5").await);
+        let environment = TokioEnvironment::default();
+        assert_debug_snapshot!(environment.run_command("echo done").await);
+        assert_debug_snapshot!(environment.run_command("false").await);
+    }
+
+    #[tokio::test]
+    async fn sync_environments_bridge_to_async() {
+        let environment = MockEnvironment::builder()
+            .env_var("HOME", "/home/dev")
+            .k8s("prod")
+            .build();
+        assert_debug_snapshot!(
+            AsyncEnvironment::run_command(&environment, "kubectl config current-context").await
+        );
+        assert_debug_snapshot!(AsyncEnvironment::canonicalize(&environment, "~/.ssh").await);
+    }
+}
@@ -0,0 +1,81 @@
+//! C ABI bindings so non-Rust embedders (terminal emulators, tmux plugins,
+//! kitty kittens) can link the validation engine directly. The matching C
+//! header is committed under `include/shellfirm.h`.
+
+use std::{
+    ffi::{CStr, CString},
+    os::raw::c_char,
+};
+
+use crate::{environment::SystemEnvironment, Config, Guardian};
+
+/// Validate the given command and return the assessment serialized to YAML
+/// (command, matched checks and decision), or null when the command could not
+/// be validated (bad input, configuration could not be loaded).
+///
+/// The returned string must be released with [`shellfirm_free_result`].
+///
+/// # Safety
+///
+/// `command` must be a valid null-terminated C string.
+#[no_mangle]
+pub unsafe extern "C" fn shellfirm_validate_command(command: *const c_char) -> *mut c_char {
+    if command.is_null() {
+        return std::ptr::null_mut();
+    }
+    let Ok(command) = CStr::from_ptr(command).to_str() else {
+        return std::ptr::null_mut();
+    };
+
+    let Some(assessment) = validate(command) else {
+        return std::ptr::null_mut();
+    };
+
+    match CString::new(assessment) {
+        Ok(result) => result.into_raw(),
+        Err(_) => std::ptr::null_mut(),
+    }
+}
+
+/// Release a string returned from [`shellfirm_validate_command`]. Passing
+/// null is a no-op.
+///
+/// # Safety
+///
+/// `result` must be a pointer returned from [`shellfirm_validate_command`]
+/// that was not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn shellfirm_free_result(result: *mut c_char) {
+    if !result.is_null() {
+        drop(CString::from_raw(result));
+    }
+}
+
+/// Run the assessment pipeline and serialize the result.
+fn validate(command: &str) -> Option<String> {
+    let config = Config::new(None).ok()?;
+    let guardian = Guardian::new(&config).ok()?;
+    let assessment = guardian.assess(command, &SystemEnvironment::default());
+    serde_yaml::to_string(&assessment).ok()
+}
+
+#[cfg(test)]
+mod test_ffi {
+    use insta::assert_debug_snapshot;
+
+    use super::*;
+
+    #[test]
+    fn can_validate_command_roundtrip() {
+        let command = CString::new("some safe command").unwrap();
+        let result = unsafe { shellfirm_validate_command(command.as_ptr()) };
+        assert_debug_snapshot!(result.is_null());
+        unsafe { shellfirm_free_result(result) };
+    }
+
+    #[test]
+    fn validate_command_with_null_returns_null() {
+        let result = unsafe { shellfirm_validate_command(std::ptr::null()) };
+        assert_debug_snapshot!(result.is_null());
+    }
+}
@@ -0,0 +1,192 @@
+//! High level API for embedding shellfirm in other Rust tools (terminal
+//! emulators, internal CLIs) without spawning the binary.
+
+use anyhow::Result;
+use serde_derive::{Deserialize, Serialize};
+
+use crate::{
+    checks::{self, Check, MatchSite, MatchedSpan},
+    config::{Config, Settings},
+    environment::Environment,
+};
+
+/// Non-interactive decision for a checked command.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub enum Decision {
+    /// No check matched, the command is safe to run.
+    #[serde(alias = "allow")]
+    Allow,
+    /// At least one check matched, the user should pass a challenge.
+    #[serde(alias = "challenge")]
+    Challenge,
+    /// At least one matched check is in the deny list.
+    #[serde(alias = "deny")]
+    Deny,
+}
+
+/// Result of assessing a single command.
+#[derive(Debug, Serialize, Clone)]
+pub struct Assessment {
+    /// The command that was assessed.
+    pub command: String,
+    /// All checks that matched the command, de-duplicated by check id.
+    pub matches: Vec<Check>,
+    /// Per-segment match detail.
+    pub match_sites: Vec<MatchSite>,
+    /// Byte ranges of the matched portions of the command, for integrations
+    /// rendering squiggles.
+    pub matched_spans: Vec<MatchedSpan>,
+    /// The decision under the loaded settings.
+    pub decision: Decision,
+}
+
+/// Embeddable validation engine, holding the loaded settings and the active
+/// checks.
+#[derive(Debug)]
+pub struct Guardian {
+    settings: Settings,
+    checks: Vec<Check>,
+}
+
+impl Guardian {
+    /// Create a guardian from the given app configuration.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` when the settings file or the checks could not be
+    /// loaded.
+    pub fn new(config: &Config) -> Result<Self> {
+        Self::from_settings(config.get_settings_from_file()?)
+    }
+
+    /// Create a guardian from already loaded settings.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` when the active checks could not be loaded.
+    pub fn from_settings(settings: Settings) -> Result<Self> {
+        let checks = settings.get_active_checks()?;
+        Ok(Self { settings, checks })
+    }
+
+    /// Create a guardian from already loaded settings and an explicit check
+    /// list, for callers layering extra checks (e.g. project policies).
+    #[must_use]
+    pub const fn with_checks(settings: Settings, checks: Vec<Check>) -> Self {
+        Self { settings, checks }
+    }
+
+    /// Assess the given command against the active checks, returning the
+    /// matched checks and the decision. Never prompts the user.
+    #[must_use]
+    pub fn assess(&self, command: &str, environment: &dyn Environment) -> Assessment {
+        let mut analysis = checks::analyze_command(&self.checks, command, environment);
+        self.settings
+            .apply_severity_filter(&mut analysis, environment);
+        self.settings
+            .apply_allow_rules(&mut analysis, command, environment);
+
+        let deny_ids = self.settings.active_deny_patterns_ids(environment);
+        let decision = if analysis
+            .matches
+            .iter()
+            .any(|c| deny_ids.contains(&c.id) || self.settings.is_denied_by_tag(c))
+        {
+            Decision::Deny
+        } else if analysis.matches.is_empty() {
+            Decision::Allow
+        } else {
+            Decision::Challenge
+        };
+
+        Assessment {
+            command: command.to_string(),
+            matches: analysis.matches,
+            match_sites: analysis.match_sites,
+            matched_spans: analysis.matched_spans,
+            decision,
+        }
+    }
+
+    /// Return only the decision for the given command.
+    #[must_use]
+    pub fn decide(&self, command: &str, environment: &dyn Environment) -> Decision {
+        self.assess(command, environment).decision
+    }
+}
+
+#[cfg(test)]
+mod test_guardian {
+    use insta::assert_debug_snapshot;
+
+    use super::*;
+    use crate::{
+        config::{
+            AuditSettings, BreakGlassSettings, ChallengeTuning, UrlReputation, DEFAULT_CHALLENGE,
+        },
+        environment::MockEnvironment,
+    };
+
+    fn get_guardian(deny_patterns_ids: Vec<String>, deny_tags: Vec<String>) -> Guardian {
+        Guardian::from_settings(Settings {
+            challenge: DEFAULT_CHALLENGE,
+            includes: vec!["base".to_string(), "fs".to_string(), "git".to_string()],
+            ignores_patterns_ids: vec![],
+            deny_patterns_ids,
+            deny_tags,
+            tag_escalation: std::collections::BTreeMap::new(),
+            max_subprocess_latency_ms: 500,
+            network: crate::network::NetworkMode::default(),
+            checks_bundle_hash: None,
+            deny_rules: vec![],
+            allow: vec![],
+            save_last_command: true,
+            copy_blocked_command_to_clipboard: false,
+            challenge_wordlist: vec![],
+            challenge_tuning: ChallengeTuning::default(),
+            totp_secret: None,
+            aliases: std::collections::BTreeMap::new(),
+            summarize_matches_above: 3,
+            escalate_on_unknown_impact: None,
+            escalate_if: crate::config::EscalateIf::default(),
+            min_severity: None,
+            context_severity_floor: std::collections::BTreeMap::new(),
+            audit: AuditSettings::default(),
+            oidc: None,
+            break_glass: BreakGlassSettings::default(),
+            url_reputation: UrlReputation::default(),
+            kubernetes_namespaces: crate::config::KubernetesNamespaces::default(),
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn can_assess_risky_command() {
+        let guardian = get_guardian(vec![], vec![]);
+        let assessment = guardian.assess("rm -rf /", &MockEnvironment::default());
+        assert_debug_snapshot!(assessment.decision);
+        assert_debug_snapshot!(assessment
+            .matches
+            .iter()
+            .map(|c| c.id.to_string())
+            .collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn can_assess_safe_command() {
+        let guardian = get_guardian(vec![], vec![]);
+        assert_debug_snapshot!(guardian.decide("ls -la", &MockEnvironment::default()));
+    }
+
+    #[test]
+    fn can_assess_denied_command() {
+        let guardian = get_guardian(vec!["fs:recursively_delete".to_string()], vec![]);
+        assert_debug_snapshot!(guardian.decide("rm -rf /", &MockEnvironment::default()));
+    }
+
+    #[test]
+    fn can_assess_tag_denied_command() {
+        let guardian = get_guardian(vec![], vec!["irreversible".to_string()]);
+        assert_debug_snapshot!(guardian.decide("rm -rf /", &MockEnvironment::default()));
+    }
+}
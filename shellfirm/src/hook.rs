@@ -0,0 +1,261 @@
+//! Install the shellfirm hook into shell rc files.
+//!
+//! Installation is not a blind append: the rc file is analyzed for known
+//! conflicting lines (other accept-line rebinds, bash-preexec loads,
+//! powerlevel10k instant-prompt blocks) and the hook snippet is placed after
+//! the last of them, so shellfirm keeps working with common zsh plugin
+//! setups.
+
+use std::str::FromStr;
+
+use anyhow::{bail, Result};
+use serde_derive::{Deserialize, Serialize};
+
+/// Marker line identifying the shellfirm snippet inside a rc file.
+pub const HOOK_MARKER: &str = "# shellfirm hook";
+
+/// Bash plugin (requires bash-preexec).
+pub const PLUGIN_SH: &str = include_str!("../../shell-plugins/shellfirm.plugin.sh");
+/// Plain zsh plugin (binds accept-line).
+pub const PLUGIN_ZSH: &str = include_str!("../../shell-plugins/shellfirm.plugin.zsh");
+/// oh-my-zsh style plugin (uses add-zsh-hook).
+pub const PLUGIN_OH_MY_ZSH: &str =
+    include_str!("../../shell-plugins/shellfirm.plugin.oh-my-zsh.zsh");
+/// Fish plugin.
+pub const PLUGIN_FISH: &str = include_str!("../../shell-plugins/shellfirm.plugin.fish");
+/// zsh completions, shipped with the plugin directory packaging.
+pub const COMPLETIONS_ZSH: &str = include_str!("../../shell-plugins/_shellfirm");
+
+/// Shells with a bundled plugin.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+impl FromStr for Shell {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "bash" => Ok(Self::Bash),
+            "zsh" => Ok(Self::Zsh),
+            "fish" => Ok(Self::Fish),
+            _ => bail!("unsupported shell: {}", s),
+        }
+    }
+}
+
+impl Shell {
+    /// File name of the bundled plugin for this shell. The zsh variant is
+    /// picked by [`select_plugin`] based on the rc file content.
+    #[must_use]
+    pub const fn plugin_file_name(&self) -> &'static str {
+        match self {
+            Self::Bash => "shellfirm.plugin.sh",
+            Self::Zsh => "shellfirm.plugin.zsh",
+            Self::Fish => "shellfirm.plugin.fish",
+        }
+    }
+}
+
+/// Result of analyzing a rc file before installing the hook.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct RcAnalysis {
+    /// Line index (0 based) after which the hook snippet should be inserted.
+    /// `None` means append at the end of the file.
+    pub insert_after_line: Option<usize>,
+    /// Human readable notes about conflicting lines that were found.
+    pub warnings: Vec<String>,
+}
+
+/// Known line patterns that conflict with the shellfirm hook when the hook is
+/// loaded before them.
+const CONFLICT_PATTERNS: &[(&str, &str)] = &[
+    ("zle -N accept-line", "another accept-line widget rebind"),
+    ("bash-preexec", "bash-preexec load"),
+    ("p10k-instant-prompt", "powerlevel10k instant prompt block"),
+    ("instant_prompt", "instant prompt block"),
+];
+
+/// Analyze the given rc file content and decide where the hook snippet
+/// should be placed.
+///
+/// # Arguments
+///
+/// * `rc_content` - content of the shell rc file.
+#[must_use]
+pub fn analyze_rc_content(rc_content: &str) -> RcAnalysis {
+    let mut insert_after_line = None;
+    let mut warnings = Vec::new();
+
+    for (line_index, line) in rc_content.lines().enumerate() {
+        let trimmed = line.trim();
+        if trimmed.starts_with('#') || trimmed.contains("shellfirm") {
+            continue;
+        }
+        for (pattern, description) in CONFLICT_PATTERNS {
+            if trimmed.contains(pattern) {
+                warnings.push(format!(
+                    "line {}: {} ({}). the shellfirm hook is placed after it",
+                    line_index + 1,
+                    description,
+                    trimmed
+                ));
+                insert_after_line = Some(line_index);
+            }
+        }
+    }
+
+    RcAnalysis {
+        insert_after_line,
+        warnings,
+    }
+}
+
+/// Pick the plugin content that fits the given shell and rc file.
+///
+/// # Arguments
+///
+/// * `shell` - shell to install for.
+/// * `rc_content` - content of the shell rc file.
+#[must_use]
+pub fn select_plugin(shell: Shell, rc_content: &str) -> &'static str {
+    match shell {
+        Shell::Bash => PLUGIN_SH,
+        Shell::Zsh => {
+            // with oh-my-zsh (or any add-zsh-hook user) prefer the preexec
+            // variant over rebinding accept-line
+            if rc_content.contains("oh-my-zsh") || rc_content.contains("add-zsh-hook") {
+                PLUGIN_OH_MY_ZSH
+            } else {
+                PLUGIN_ZSH
+            }
+        }
+        Shell::Fish => PLUGIN_FISH,
+    }
+}
+
+/// Return the rc file content with the hook snippet installed in the right
+/// position. When the hook is already installed the content is returned
+/// unchanged.
+///
+/// # Arguments
+///
+/// * `rc_content` - content of the shell rc file.
+/// * `plugin_path` - path of the plugin file the snippet should source.
+#[must_use]
+pub fn install_hook(rc_content: &str, plugin_path: &str) -> (String, RcAnalysis) {
+    let analysis = analyze_rc_content(rc_content);
+
+    if rc_content.contains(HOOK_MARKER) {
+        return (rc_content.to_string(), analysis);
+    }
+
+    let snippet = format!("{HOOK_MARKER}\nsource \"{plugin_path}\"");
+    let mut lines: Vec<String> = rc_content
+        .lines()
+        .map(std::string::ToString::to_string)
+        .collect();
+    match analysis.insert_after_line {
+        Some(line_index) => lines.insert(line_index + 1, snippet),
+        None => lines.push(snippet),
+    }
+
+    (format!("{}\n", lines.join("\n")), analysis)
+}
+
+/// Write an oh-my-zsh/zinit/antidote compatible plugin directory: the plugin
+/// file named after the directory plus the completions file, so plugin
+/// managers can load shellfirm without touching the rc file.
+///
+/// # Arguments
+///
+/// * `plugin_dir` - directory to write the plugin into (created if missing).
+///
+/// # Errors
+///
+/// Will return `Err` when the plugin files could not be written
+pub fn write_zsh_plugin_dir(plugin_dir: &std::path::Path) -> Result<Vec<String>> {
+    std::fs::create_dir_all(plugin_dir)?;
+
+    let plugin_name = plugin_dir
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("shellfirm");
+
+    // plugin managers source <dir>/<dir-name>.plugin.zsh. use the
+    // add-zsh-hook variant which plays nice with other plugins
+    let plugin_file = plugin_dir.join(format!("{plugin_name}.plugin.zsh"));
+    std::fs::write(&plugin_file, PLUGIN_OH_MY_ZSH)?;
+
+    let completions_file = plugin_dir.join("_shellfirm");
+    std::fs::write(&completions_file, COMPLETIONS_ZSH)?;
+
+    Ok(vec![
+        plugin_file.display().to_string(),
+        completions_file.display().to_string(),
+    ])
+}
+
+#[cfg(test)]
+mod test_hook {
+    use insta::assert_debug_snapshot;
+
+    use super::*;
+
+    #[test]
+    fn can_analyze_clean_rc_file() {
+        assert_debug_snapshot!(analyze_rc_content(
+            "export PATH=$PATH:/usr/local/bin\nalias ll='ls -la'\n"
+        ));
+    }
+
+    #[test]
+    fn can_analyze_rc_file_with_conflicts() {
+        let rc_content = r#"source ~/.bash-preexec.sh
+zle -N accept-line my-custom-widget
+alias ll='ls -la'
+"#;
+        assert_debug_snapshot!(analyze_rc_content(rc_content));
+    }
+
+    #[test]
+    fn can_analyze_rc_file_with_instant_prompt() {
+        let rc_content = r#"if [[ -r "${XDG_CACHE_HOME:-$HOME/.cache}/p10k-instant-prompt-${(%):-%n}.zsh" ]]; then
+  source "${XDG_CACHE_HOME:-$HOME/.cache}/p10k-instant-prompt-${(%):-%n}.zsh"
+fi
+"#;
+        assert_debug_snapshot!(analyze_rc_content(rc_content));
+    }
+
+    #[test]
+    fn can_install_hook_after_conflicts() {
+        let rc_content = "zle -N accept-line my-custom-widget\nalias ll='ls -la'\n";
+        assert_debug_snapshot!(install_hook(rc_content, "~/.config/shellfirm/plugin.zsh"));
+    }
+
+    #[test]
+    fn install_hook_is_idempotent() {
+        let (installed, _) = install_hook("alias ll='ls -la'\n", "~/plugin.zsh");
+        let (installed_again, _) = install_hook(&installed, "~/plugin.zsh");
+        assert_debug_snapshot!(installed == installed_again);
+    }
+
+    #[test]
+    fn zsh_plugins_guard_bracketed_paste() {
+        // both zsh variants must check a multi-line paste before it reaches
+        // the buffer
+        assert_debug_snapshot!(PLUGIN_ZSH.contains("zle -N bracketed-paste"));
+        assert_debug_snapshot!(PLUGIN_OH_MY_ZSH.contains("zle -N bracketed-paste"));
+    }
+
+    #[test]
+    fn can_select_zsh_plugin_variant() {
+        assert_debug_snapshot!(
+            select_plugin(Shell::Zsh, "source $ZSH/oh-my-zsh.sh") == PLUGIN_OH_MY_ZSH
+        );
+        assert_debug_snapshot!(select_plugin(Shell::Zsh, "") == PLUGIN_ZSH);
+    }
+}
@@ -1,7 +1,30 @@
+// the settings JSON Schema is one large `json!` literal
+#![recursion_limit = "256"]
+
+pub mod agent;
+pub mod audit;
+pub mod capture;
 pub mod checks;
+pub mod command;
 mod config;
+pub mod context;
 mod data;
+pub mod diagnostics;
 pub mod dialog;
+pub mod environment;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+mod guardian;
+pub mod hook;
+pub mod network;
+pub mod oidc;
+pub mod policy;
 mod prompt;
-pub use config::{Challenge, Config, Settings};
+pub mod schema;
+pub mod totp;
+pub use config::{
+    detect_include_groups, AllowRule, Challenge, Config, EscalateIf, LastCommand, Settings,
+};
 pub use data::CmdExit;
+pub use guardian::{Assessment, Decision, Guardian};
+pub use prompt::CHALLENGE_SEED_ENV_VAR;
@@ -0,0 +1,54 @@
+//! Central network gate. Every feature capable of network access must pass
+//! through [`ensure_allowed`] before opening a connection, so the
+//! `network: never` guarantee can be reviewed in a single place.
+
+use anyhow::{bail, Result};
+use serde_derive::{Deserialize, Serialize};
+
+/// Whether shellfirm is allowed to open network connections.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum NetworkMode {
+    /// Network features behave normally.
+    #[default]
+    Allow,
+    /// Air-gapped mode: every network feature is disabled.
+    Never,
+}
+
+/// Verify that the given network feature is allowed to run.
+///
+/// # Arguments
+///
+/// * `mode` - network mode from the user settings.
+/// * `feature` - short name of the feature asking for network access, used in
+///   the error message.
+///
+/// # Errors
+///
+/// Will return `Err` when the settings are set to `network: never`.
+pub fn ensure_allowed(mode: NetworkMode, feature: &str) -> Result<()> {
+    match mode {
+        NetworkMode::Allow => Ok(()),
+        NetworkMode::Never => {
+            bail!("network access is disabled (network: never), refusing: {feature}")
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_network {
+    use insta::assert_debug_snapshot;
+
+    use super::*;
+
+    #[test]
+    fn can_allow_network_features() {
+        assert_debug_snapshot!(ensure_allowed(NetworkMode::Allow, "self-update"));
+    }
+
+    #[test]
+    fn never_mode_blocks_network_features() {
+        assert_debug_snapshot!(ensure_allowed(NetworkMode::Never, "self-update"));
+    }
+}
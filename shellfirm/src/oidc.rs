@@ -0,0 +1,327 @@
+//! Optional OIDC device code flow login, producing a cached identity whose
+//! claims (groups, roles) can be referenced in policies — role-based
+//! guardrails on shared infrastructure without per-machine user accounts.
+
+use std::{
+    path::PathBuf,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{bail, Context, Result};
+use serde_derive::{Deserialize, Serialize};
+
+use crate::{
+    environment::{run_command_with_timeout, Environment},
+    network::{self, NetworkMode},
+};
+
+/// File the cached identity is stored in, inside the config folder.
+pub const IDENTITY_FILE_NAME: &str = "identity.json";
+
+/// Environment variable overriding where the cached identity is read from.
+pub const IDENTITY_FILE_ENV_VAR: &str = "SHELLFIRM_IDENTITY_FILE";
+
+/// Hard timeout for one request to the identity provider.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Polling interval of the device flow when the provider does not name one.
+const DEFAULT_POLL_INTERVAL_SECONDS: u64 = 5;
+
+/// Identity provider settings (`oidc` in the settings file).
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct OidcSettings {
+    /// Device authorization endpoint, e.g.
+    /// `https://idp.example.com/oauth/device/code`.
+    pub device_endpoint: String,
+    /// Token endpoint, e.g. `https://idp.example.com/oauth/token`.
+    pub token_endpoint: String,
+    /// OAuth client id of this tool.
+    pub client_id: String,
+}
+
+/// Claims of a logged in user, cached until the token expires.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct CachedIdentity {
+    /// Subject claim (who logged in).
+    pub subject: String,
+    /// Group claims.
+    pub groups: Vec<String>,
+    /// Role claims.
+    pub roles: Vec<String>,
+    /// Expiry claim (seconds since epoch); the cache is ignored after it.
+    pub expires_at: u64,
+}
+
+/// Run the device code flow: print the verification instructions, poll the
+/// token endpoint until the user approves, and return the token claims.
+///
+/// # Errors
+///
+/// Will return `Err` when the network is disabled, the provider could not be
+/// reached, or the flow expired before approval.
+pub fn login(settings: &OidcSettings, network: NetworkMode) -> Result<CachedIdentity> {
+    network::ensure_allowed(network, "OIDC login")?;
+
+    let device = post_form(
+        &settings.device_endpoint,
+        &format!("client_id={}&scope=openid%20profile", settings.client_id),
+    )
+    .context("could not start the device flow")?;
+    let device_code = string_claim(&device, "device_code")
+        .context("device flow response is missing device_code")?;
+    let user_code =
+        string_claim(&device, "user_code").context("device flow response is missing user_code")?;
+    let verification_uri = string_claim(&device, "verification_uri")
+        .or_else(|| string_claim(&device, "verification_url"))
+        .context("device flow response is missing verification_uri")?;
+    let interval = device
+        .get("interval")
+        .and_then(serde_json::Value::as_u64)
+        .unwrap_or(DEFAULT_POLL_INTERVAL_SECONDS);
+    let expires_in = device
+        .get("expires_in")
+        .and_then(serde_json::Value::as_u64)
+        .unwrap_or(300);
+
+    eprintln!("To log in, visit {verification_uri} and enter the code {user_code}");
+
+    let deadline = SystemTime::now() + Duration::from_secs(expires_in);
+    while SystemTime::now() < deadline {
+        std::thread::sleep(Duration::from_secs(interval));
+        // the provider answers 4xx while the approval is pending, which
+        // surfaces here as a failed request — keep polling
+        if let Some(token) = post_form(
+            &settings.token_endpoint,
+            &format!(
+                "client_id={}&device_code={device_code}&grant_type=urn:ietf:params:oauth:grant-type:device_code",
+                settings.client_id
+            ),
+        ) {
+            let jwt = string_claim(&token, "id_token")
+                .or_else(|| string_claim(&token, "access_token"))
+                .context("token response carries no token")?;
+            return identity_from_claims(&decode_claims(&jwt)?);
+        }
+    }
+    bail!("device flow expired before the login was approved");
+}
+
+/// Load the cached identity the current shell should use: from the file named
+/// by `SHELLFIRM_IDENTITY_FILE` when set, the config folder otherwise.
+/// Missing and expired identities read as `None`.
+#[must_use]
+pub fn cached_identity(environment: &dyn Environment) -> Option<CachedIdentity> {
+    let path = environment
+        .env_var(IDENTITY_FILE_ENV_VAR)
+        .map(PathBuf::from)
+        .or_else(|| {
+            Some(
+                dirs::home_dir()?
+                    .join(".shellfirm")
+                    .join(IDENTITY_FILE_NAME),
+            )
+        })?;
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    load(&path, now).ok().flatten()
+}
+
+/// Load a cached identity from the given file. An identity past its expiry
+/// reads as `None`.
+///
+/// # Errors
+///
+/// Will return `Err` when the file exists but could not be parsed.
+pub fn load(path: &std::path::Path, now: u64) -> Result<Option<CachedIdentity>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let identity: CachedIdentity = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+    if identity.expires_at <= now {
+        return Ok(None);
+    }
+    Ok(Some(identity))
+}
+
+/// Save a cached identity to the given file.
+///
+/// # Errors
+///
+/// Will return `Err` when the file could not be written.
+pub fn save(path: &std::path::Path, identity: &CachedIdentity) -> Result<()> {
+    std::fs::write(path, serde_json::to_string(identity)?)?;
+    Ok(())
+}
+
+/// Decode the claims of a JWT. The signature is not verified: the token
+/// came over TLS from the configured provider and only gates local prompts,
+/// server-side enforcement must verify it again.
+///
+/// # Errors
+///
+/// Will return `Err` when the token is not a JWT or its payload is not JSON.
+pub fn decode_claims(jwt: &str) -> Result<serde_json::Value> {
+    let payload = jwt
+        .split('.')
+        .nth(1)
+        .context("token is not a JWT (expected three dot-separated segments)")?;
+    Ok(serde_json::from_slice(&base64url_decode(payload)?)?)
+}
+
+/// Build a cached identity from decoded token claims.
+///
+/// # Errors
+///
+/// Will return `Err` when the claims carry no subject.
+pub fn identity_from_claims(claims: &serde_json::Value) -> Result<CachedIdentity> {
+    Ok(CachedIdentity {
+        subject: string_claim(claims, "sub").context("token claims carry no subject")?,
+        groups: string_list_claim(claims, "groups"),
+        roles: string_list_claim(claims, "roles"),
+        expires_at: claims
+            .get("exp")
+            .and_then(serde_json::Value::as_u64)
+            .unwrap_or(0),
+    })
+}
+
+fn string_claim(claims: &serde_json::Value, key: &str) -> Option<String> {
+    claims
+        .get(key)
+        .and_then(serde_json::Value::as_str)
+        .map(ToString::to_string)
+}
+
+fn string_list_claim(claims: &serde_json::Value, key: &str) -> Vec<String> {
+    claims
+        .get(key)
+        .and_then(serde_json::Value::as_array)
+        .map(|values| {
+            values
+                .iter()
+                .filter_map(serde_json::Value::as_str)
+                .map(ToString::to_string)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// POST a form to the given URL, returning the parsed JSON response on any
+/// 2xx answer. The form is `key=value&…` without whitespace, so it survives
+/// the whitespace splitting of the no-shell command runner unquoted — quotes
+/// would reach the IdP as literal body bytes.
+fn post_form(url: &str, form: &str) -> Option<serde_json::Value> {
+    run_command_with_timeout(
+        &format!("curl -fsS -X POST -d {form} {url}"),
+        REQUEST_TIMEOUT,
+    )
+    .and_then(|body| serde_json::from_str(&body).ok())
+}
+
+/// Decode base64url (RFC 4648, with or without padding).
+fn base64url_decode(input: &str) -> Result<Vec<u8>> {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+    let mut bits: u32 = 0;
+    let mut bit_count = 0;
+    let mut out = Vec::with_capacity(input.len() * 3 / 4);
+    for byte in input.bytes() {
+        if byte == b'=' {
+            break;
+        }
+        let value = ALPHABET
+            .iter()
+            .position(|&a| a == byte)
+            .with_context(|| format!("invalid base64url character `{}`", byte as char))?;
+        bits = (bits << 6) | value as u32;
+        bit_count += 6;
+        if bit_count >= 8 {
+            bit_count -= 8;
+            out.push((bits >> bit_count) as u8);
+        }
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod test_oidc {
+    use insta::assert_debug_snapshot;
+    use tempdir::TempDir;
+
+    use super::*;
+    use crate::environment::MockEnvironment;
+
+    /// An unsigned JWT with the given JSON payload.
+    fn make_jwt(payload: &str) -> String {
+        fn base64url_encode(input: &[u8]) -> String {
+            const ALPHABET: &[u8] =
+                b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+            let mut out = String::new();
+            for chunk in input.chunks(3) {
+                let mut bits: u32 = 0;
+                for (i, byte) in chunk.iter().enumerate() {
+                    bits |= u32::from(*byte) << (16 - 8 * i);
+                }
+                for i in 0..=chunk.len() {
+                    out.push(ALPHABET[((bits >> (18 - 6 * i)) & 0x3f) as usize] as char);
+                }
+            }
+            out
+        }
+        format!(
+            "{}.{}.signature",
+            base64url_encode(br#"{"alg":"none"}"#),
+            base64url_encode(payload.as_bytes())
+        )
+    }
+
+    #[test]
+    fn can_decode_jwt_claims() {
+        let jwt = make_jwt(r#"{"sub":"person@example.com","roles":["dba"],"exp":4100000000}"#);
+        let claims = decode_claims(&jwt).unwrap();
+        assert_debug_snapshot!(identity_from_claims(&claims));
+        assert_debug_snapshot!(decode_claims("not-a-jwt").is_err());
+    }
+
+    #[test]
+    fn expired_identities_read_as_none() {
+        let temp_dir = TempDir::new("oidc").unwrap();
+        let path = temp_dir.path().join(IDENTITY_FILE_NAME);
+        save(
+            &path,
+            &CachedIdentity {
+                subject: "person@example.com".to_string(),
+                groups: vec![],
+                roles: vec!["dba".to_string()],
+                expires_at: 2_000,
+            },
+        )
+        .unwrap();
+
+        assert_debug_snapshot!(load(&path, 1_000).unwrap().is_some());
+        assert_debug_snapshot!(load(&path, 3_000).unwrap().is_none());
+        temp_dir.close().unwrap();
+    }
+
+    #[test]
+    fn cached_identity_honors_the_file_override() {
+        let temp_dir = TempDir::new("oidc").unwrap();
+        let path = temp_dir.path().join(IDENTITY_FILE_NAME);
+        save(
+            &path,
+            &CachedIdentity {
+                subject: "person@example.com".to_string(),
+                groups: vec!["platform".to_string()],
+                roles: vec![],
+                expires_at: u64::MAX,
+            },
+        )
+        .unwrap();
+
+        let environment = MockEnvironment::builder()
+            .env_var(IDENTITY_FILE_ENV_VAR, &path.display().to_string())
+            .build();
+        assert_debug_snapshot!(cached_identity(&environment));
+        temp_dir.close().unwrap();
+    }
+}
@@ -0,0 +1,350 @@
+//! Project level policy files (`.shellfirm.yaml`), composable through
+//! `extends` so monorepos and multi-repo orgs can share a base policy
+//! without copy-paste drift.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use serde_derive::{Deserialize, Serialize};
+
+use crate::{
+    checks::Check,
+    config::{AllowRule, Settings},
+    environment::{run_command_with_timeout, Environment},
+    guardian::{Decision, Guardian},
+    network::{self, NetworkMode},
+};
+
+/// File name of a project policy, looked up in the working directory.
+pub const POLICY_FILE_NAME: &str = ".shellfirm.yaml";
+
+/// Hard timeout for fetching one remote policy include.
+const REMOTE_POLICY_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// A project policy: extra checks and list tweaks layered on top of the user
+/// settings.
+#[derive(Debug, Default, Deserialize, Serialize, Clone)]
+pub struct Policy {
+    /// Policies this one builds on: local paths (relative to the declaring
+    /// file) or `https://` URLs. Merged depth-first in declaration order,
+    /// the declaring file last, so later sources win.
+    #[serde(default)]
+    pub extends: Vec<String>,
+    /// Extra checks added by this policy.
+    #[serde(default)]
+    pub checks: Vec<Check>,
+    /// Check ids to deny.
+    #[serde(default)]
+    pub deny_patterns_ids: Vec<String>,
+    /// Check tags to deny (e.g. `irreversible`).
+    #[serde(default)]
+    pub deny_tags: Vec<String>,
+    /// Check ids to ignore.
+    #[serde(default)]
+    pub ignores_patterns_ids: Vec<String>,
+    /// Allowlist entries: commands this project declared safe, optionally
+    /// scoped to a directory.
+    #[serde(default)]
+    pub allow: Vec<AllowRule>,
+    /// Expectations for concrete commands, runnable with
+    /// `shellfirm policy test` so guardrails can be CI'd like code.
+    #[serde(default)]
+    pub tests: Vec<PolicyTest>,
+}
+
+/// One policy test case: a command and the outcome it must get.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct PolicyTest {
+    /// The command to assess.
+    pub command: String,
+    /// The expected outcome: allow, challenge or deny.
+    pub expect: Decision,
+}
+
+/// Outcome of one policy test case.
+#[derive(Debug, Serialize, Clone)]
+pub struct PolicyTestResult {
+    /// The assessed command.
+    pub command: String,
+    /// The outcome the policy test expected.
+    pub expected: Decision,
+    /// The outcome the effective configuration produced.
+    pub actual: Decision,
+}
+
+impl PolicyTestResult {
+    /// Did the actual outcome match the expectation.
+    #[must_use]
+    pub fn passed(&self) -> bool {
+        self.expected == self.actual
+    }
+}
+
+impl Policy {
+    /// Merge another policy over this one: its checks replace same-id checks,
+    /// its list entries are appended de-duplicated.
+    fn absorb(&mut self, overlay: Self) {
+        for check in overlay.checks {
+            self.checks.retain(|existing| existing.id != check.id);
+            self.checks.push(check);
+        }
+        for id in overlay.deny_patterns_ids {
+            if !self.deny_patterns_ids.contains(&id) {
+                self.deny_patterns_ids.push(id);
+            }
+        }
+        for tag in overlay.deny_tags {
+            if !self.deny_tags.contains(&tag) {
+                self.deny_tags.push(tag);
+            }
+        }
+        for id in overlay.ignores_patterns_ids {
+            if !self.ignores_patterns_ids.contains(&id) {
+                self.ignores_patterns_ids.push(id);
+            }
+        }
+        self.allow.extend(overlay.allow);
+        self.tests.extend(overlay.tests);
+    }
+}
+
+/// Run the tests of a policy against the effective configuration: the given
+/// settings with the policy's checks and list tweaks layered on top.
+///
+/// # Errors
+///
+/// Will return `Err` when the active checks could not be loaded.
+pub fn run_tests(
+    policy: &Policy,
+    settings: &Settings,
+    environment: &dyn Environment,
+) -> Result<Vec<PolicyTestResult>> {
+    let mut settings = settings.clone();
+    for id in &policy.deny_patterns_ids {
+        if !settings.deny_patterns_ids.contains(id) {
+            settings.deny_patterns_ids.push(id.to_string());
+        }
+    }
+    for tag in &policy.deny_tags {
+        if !settings.deny_tags.contains(tag) {
+            settings.deny_tags.push(tag.to_string());
+        }
+    }
+    for id in &policy.ignores_patterns_ids {
+        if !settings.ignores_patterns_ids.contains(id) {
+            settings.ignores_patterns_ids.push(id.to_string());
+        }
+    }
+    settings.allow.extend(policy.allow.iter().cloned());
+
+    let mut checks = settings.get_active_checks()?;
+    for check in &policy.checks {
+        checks.retain(|existing| existing.id != check.id);
+        checks.push(check.clone());
+    }
+    checks.retain(|check| !settings.ignores_patterns_ids.contains(&check.id));
+
+    let guardian = Guardian::with_checks(settings, checks);
+    Ok(policy
+        .tests
+        .iter()
+        .map(|test| PolicyTestResult {
+            command: test.command.to_string(),
+            expected: test.expect.clone(),
+            actual: guardian.decide(&test.command, environment),
+        })
+        .collect())
+}
+
+/// Load a policy file and resolve its `extends` chain with cycle detection.
+/// The merge order is deterministic: depth-first, extends entries in
+/// declaration order, the declaring file merged last.
+///
+/// # Arguments
+///
+/// * `path` - policy file to load.
+/// * `network` - whether remote (`https://`) includes are allowed.
+///
+/// # Errors
+///
+/// Will return `Err` when a file could not be read or parsed, when the
+/// extends chain has a cycle, or when a remote include is used while the
+/// network is disabled.
+pub fn load(path: &Path, network: NetworkMode) -> Result<Policy> {
+    let mut visited: Vec<String> = Vec::new();
+    load_source(
+        &path.display().to_string(),
+        Path::new("."),
+        network,
+        &mut visited,
+    )
+}
+
+/// Load the policy of the given working directory, when one exists.
+///
+/// # Errors
+///
+/// Will return `Err` when the policy file exists but could not be loaded.
+pub fn load_for_dir(dir: &Path, network: NetworkMode) -> Result<Option<Policy>> {
+    let path = dir.join(POLICY_FILE_NAME);
+    if !path.exists() {
+        return Ok(None);
+    }
+    load(&path, network).map(Some)
+}
+
+/// Load one policy source (path or URL) and its extends, recursively.
+fn load_source(
+    source: &str,
+    base_dir: &Path,
+    network: NetworkMode,
+    visited: &mut Vec<String>,
+) -> Result<Policy> {
+    let (key, content, next_base_dir) = read_source(source, base_dir, network)?;
+    if visited.contains(&key) {
+        bail!(
+            "policy extends cycle detected: {} -> {}",
+            visited.join(" -> "),
+            key
+        );
+    }
+    visited.push(key);
+
+    let declared: Policy = serde_yaml::from_str(&content)
+        .with_context(|| format!("could not parse policy {source}"))?;
+
+    let mut merged = Policy::default();
+    for parent in &declared.extends {
+        let parent_policy = load_source(parent, &next_base_dir, network, visited)?;
+        merged.absorb(parent_policy);
+    }
+    merged.absorb(Policy {
+        extends: vec![],
+        ..declared
+    });
+
+    visited.pop();
+    Ok(merged)
+}
+
+/// Read one policy source, returning its cycle-detection key, its content and
+/// the base directory its own relative extends resolve against.
+fn read_source(
+    source: &str,
+    base_dir: &Path,
+    network: NetworkMode,
+) -> Result<(String, String, PathBuf)> {
+    if source.starts_with("https://") || source.starts_with("http://") {
+        network::ensure_allowed(network, "remote policy includes")?;
+        let content =
+            run_command_with_timeout(&format!("curl -fsSL {source}"), REMOTE_POLICY_TIMEOUT)
+                .with_context(|| format!("could not fetch remote policy {source}"))?;
+        return Ok((source.to_string(), content, base_dir.to_path_buf()));
+    }
+
+    let path = base_dir.join(source);
+    let key = path
+        .canonicalize()
+        .unwrap_or_else(|_| path.clone())
+        .display()
+        .to_string();
+    let content = std::fs::read_to_string(&path)
+        .with_context(|| format!("could not read policy {source}"))?;
+    let next_base_dir = path
+        .parent()
+        .map_or_else(|| PathBuf::from("."), Path::to_path_buf);
+    Ok((key, content, next_base_dir))
+}
+
+#[cfg(test)]
+mod test_policy {
+    use insta::assert_debug_snapshot;
+    use tempdir::TempDir;
+
+    use super::*;
+
+    #[test]
+    fn can_merge_extended_policies() {
+        let temp_dir = TempDir::new("policy").unwrap();
+        std::fs::write(
+            temp_dir.path().join("base.yaml"),
+            r"
+deny_patterns_ids:
+  - fs:recursively_delete
+checks:
+  - id: org:rollout_restart
+    test: kubectl rollout restart
+    description: restarts a deployment
+    from: org
+tests:
+  - command: rm -rf /
+    expect: challenge
+",
+        )
+        .unwrap();
+        std::fs::write(
+            temp_dir.path().join(POLICY_FILE_NAME),
+            r"
+extends:
+  - base.yaml
+deny_patterns_ids:
+  - git:force_push
+checks:
+  - id: org:rollout_restart
+    test: kubectl rollout restart deployment/payments
+    description: restarts the payments deployment
+    from: org
+",
+        )
+        .unwrap();
+
+        let policy = load(&temp_dir.path().join(POLICY_FILE_NAME), NetworkMode::Never).unwrap();
+        assert_debug_snapshot!(policy.deny_patterns_ids);
+        // the declaring file wins over the base for the same check id
+        assert_debug_snapshot!(policy
+            .checks
+            .iter()
+            .map(|c| (c.id.to_string(), c.test.to_string()))
+            .collect::<Vec<_>>());
+        // tests from the whole extends chain are kept
+        assert_debug_snapshot!(policy.tests.len());
+        temp_dir.close().unwrap();
+    }
+
+    #[test]
+    fn detects_extends_cycles() {
+        let temp_dir = TempDir::new("policy").unwrap();
+        std::fs::write(temp_dir.path().join("a.yaml"), "extends:\n  - b.yaml\n").unwrap();
+        std::fs::write(temp_dir.path().join("b.yaml"), "extends:\n  - a.yaml\n").unwrap();
+
+        let result = load(&temp_dir.path().join("a.yaml"), NetworkMode::Never);
+        assert_debug_snapshot!(result
+            .unwrap_err()
+            .to_string()
+            .contains("policy extends cycle detected"));
+        temp_dir.close().unwrap();
+    }
+
+    #[test]
+    fn remote_includes_honor_the_network_gate() {
+        let temp_dir = TempDir::new("policy").unwrap();
+        std::fs::write(
+            temp_dir.path().join(POLICY_FILE_NAME),
+            "extends:\n  - https://example.com/org.yaml\n",
+        )
+        .unwrap();
+
+        let result = load(&temp_dir.path().join(POLICY_FILE_NAME), NetworkMode::Never);
+        assert_debug_snapshot!(result.unwrap_err().to_string());
+        temp_dir.close().unwrap();
+    }
+
+    #[test]
+    fn missing_policy_is_not_an_error_for_directories() {
+        let temp_dir = TempDir::new("policy").unwrap();
+        assert_debug_snapshot!(load_for_dir(temp_dir.path(), NetworkMode::Never)
+            .unwrap()
+            .is_none());
+        temp_dir.close().unwrap();
+    }
+}
@@ -1,7 +1,22 @@
-use std::{io, thread, time::Duration};
+use std::{io, process::Command, thread, time::Duration};
 
 use console::style;
-use rand::Rng;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::config::{ChallengeTuning, MathOperation};
+
+/// Environment variable seeding the challenge generation, so integration
+/// tests and PTY harnesses can assert exact prompt contents.
+pub const CHALLENGE_SEED_ENV_VAR: &str = "SHELLFIRM_CHALLENGE_SEED";
+
+/// Outcome of a challenge prompt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    /// The user solved the challenge, the command can run.
+    Approved,
+    /// The user chose to run the safer alternative instead.
+    RunAlternative,
+}
 
 /// wrong answer text show when user solve the challenge incorrectly
 const WRONG_ANSWER: &str = "wrong answer, try again...";
@@ -11,29 +26,104 @@ const SOLVE_MATH_TEXT: &str = "Solve the challenge:";
 const SOLVE_ENTER_TEXT: &str = "Type `Enter` to continue";
 /// show yes challenge text
 const SOLVE_YES_TEXT: &str = "Type `yes` to continue";
+/// show word challenge text
+const SOLVE_WORD_TEXT: &str = "Type the word";
+/// show delay challenge text
+const SOLVE_DELAY_TEXT: &str = "This command is risky. Take a breath";
+/// show totp challenge text
+const SOLVE_TOTP_TEXT: &str = "Type the current code from your authenticator app to continue";
 /// show yes challenge text
 const DENIED_TEXT: &str = "The command is not allowed.";
+/// show break-glass prompt text
+const BREAK_GLASS_TEXT: &str =
+    "Type a justification to run it once anyway (recorded for review), or";
 /// show to the user how can he cancel the command
 const CANCEL_PROMPT_TEXT: &str = "^C to cancel";
+/// answer that runs the safer alternative instead of the original command
+const ALTERNATIVE_ANSWER: &str = "a";
+/// answer that expands the summarized match details
+const DETAILS_ANSWER: &str = "d";
+
+/// RNG driving challenge generation: seeded from
+/// [`CHALLENGE_SEED_ENV_VAR`] when set, random otherwise.
+fn challenge_rng() -> StdRng {
+    std::env::var(CHALLENGE_SEED_ENV_VAR)
+        .ok()
+        .and_then(|seed| seed.trim().parse().ok())
+        .map_or_else(StdRng::from_entropy, StdRng::seed_from_u64)
+}
+
+/// Generate the math challenge question and its expected answer, within the
+/// configured operand range and operations.
+fn generate_math_challenge(rng: &mut impl Rng, tuning: &ChallengeTuning) -> (String, u64) {
+    let min = tuning.math_operand_min;
+    let max = tuning.math_operand_max.max(min + 1);
+    let num_a = u64::from(rng.gen_range(min..max));
+    let num_b = u64::from(rng.gen_range(min..max));
+
+    let operation = if tuning.math_operations.is_empty() {
+        MathOperation::Add
+    } else {
+        tuning.math_operations[rng.gen_range(0..tuning.math_operations.len())]
+    };
+    match operation {
+        MathOperation::Add => (format!("{num_a} + {num_b}"), num_a + num_b),
+        MathOperation::Sub => {
+            // order the operands so the answer is never negative
+            let (hi, lo) = if num_a >= num_b {
+                (num_a, num_b)
+            } else {
+                (num_b, num_a)
+            };
+            (format!("{hi} - {lo}"), hi - lo)
+        }
+        MathOperation::Mul => (format!("{num_a} * {num_b}"), num_a * num_b),
+    }
+}
+
+/// Pick the word the user has to type: from the wordlist when it is not
+/// empty, a generated word from the configured charset otherwise, `None` when
+/// neither could produce a word.
+fn pick_word(rng: &mut impl Rng, wordlist: &[String], tuning: &ChallengeTuning) -> Option<String> {
+    if !wordlist.is_empty() {
+        return Some(wordlist[rng.gen_range(0..wordlist.len())].to_string());
+    }
+    let charset: Vec<char> = tuning.word_charset.chars().collect();
+    if charset.is_empty() || tuning.word_length == 0 {
+        return None;
+    }
+    Some(
+        (0..tuning.word_length)
+            .map(|_| charset[rng.gen_range(0..charset.len())])
+            .collect(),
+    )
+}
 
 /// Show math challenge to the user.
-pub fn math_challenge() -> bool {
-    let mut rng = rand::thread_rng();
-    let num_a = rng.gen_range(0..10);
-    let num_b = rng.gen_range(0..10);
-    let expected_answer = num_a + num_b;
+pub fn math_challenge(
+    tuning: &ChallengeTuning,
+    alternative: Option<&str>,
+    details: Option<&[String]>,
+) -> Outcome {
+    let (question, expected_answer) = generate_math_challenge(&mut challenge_rng(), tuning);
 
     eprintln!(
-        "{}: {} + {} = ? {}",
+        "{}: {} = ? {}{}",
         SOLVE_MATH_TEXT,
-        num_a,
-        num_b,
+        question,
+        get_alternative_string(alternative),
         get_cancel_string()
     );
     loop {
         let answer = show_stdin_prompt();
+        if is_alternative_answer(&answer, alternative) {
+            return Outcome::RunAlternative;
+        }
+        if show_details(&answer, details) {
+            continue;
+        }
 
-        let answer: u32 = match answer.trim().parse() {
+        let answer: u64 = match answer.trim().parse() {
             Ok(num) => num,
             Err(_) => continue,
         };
@@ -42,34 +132,204 @@ pub fn math_challenge() -> bool {
         }
         eprintln!("{WRONG_ANSWER}");
     }
-    true
+    Outcome::Approved
 }
 
 /// Show enter challenge to the user.
-pub fn enter_challenge() -> bool {
-    eprintln!("{} {}", SOLVE_ENTER_TEXT, get_cancel_string());
+pub fn enter_challenge(alternative: Option<&str>, details: Option<&[String]>) -> Outcome {
+    eprintln!(
+        "{} {}{}",
+        SOLVE_ENTER_TEXT,
+        get_alternative_string(alternative),
+        get_cancel_string()
+    );
     loop {
         let answer = show_stdin_prompt();
+        if is_alternative_answer(&answer, alternative) {
+            return Outcome::RunAlternative;
+        }
+        if show_details(&answer, details) {
+            continue;
+        }
         if answer == "\n" {
             break;
         }
         eprintln!("{WRONG_ANSWER}");
     }
-    true
+    Outcome::Approved
 }
 
 /// Show yes challenge to the user.
-pub fn yes_challenge() -> bool {
-    eprintln!("{} {}", SOLVE_YES_TEXT, get_cancel_string());
+pub fn yes_challenge(alternative: Option<&str>, details: Option<&[String]>) -> Outcome {
+    eprintln!(
+        "{} {}{}",
+        SOLVE_YES_TEXT,
+        get_alternative_string(alternative),
+        get_cancel_string()
+    );
+    loop {
+        let answer = show_stdin_prompt();
+        if is_alternative_answer(&answer, alternative) {
+            return Outcome::RunAlternative;
+        }
+        if show_details(&answer, details) {
+            continue;
+        }
+        if answer.trim() == "yes" {
+            break;
+        }
+        eprintln!("{WRONG_ANSWER}");
+    }
+    Outcome::Approved
+}
+
+/// Show word challenge to the user, falling back to the yes challenge when
+/// neither the wordlist nor the charset can produce a word.
+pub fn word_challenge(
+    wordlist: &[String],
+    tuning: &ChallengeTuning,
+    alternative: Option<&str>,
+    details: Option<&[String]>,
+) -> Outcome {
+    let Some(word) = pick_word(&mut challenge_rng(), wordlist, tuning) else {
+        return yes_challenge(alternative, details);
+    };
+    eprintln!(
+        "{} `{}` to continue {}{}",
+        SOLVE_WORD_TEXT,
+        word,
+        get_alternative_string(alternative),
+        get_cancel_string()
+    );
     loop {
-        if show_stdin_prompt().trim() == "yes" {
+        let answer = show_stdin_prompt();
+        if is_alternative_answer(&answer, alternative) {
+            return Outcome::RunAlternative;
+        }
+        if show_details(&answer, details) {
+            continue;
+        }
+        if answer.trim() == word {
             break;
         }
         eprintln!("{WRONG_ANSWER}");
     }
+    Outcome::Approved
+}
+
+/// Show delay challenge to the user: a visible countdown, then a plain
+/// enter prompt — friction without a cognitive puzzle.
+pub fn delay_challenge(
+    seconds: u64,
+    alternative: Option<&str>,
+    details: Option<&[String]>,
+) -> Outcome {
+    eprintln!("{} {}", SOLVE_DELAY_TEXT, get_cancel_string());
+    for remaining in (1..=seconds).rev() {
+        eprint!("\r  continuing in {remaining}s...  ");
+        thread::sleep(Duration::from_secs(1));
+    }
+    // wipe the countdown before the enter prompt takes over
+    eprint!("\r                        \r");
+    enter_challenge(alternative, details)
+}
+
+/// Show totp challenge to the user: the answer has to be the current code of
+/// the enrolled authenticator (see [`crate::totp`]).
+pub fn totp_challenge(
+    secret: &str,
+    alternative: Option<&str>,
+    details: Option<&[String]>,
+) -> Outcome {
+    eprintln!(
+        "{} {}{}",
+        SOLVE_TOTP_TEXT,
+        get_alternative_string(alternative),
+        get_cancel_string()
+    );
+    loop {
+        let answer = show_stdin_prompt();
+        if is_alternative_answer(&answer, alternative) {
+            return Outcome::RunAlternative;
+        }
+        if show_details(&answer, details) {
+            continue;
+        }
+        if crate::totp::verify(secret, answer.trim()) {
+            break;
+        }
+        eprintln!("{WRONG_ANSWER}");
+    }
+    Outcome::Approved
+}
+
+/// Run the safer alternative instead of the original command, then block the
+/// original command like [`deny`] (the user cancels it with ^C).
+pub fn run_alternative(alternative: &str) -> ! {
+    eprintln!("running safer alternative: {alternative}");
+    let status = Command::new("sh").arg("-c").arg(alternative).status();
+    match status {
+        Ok(status) if status.success() => {
+            eprintln!("alternative finished successfully.");
+        }
+        Ok(status) => eprintln!("alternative exited with {status}."),
+        Err(err) => eprintln!("could not run alternative: {err}"),
+    }
+    eprintln!(
+        "the original command is blocked. type {} to dismiss it",
+        get_cancel_string()
+    );
+    loop {
+        thread::sleep(Duration::from_secs(60));
+    }
+}
+
+/// Check if the user picked the safer alternative.
+fn is_alternative_answer(answer: &str, alternative: Option<&str>) -> bool {
+    alternative.is_some() && answer.trim() == ALTERNATIVE_ANSWER
+}
+
+/// Print the summarized match details when the user asked for them,
+/// returning true when the answer was the details keypress.
+fn show_details(answer: &str, details: Option<&[String]>) -> bool {
+    let Some(details) = details else {
+        return false;
+    };
+    if answer.trim() != DETAILS_ANSWER {
+        return false;
+    }
+    for line in details {
+        eprintln!("{line}");
+    }
     true
 }
 
+/// Return the `a` hint shown when a safer alternative is available.
+fn get_alternative_string(alternative: Option<&str>) -> String {
+    alternative.map_or_else(String::new, |_| {
+        format!(
+            "{} ",
+            style("type `a` to run the safer alternative instead,").italic()
+        )
+    })
+}
+
+/// Break-glass prompt for a denied command: the user types a non-empty
+/// justification and the command is allowed to run once (the caller records
+/// the justification), or dismisses the command with ^C like [`deny`].
+pub fn break_glass_challenge() -> String {
+    eprintln!("{DENIED_TEXT}");
+    eprintln!("{} {}", BREAK_GLASS_TEXT, get_cancel_string());
+    loop {
+        let answer = show_stdin_prompt();
+        let justification = answer.trim();
+        if !justification.is_empty() {
+            return justification.to_string();
+        }
+        eprintln!("the justification cannot be empty");
+    }
+}
+
 /// Deny function will loop FOREVER until the user kill the process ^C.
 /// it mean that the use command will never executed
 pub fn deny() {
@@ -93,3 +353,69 @@ fn show_stdin_prompt() -> String {
 fn get_cancel_string() -> String {
     format!("{}", style(CANCEL_PROMPT_TEXT).underlined().bold().italic())
 }
+
+#[cfg(test)]
+mod test_prompt {
+    use insta::assert_debug_snapshot;
+
+    use super::*;
+
+    #[test]
+    fn challenge_generation_is_deterministic_with_a_seed() {
+        let tuning = ChallengeTuning::default();
+        let mut rng = StdRng::seed_from_u64(7);
+        assert_debug_snapshot!(generate_math_challenge(&mut rng, &tuning));
+        assert_debug_snapshot!(generate_math_challenge(&mut rng, &tuning));
+        // the same seed generates the same challenge again
+        assert_debug_snapshot!(generate_math_challenge(
+            &mut StdRng::seed_from_u64(7),
+            &tuning
+        ));
+    }
+
+    #[test]
+    fn math_challenge_honors_the_tuning() {
+        let tuning = ChallengeTuning {
+            math_operand_min: 100,
+            math_operand_max: 1000,
+            math_operations: vec![MathOperation::Sub, MathOperation::Mul],
+            ..ChallengeTuning::default()
+        };
+        let mut rng = StdRng::seed_from_u64(7);
+        assert_debug_snapshot!(generate_math_challenge(&mut rng, &tuning));
+        assert_debug_snapshot!(generate_math_challenge(&mut rng, &tuning));
+    }
+
+    #[test]
+    fn can_pick_a_word_from_the_wordlist() {
+        let wordlist: Vec<String> = ["confirm", "proceed", "approve"]
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        let mut rng = StdRng::seed_from_u64(7);
+        assert_debug_snapshot!(pick_word(&mut rng, &wordlist, &ChallengeTuning::default()));
+    }
+
+    #[test]
+    fn generates_a_word_when_the_wordlist_is_empty() {
+        let mut rng = StdRng::seed_from_u64(7);
+        assert_debug_snapshot!(pick_word(&mut rng, &[], &ChallengeTuning::default()));
+        assert_debug_snapshot!(pick_word(
+            &mut rng,
+            &[],
+            &ChallengeTuning {
+                word_length: 8,
+                word_charset: "אבגדהוזח".to_string(),
+                ..ChallengeTuning::default()
+            }
+        ));
+        assert_debug_snapshot!(pick_word(
+            &mut rng,
+            &[],
+            &ChallengeTuning {
+                word_charset: String::new(),
+                ..ChallengeTuning::default()
+            }
+        ));
+    }
+}
@@ -0,0 +1,355 @@
+//! JSON Schema documents for the hand-written config files
+//! (`settings.yaml` and `.shellfirm.yaml`), printed by
+//! `shellfirm config schema` so editors with yaml-language-server support
+//! get autocompletion and validation.
+
+use serde_json::{json, Value};
+
+/// The JSON Schema of the settings file.
+#[must_use]
+pub fn settings_schema() -> Value {
+    json!({
+        "$schema": "http://json-schema.org/draft-07/schema#",
+        "title": "shellfirm settings",
+        "type": "object",
+        "additionalProperties": false,
+        "properties": {
+            "challenge": challenge_schema(),
+            "includes": string_list("Check groups to enable (file names in the checks folder)."),
+            "ignores_patterns_ids": string_list("Check ids to disable."),
+            "deny_patterns_ids": string_list("Check ids to deny outright."),
+            "deny_tags": string_list("Check tags to deny outright (e.g. irreversible)."),
+            "tag_escalation": {
+                "type": "object",
+                "description": "Escalate the challenge for commands whose matched checks carry the tag.",
+                "additionalProperties": challenge_schema(),
+            },
+            "max_subprocess_latency_ms": {
+                "type": "integer",
+                "description": "Max added latency (milliseconds) a single check subprocess may spend.",
+            },
+            "network": {
+                "type": "string",
+                "enum": ["allow", "never"],
+                "description": "Whether network features (remote policy, audit collector) are allowed.",
+            },
+            "checks_bundle_hash": {
+                "type": ["string", "null"],
+                "description": "Content hash of the checks bundle recorded when the file was written.",
+            },
+            "allow": {
+                "type": "array",
+                "description": "Commands that never challenge, optionally scoped to a directory.",
+                "items": allow_rule_schema(),
+            },
+            "deny_rules": {
+                "type": "array",
+                "description": "Conditional deny rules, denying a check only when the condition holds.",
+                "items": deny_rule_schema(),
+            },
+            "save_last_command": { "type": "boolean" },
+            "copy_blocked_command_to_clipboard": { "type": "boolean" },
+            "challenge_wordlist": string_list("Words the Word challenge picks from."),
+            "challenge_tuning": challenge_tuning_schema(),
+            "totp_secret": {
+                "type": ["string", "null"],
+                "description": "Base32 secret of the Totp challenge, written by `shellfirm totp enroll`.",
+            },
+            "kubernetes_namespaces": {
+                "type": "object",
+                "additionalProperties": false,
+                "description": "Namespace allow/deny lists of the kubernetes checks.",
+                "properties": {
+                    "allow": string_list("Namespaces where kubernetes checks never challenge, `*` wildcards supported."),
+                    "deny": string_list("Namespaces where kubernetes checks are always denied, `*` wildcards supported."),
+                },
+            },
+            "aliases": {
+                "type": "object",
+                "description": "Shell aliases to expand before the checks run, imported with `shellfirm alias import`.",
+                "additionalProperties": { "type": "string" },
+            },
+            "summarize_matches_above": {
+                "type": "integer",
+                "description": "Summarize the matched checks by group when more than this many match.",
+            },
+            "escalate_on_unknown_impact": {
+                "type": ["string", "null"],
+                "enum": [null, "Math", "Enter", "Yes", "Word", "Totp", "Delay"],
+                "description": "Challenge to escalate to when the blast radius could not be computed.",
+            },
+            "escalate_if": {
+                "type": "object",
+                "additionalProperties": false,
+                "description": "Blast radius thresholds that bump the challenge.",
+                "properties": {
+                    "files": { "type": ["string", "null"], "description": "Counted files threshold, e.g. `\">10000\"`." },
+                    "size": { "type": ["string", "null"], "description": "Measured size threshold, e.g. `\">50GB\"`." },
+                    "commits": { "type": ["string", "null"], "description": "Counted commits threshold, e.g. `\">100\"`." },
+                    "challenge": { "type": ["string", "null"], "enum": [null, "Math", "Enter", "Yes", "Word", "Totp", "Delay"] },
+                },
+            },
+            "min_severity": {
+                "type": ["string", "null"],
+                "enum": [null, "Low", "Medium", "High", "Critical"],
+                "description": "Only intercept checks with at least this severity.",
+            },
+            "context_severity_floor": {
+                "type": "object",
+                "description": "Severity floor per context label (e.g. `k8s=prod-*: High`).",
+                "additionalProperties": severity_schema(),
+            },
+            "audit": {
+                "type": "object",
+                "additionalProperties": false,
+                "properties": {
+                    "remote": {
+                        "type": ["object", "null"],
+                        "additionalProperties": false,
+                        "required": ["url"],
+                        "properties": {
+                            "url": { "type": "string" },
+                            "token_env": { "type": ["string", "null"] },
+                            "batch_size": { "type": "integer" },
+                            "max_retries": { "type": "integer" },
+                        },
+                    },
+                },
+            },
+            "oidc": {
+                "type": ["object", "null"],
+                "additionalProperties": false,
+                "required": ["device_endpoint", "token_endpoint", "client_id"],
+                "properties": {
+                    "device_endpoint": { "type": "string" },
+                    "token_endpoint": { "type": "string" },
+                    "client_id": { "type": "string" },
+                },
+            },
+            "break_glass": {
+                "type": "object",
+                "additionalProperties": false,
+                "properties": {
+                    "allowed": { "type": "boolean" },
+                    "requires": { "type": "string", "enum": ["justification"] },
+                    "webhook": { "type": ["string", "null"] },
+                },
+            },
+            "url_reputation": {
+                "type": "object",
+                "additionalProperties": false,
+                "properties": {
+                    "allow": string_list("Hosts the organization trusts, `*` wildcards supported."),
+                    "deny": string_list("Hosts the organization flags, `*` wildcards supported."),
+                },
+            },
+        },
+    })
+}
+
+/// The JSON Schema of a project policy file (`.shellfirm.yaml`).
+#[must_use]
+pub fn policy_schema() -> Value {
+    json!({
+        "$schema": "http://json-schema.org/draft-07/schema#",
+        "title": "shellfirm project policy",
+        "type": "object",
+        "additionalProperties": false,
+        "properties": {
+            "extends": string_list("Policies this one builds on: paths or https:// URLs."),
+            "checks": {
+                "type": "array",
+                "description": "Extra checks added by this policy.",
+                "items": check_schema(),
+            },
+            "deny_patterns_ids": string_list("Check ids to deny."),
+            "deny_tags": string_list("Check tags to deny."),
+            "ignores_patterns_ids": string_list("Check ids to ignore."),
+            "allow": {
+                "type": "array",
+                "description": "Commands this project declared safe, optionally scoped to a directory.",
+                "items": allow_rule_schema(),
+            },
+            "tests": {
+                "type": "array",
+                "description": "Expectations for concrete commands, runnable with `shellfirm policy test`.",
+                "items": {
+                    "type": "object",
+                    "additionalProperties": false,
+                    "required": ["command", "expect"],
+                    "properties": {
+                        "command": { "type": "string" },
+                        "expect": {
+                            "type": "string",
+                            "enum": ["allow", "challenge", "deny"],
+                        },
+                    },
+                },
+            },
+        },
+    })
+}
+
+/// The JSON Schema of a single check, as written in policies and custom
+/// check files.
+fn check_schema() -> Value {
+    json!({
+        "type": "object",
+        "additionalProperties": false,
+        "required": ["id", "test", "description", "from"],
+        "properties": {
+            "id": { "type": "string" },
+            "test": { "type": "string", "description": "Regex the command is tested against." },
+            "description": { "type": "string" },
+            "from": { "type": "string", "description": "Group of the check." },
+            "challenge": challenge_schema(),
+            "filters": {
+                "type": "object",
+                "additionalProperties": false,
+                "properties": {
+                    "IsExists": { "type": "string" },
+                    "NotContains": { "type": "string" },
+                },
+            },
+            "severity": severity_schema(),
+            "alternative": { "type": ["string", "null"] },
+            "alternatives": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "additionalProperties": false,
+                    "required": ["template"],
+                    "properties": {
+                        "template": { "type": "string" },
+                        "os": string_list("Operating systems the alternative applies to."),
+                        "install_hint": { "type": ["string", "null"] },
+                    },
+                },
+            },
+            "explanation": { "type": ["string", "null"] },
+            "docs_url": { "type": ["string", "null"] },
+            "examples": string_list("Example commands evaluated by `shellfirm explain`."),
+            "tags": string_list("Free-form labels and compliance mappings."),
+            "os": string_list(
+                "Operating systems the check applies to (macos / linux / windows). Empty means \
+                 every platform."
+            ),
+        },
+    })
+}
+
+/// Schema fragment of one allowlist entry.
+fn allow_rule_schema() -> Value {
+    json!({
+        "type": "object",
+        "additionalProperties": false,
+        "required": ["pattern"],
+        "properties": {
+            "pattern": {
+                "type": "string",
+                "description": "Regex the whole command segment must match.",
+            },
+            "directory": {
+                "type": ["string", "null"],
+                "description": "Only apply inside this directory and below (`~` is expanded).",
+            },
+        },
+    })
+}
+
+/// Schema fragment of a list of strings with the given description.
+fn string_list(description: &str) -> Value {
+    json!({
+        "type": "array",
+        "items": { "type": "string" },
+        "description": description,
+    })
+}
+
+/// Schema fragment of the challenge enum.
+/// Schema of one conditional deny rule.
+fn deny_rule_schema() -> Value {
+    json!({
+        "type": "object",
+        "additionalProperties": false,
+        "required": ["id"],
+        "properties": {
+            "id": { "type": "string" },
+            "when": {
+                "type": ["object", "null"],
+                "additionalProperties": false,
+                "properties": {
+                    "k8s_context": { "type": ["string", "null"] },
+                    "identity": { "type": ["string", "null"] },
+                    "unless_role": { "type": ["string", "null"] },
+                    "source": { "type": ["string", "null"] },
+                },
+            },
+        },
+    })
+}
+
+/// Schema of the `challenge_tuning` settings object.
+fn challenge_tuning_schema() -> Value {
+    json!({
+        "type": "object",
+        "additionalProperties": false,
+        "properties": {
+            "math_operand_min": { "type": "integer" },
+            "math_operand_max": { "type": "integer" },
+            "math_operations": string_list("Operations of the math challenge (Add/Sub/Mul)."),
+            "word_length": { "type": "integer" },
+            "word_charset": { "type": "string" },
+            "delay_seconds": { "type": "integer" },
+            "delay_seconds_by_severity": {
+                "type": "object",
+                "description": "Per-severity overrides of delay_seconds (e.g. Critical: 15).",
+                "additionalProperties": { "type": "integer" },
+            },
+        },
+    })
+}
+
+fn challenge_schema() -> Value {
+    json!({ "type": "string", "enum": ["Math", "Enter", "Yes", "Word", "Totp", "Delay"] })
+}
+
+/// Schema fragment of the severity enum.
+fn severity_schema() -> Value {
+    json!({ "type": "string", "enum": ["Low", "Medium", "High", "Critical"] })
+}
+
+#[cfg(test)]
+mod test_schema {
+    use insta::assert_debug_snapshot;
+
+    use super::*;
+
+    /// The schema keys must track the keys the settings loader accepts, so
+    /// editor validation and the typo detection never disagree.
+    #[test]
+    fn settings_schema_covers_every_settings_key() {
+        let schema = settings_schema();
+        let mut properties: Vec<&str> = schema["properties"]
+            .as_object()
+            .unwrap()
+            .keys()
+            .map(String::as_str)
+            .collect();
+        properties.sort_unstable();
+        let mut expected: Vec<&str> = crate::config::SETTINGS_KEYS.to_vec();
+        expected.sort_unstable();
+        assert_eq!(properties, expected);
+    }
+
+    #[test]
+    fn can_export_policy_schema() {
+        let schema = policy_schema();
+        assert_debug_snapshot!(schema["properties"]
+            .as_object()
+            .unwrap()
+            .keys()
+            .collect::<Vec<_>>());
+        assert_debug_snapshot!(schema["additionalProperties"]);
+    }
+}
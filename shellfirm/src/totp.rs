@@ -0,0 +1,205 @@
+//! Time-based one-time passwords (RFC 6238) backing the `Totp` challenge.
+//!
+//! The secret is generated by `shellfirm totp enroll`, stored in the settings
+//! file in the config dir, and shared with an authenticator app as a base32
+//! string / `otpauth://` URL. Codes are computed with HMAC-SHA256 (the crate
+//! already ships sha2), so the enrollment URL pins `algorithm=SHA256`.
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use rand::Rng;
+use sha2::{Digest, Sha256};
+
+/// RFC 4648 base32 alphabet, the encoding authenticator apps expect.
+const BASE32_ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+/// Number of digits of a code.
+pub const TOTP_DIGITS: usize = 6;
+
+/// Seconds a code is valid for.
+pub const TOTP_STEP_SECONDS: u64 = 30;
+
+/// Secret length in bytes before base32 encoding.
+const SECRET_BYTES: usize = 20;
+
+/// Accept codes from the adjacent time steps too, absorbing clock skew and
+/// the time it takes to type the code.
+const VERIFY_WINDOW_STEPS: u64 = 1;
+
+/// Generate a new base32-encoded secret.
+#[must_use]
+pub fn generate_secret() -> String {
+    let mut bytes = [0_u8; SECRET_BYTES];
+    rand::thread_rng().fill(&mut bytes);
+    encode_base32(&bytes)
+}
+
+/// The `otpauth://` URL an authenticator app enrolls from.
+///
+/// # Arguments
+///
+/// * `secret` - the base32-encoded secret.
+#[must_use]
+pub fn otpauth_url(secret: &str) -> String {
+    format!(
+        "otpauth://totp/shellfirm?secret={secret}&algorithm=SHA256&digits={TOTP_DIGITS}&period={TOTP_STEP_SECONDS}"
+    )
+}
+
+/// Check a code against the secret at the given unix time, accepting the
+/// adjacent time steps to absorb clock skew. `false` when the secret is not
+/// valid base32.
+///
+/// # Arguments
+///
+/// * `secret` - the base32-encoded secret.
+/// * `code` - the code the user typed.
+/// * `unix_time` - seconds since the unix epoch.
+#[must_use]
+pub fn verify_at(secret: &str, code: &str, unix_time: u64) -> bool {
+    let Some(secret) = decode_base32(secret) else {
+        return false;
+    };
+    let current_step = unix_time / TOTP_STEP_SECONDS;
+    (current_step.saturating_sub(VERIFY_WINDOW_STEPS)..=current_step + VERIFY_WINDOW_STEPS)
+        .any(|step| code_at(&secret, step) == code)
+}
+
+/// Check a code against the secret at the current time.
+///
+/// # Arguments
+///
+/// * `secret` - the base32-encoded secret.
+/// * `code` - the code the user typed.
+#[must_use]
+pub fn verify(secret: &str, code: &str) -> bool {
+    let unix_time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default();
+    verify_at(secret, code, unix_time)
+}
+
+/// Compute the code of the given time step (RFC 4226 dynamic truncation over
+/// HMAC-SHA256).
+///
+/// # Arguments
+///
+/// * `secret` - the decoded secret bytes.
+/// * `time_step` - unix time divided by [`TOTP_STEP_SECONDS`].
+#[must_use]
+pub fn code_at(secret: &[u8], time_step: u64) -> String {
+    let mac = hmac_sha256(secret, &time_step.to_be_bytes());
+    let offset = (mac[mac.len() - 1] & 0x0f) as usize;
+    let binary = (u32::from(mac[offset] & 0x7f) << 24)
+        | (u32::from(mac[offset + 1]) << 16)
+        | (u32::from(mac[offset + 2]) << 8)
+        | u32::from(mac[offset + 3]);
+    format!(
+        "{:0width$}",
+        binary % 10_u32.pow(TOTP_DIGITS as u32),
+        width = TOTP_DIGITS
+    )
+}
+
+/// HMAC-SHA256 (RFC 2104) over the already shipped sha2.
+fn hmac_sha256(key: &[u8], message: &[u8]) -> Vec<u8> {
+    const BLOCK_SIZE: usize = 64;
+    let mut block_key = [0_u8; BLOCK_SIZE];
+    if key.len() > BLOCK_SIZE {
+        block_key[..32].copy_from_slice(&Sha256::digest(key));
+    } else {
+        block_key[..key.len()].copy_from_slice(key);
+    }
+
+    let mut inner = Sha256::new();
+    inner.update(block_key.map(|byte| byte ^ 0x36));
+    inner.update(message);
+
+    let mut outer = Sha256::new();
+    outer.update(block_key.map(|byte| byte ^ 0x5c));
+    outer.update(inner.finalize());
+    outer.finalize().to_vec()
+}
+
+/// Encode bytes as unpadded base32.
+#[must_use]
+pub fn encode_base32(bytes: &[u8]) -> String {
+    let mut encoded = String::new();
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+    for byte in bytes {
+        buffer = (buffer << 8) | u32::from(*byte);
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            encoded.push(BASE32_ALPHABET[((buffer >> bits) & 0x1f) as usize] as char);
+        }
+    }
+    if bits > 0 {
+        encoded.push(BASE32_ALPHABET[((buffer << (5 - bits)) & 0x1f) as usize] as char);
+    }
+    encoded
+}
+
+/// Decode a base32 string, case-insensitive and ignoring `=` padding. `None`
+/// on any other character.
+#[must_use]
+pub fn decode_base32(secret: &str) -> Option<Vec<u8>> {
+    let mut decoded: Vec<u8> = Vec::new();
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+    for char in secret.trim_end_matches('=').to_uppercase().bytes() {
+        let value = BASE32_ALPHABET.iter().position(|entry| *entry == char)?;
+        buffer = (buffer << 5) | value as u32;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            decoded.push(((buffer >> bits) & 0xff) as u8);
+        }
+    }
+    Some(decoded)
+}
+
+#[cfg(test)]
+mod test_totp {
+    use insta::assert_debug_snapshot;
+
+    use super::*;
+
+    /// The RFC 6238 appendix B secret of the SHA256 test vectors.
+    const RFC_SECRET: &[u8] = b"12345678901234567890123456789012";
+
+    #[test]
+    fn can_encode_and_decode_base32() {
+        assert_debug_snapshot!(encode_base32(b"hello world"));
+        assert_debug_snapshot!(decode_base32("NBSWY3DPEB3W64TMMQ"));
+        assert_debug_snapshot!(decode_base32("nbswy3dpeb3w64tmmq======"));
+        assert_debug_snapshot!(decode_base32("not base32!"));
+    }
+
+    #[test]
+    fn computes_the_rfc_6238_sha256_test_vectors() {
+        // unix time 59 / 1111111109 / 20000000000, appendix B of the RFC
+        // (the last 6 of its 8 digits)
+        assert_debug_snapshot!(code_at(RFC_SECRET, 59 / TOTP_STEP_SECONDS));
+        assert_debug_snapshot!(code_at(RFC_SECRET, 1_111_111_109 / TOTP_STEP_SECONDS));
+        assert_debug_snapshot!(code_at(RFC_SECRET, 20_000_000_000 / TOTP_STEP_SECONDS));
+    }
+
+    #[test]
+    fn can_verify_within_the_window() {
+        let secret = encode_base32(RFC_SECRET);
+        let code = code_at(RFC_SECRET, 1_111_111_109 / TOTP_STEP_SECONDS);
+        assert!(verify_at(&secret, &code, 1_111_111_109));
+        // one step of clock skew on either side is absorbed
+        assert!(verify_at(&secret, &code, 1_111_111_109 - TOTP_STEP_SECONDS));
+        assert!(verify_at(&secret, &code, 1_111_111_109 + TOTP_STEP_SECONDS));
+        assert!(!verify_at(
+            &secret,
+            &code,
+            1_111_111_109 + 2 * TOTP_STEP_SECONDS
+        ));
+        assert!(!verify_at(&secret, "000000", 1_111_111_109));
+        assert!(!verify_at("not base32!", &code, 1_111_111_109));
+    }
+}
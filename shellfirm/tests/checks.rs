@@ -12,6 +12,7 @@ struct TestSensitivePatterns {
 }
 
 #[derive(Debug, Deserialize, Clone)]
+#[allow(dead_code)]
 struct TestSensitivePatternsResult {
     pub file_path: String,
     pub test: String,
@@ -32,7 +33,7 @@ fn test_checks() {
         let file_name = file.file_name().unwrap().to_str().unwrap().to_string();
         let mut test_file_results: Vec<TestSensitivePatternsResult> = Vec::new();
         let tests: Vec<TestSensitivePatterns> =
-            serde_yaml::from_reader(std::fs::File::open(&file.display().to_string()).unwrap())
+            serde_yaml::from_reader(std::fs::File::open(file.display().to_string()).unwrap())
                 .unwrap();
 
         for test in tests {
@@ -0,0 +1,131 @@
+//! End-to-end tests that load the shell plugins inside a real shell running
+//! in a PTY, type a risky command and verify that shellfirm intercept it.
+//!
+//! Shells that are not installed on the machine are skipped, so the suite
+//! could run on minimal CI images that only ship bash.
+
+use std::{process::Command, time::Duration};
+
+use expectrl::{session::OsSession, Expect, Regex};
+use tempdir::TempDir;
+
+const EXPECT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Return true when the given shell binary is available on this machine.
+fn shell_exists(shell: &str) -> bool {
+    Command::new("which")
+        .arg(shell)
+        .output()
+        .map(|o| o.status.success())
+        .unwrap_or(false)
+}
+
+/// Spawn the given shell in a PTY with a fresh config folder and with the
+/// compiled shellfirm binary first in the PATH.
+fn spawn_shell(shell: &str, home: &TempDir) -> OsSession {
+    let shellfirm_bin = std::path::PathBuf::from(env!("CARGO_BIN_EXE_shellfirm"));
+    let bin_folder = shellfirm_bin.parent().unwrap();
+    let path = format!(
+        "{}:{}",
+        bin_folder.display(),
+        std::env::var("PATH").unwrap()
+    );
+
+    std::fs::create_dir_all(home.path().join(".config")).unwrap();
+
+    let mut command = Command::new(shell);
+    command
+        .env("PATH", path)
+        .env("HOME", home.path())
+        .env("XDG_CONFIG_HOME", home.path().join(".config"))
+        .env("TERM", "dumb");
+
+    let mut session = OsSession::spawn(command).unwrap();
+    session.set_expect_timeout(Some(EXPECT_TIMEOUT));
+    session
+}
+
+/// Expect the challenge screen, solve the math challenge and wait for the
+/// given marker to show that the shell got the control back.
+fn solve_math_challenge(session: &mut OsSession) {
+    let captures = session
+        .expect(Regex(r"Solve the challenge.*?(\d+) \+ (\d+)"))
+        .unwrap();
+    let get_number = |idx| {
+        String::from_utf8_lossy(captures.get(idx).unwrap())
+            .parse::<u32>()
+            .unwrap()
+    };
+    let answer = get_number(1) + get_number(2);
+    session.send_line(answer.to_string()).unwrap();
+}
+
+#[test]
+fn bash_hook_intercepts_risky_command() {
+    if !shell_exists("bash") {
+        eprintln!("bash is not installed, skipping");
+        return;
+    }
+
+    let home = TempDir::new("shellfirm-e2e").unwrap();
+    let mut session = spawn_shell("bash", &home);
+
+    session
+        .send_line("source ../shell-plugins/shellfirm.plugin.sh")
+        .unwrap();
+    // bash only calls `preexec` when bash-preexec is installed, so call the
+    // plugin hook directly the way bash-preexec would.
+    session
+        .send_line("preexec 'rm -rf /'; echo \"hook=$?\"")
+        .unwrap();
+    session.expect("RISKY COMMAND FOUND").unwrap();
+
+    solve_math_challenge(&mut session);
+    session.expect("hook=0").unwrap();
+
+    session
+        .send_line("preexec 'echo all good'; echo \"hook=$?\"")
+        .unwrap();
+    session.expect("hook=0").unwrap();
+}
+
+#[test]
+fn zsh_hook_intercepts_risky_command() {
+    if !shell_exists("zsh") {
+        eprintln!("zsh is not installed, skipping");
+        return;
+    }
+
+    let home = TempDir::new("shellfirm-e2e").unwrap();
+    let mut session = spawn_shell("zsh", &home);
+
+    session
+        .send_line("source ../shell-plugins/shellfirm.plugin.oh-my-zsh.zsh")
+        .unwrap();
+    session
+        .send_line("shellfirm-pre-command 'rm -rf /'; echo \"hook=$?\"")
+        .unwrap();
+    session.expect("RISKY COMMAND FOUND").unwrap();
+
+    solve_math_challenge(&mut session);
+    session.expect("hook=0").unwrap();
+}
+
+#[test]
+fn fish_runs_pre_command_check() {
+    if !shell_exists("fish") {
+        eprintln!("fish is not installed, skipping");
+        return;
+    }
+
+    let home = TempDir::new("shellfirm-e2e").unwrap();
+    let mut session = spawn_shell("fish", &home);
+
+    session
+        .send_line("shellfirm pre-command --command 'rm -rf /'; echo \"hook=$status\"")
+        .unwrap();
+    session.expect("RISKY COMMAND FOUND").unwrap();
+
+    solve_math_challenge(&mut session);
+    session.expect("hook=0").unwrap();
+}